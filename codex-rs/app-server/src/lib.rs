@@ -1,7 +1,7 @@
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
 use codex_common::CliConfigOverrides;
-use codex_core::config::Config;
+use codex_core::config::ConfigBuilder;
 use std::io::ErrorKind;
 use std::io::Result as IoResult;
 use std::path::PathBuf;
@@ -72,17 +72,29 @@ pub async fn run_main(
 
     // Parse CLI overrides once and derive the base Config eagerly so later
     // components do not need to work with raw TOML values.
-    let cli_kv_overrides = cli_config_overrides.parse_overrides().map_err(|e| {
+    let parse_error = |e: String| {
         std::io::Error::new(
             ErrorKind::InvalidInput,
             format!("error parsing -c overrides: {e}"),
         )
-    })?;
-    let config = Config::load_with_cli_overrides(cli_kv_overrides.clone())
+    };
+    let cli_kv_overrides = cli_config_overrides
+        .parse_overrides()
+        .map_err(parse_error)?;
+    let loader_overrides = cli_config_overrides
+        .loader_overrides()
+        .map_err(parse_error)?;
+    let config = ConfigBuilder::default()
+        .cli_overrides(cli_kv_overrides.clone())
+        .loader_overrides(loader_overrides.clone())
+        .build()
         .await
         .map_err(|e| {
             std::io::Error::new(ErrorKind::InvalidData, format!("error loading config: {e}"))
         })?;
+    cli_config_overrides
+        .write_config_dump(&config)
+        .map_err(std::io::Error::other)?;
 
     let feedback = CodexFeedback::new();
 
@@ -125,6 +137,7 @@ pub async fn run_main(
             codex_linux_sandbox_exe,
             std::sync::Arc::new(config),
             cli_overrides,
+            loader_overrides,
             feedback.clone(),
         );
         async move {
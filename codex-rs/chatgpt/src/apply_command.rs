@@ -2,7 +2,6 @@ use std::path::PathBuf;
 
 use clap::Parser;
 use codex_common::CliConfigOverrides;
-use codex_core::config::Config;
 
 use crate::chatgpt_token::init_chatgpt_token_from_auth;
 use crate::get_task::GetTaskResponse;
@@ -22,13 +21,12 @@ pub async fn run_apply_command(
     apply_cli: ApplyCommand,
     cwd: Option<PathBuf>,
 ) -> anyhow::Result<()> {
-    let config = Config::load_with_cli_overrides(
-        apply_cli
-            .config_overrides
-            .parse_overrides()
-            .map_err(anyhow::Error::msg)?,
-    )
-    .await?;
+    let config = apply_cli
+        .config_overrides
+        .config_builder()
+        .map_err(anyhow::Error::msg)?
+        .build()
+        .await?;
 
     init_chatgpt_token_from_auth(&config.codex_home, config.cli_auth_credentials_store_mode)
         .await?;
@@ -6,7 +6,6 @@ mod seatbelt;
 use std::path::PathBuf;
 
 use codex_common::CliConfigOverrides;
-use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use codex_core::exec_env::create_env;
 use codex_core::landlock::spawn_command_under_linux_sandbox;
@@ -109,17 +108,16 @@ async fn run_command_under_sandbox(
     log_denials: bool,
 ) -> anyhow::Result<()> {
     let sandbox_mode = create_sandbox_mode(full_auto);
-    let config = Config::load_with_cli_overrides_and_harness_overrides(
-        config_overrides
-            .parse_overrides()
-            .map_err(anyhow::Error::msg)?,
-        ConfigOverrides {
+    let config = config_overrides
+        .config_builder()
+        .map_err(anyhow::Error::msg)?
+        .harness_overrides(ConfigOverrides {
             sandbox_mode: Some(sandbox_mode),
             codex_linux_sandbox_exe,
             ..Default::default()
-        },
-    )
-    .await?;
+        })
+        .build()
+        .await?;
 
     // In practice, this should be `std::env::current_dir()` because this CLI
     // does not support `--cwd`, but let's use the config value for consistency.
@@ -201,7 +201,7 @@ pub async fn run_logout(cli_config_overrides: CliConfigOverrides) -> ! {
 }
 
 async fn load_config_or_exit(cli_config_overrides: CliConfigOverrides) -> Config {
-    let cli_overrides = match cli_config_overrides.parse_overrides() {
+    let config_builder = match cli_config_overrides.config_builder() {
         Ok(v) => v,
         Err(e) => {
             eprintln!("Error parsing -c overrides: {e}");
@@ -209,7 +209,7 @@ async fn load_config_or_exit(cli_config_overrides: CliConfigOverrides) -> Config
         }
     };
 
-    match Config::load_with_cli_overrides(cli_overrides).await {
+    match config_builder.build().await {
         Ok(config) => config,
         Err(e) => {
             eprintln!("Error loading configuration: {e}");
@@ -36,7 +36,7 @@ mod wsl_paths;
 
 use crate::mcp_cmd::McpCli;
 
-use codex_core::config::Config;
+use codex_core::config::ConfigBuilder;
 use codex_core::config::ConfigOverrides;
 use codex_core::config::find_codex_home;
 use codex_core::config::load_config_as_toml_with_cli_overrides;
@@ -606,11 +606,16 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
                     ..Default::default()
                 };
 
-                let config = Config::load_with_cli_overrides_and_harness_overrides(
-                    cli_kv_overrides,
-                    overrides,
-                )
-                .await?;
+                let config = ConfigBuilder::default()
+                    .cli_overrides(cli_kv_overrides)
+                    .harness_overrides(overrides)
+                    .loader_overrides(
+                        root_config_overrides
+                            .loader_overrides()
+                            .map_err(anyhow::Error::msg)?,
+                    )
+                    .build()
+                    .await?;
                 for def in codex_core::features::FEATURES.iter() {
                     let name = def.key;
                     let stage = stage_str(def.stage);
@@ -681,9 +686,9 @@ async fn resolve_git_branch_override(cli: &mut TuiCli) -> std::io::Result<()> {
 /// TUI frontend to launch. The full configuration is still loaded later by the interactive TUI.
 async fn is_tui2_enabled(cli: &TuiCli) -> std::io::Result<bool> {
     let overrides_cli = cli.config_overrides.clone();
-    let cli_kv_overrides = overrides_cli
-        .parse_overrides()
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let invalid_input = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, e);
+    let cli_kv_overrides = overrides_cli.parse_overrides().map_err(invalid_input)?;
+    let loader_overrides = overrides_cli.loader_overrides().map_err(invalid_input)?;
 
     let codex_home = find_codex_home()?;
     let cwd = cli.cwd.clone();
@@ -691,8 +696,13 @@ async fn is_tui2_enabled(cli: &TuiCli) -> std::io::Result<bool> {
         Some(path) => AbsolutePathBuf::from_absolute_path(path)?,
         None => AbsolutePathBuf::current_dir()?,
     };
-    let config_toml =
-        load_config_as_toml_with_cli_overrides(&codex_home, &config_cwd, cli_kv_overrides).await?;
+    let config_toml = load_config_as_toml_with_cli_overrides(
+        &codex_home,
+        &config_cwd,
+        cli_kv_overrides,
+        loader_overrides,
+    )
+    .await?;
     let config_profile = config_toml.get_config_profile(cli.config_profile.clone())?;
     let overrides = FeatureOverrides::default();
     let features = Features::from_config(&config_toml, &config_profile, overrides);
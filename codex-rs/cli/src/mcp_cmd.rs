@@ -7,7 +7,6 @@ use anyhow::bail;
 use clap::ArgGroup;
 use codex_common::CliConfigOverrides;
 use codex_common::format_env_display::format_env_display;
-use codex_core::config::Config;
 use codex_core::config::edit::ConfigEditsBuilder;
 use codex_core::config::find_codex_home;
 use codex_core::config::load_global_mcp_servers;
@@ -180,10 +179,10 @@ impl McpCli {
 
 async fn run_add(config_overrides: &CliConfigOverrides, add_args: AddArgs) -> Result<()> {
     // Validate any provided overrides even though they are not currently applied.
-    let overrides = config_overrides
-        .parse_overrides()
-        .map_err(anyhow::Error::msg)?;
-    let config = Config::load_with_cli_overrides(overrides)
+    let config = config_overrides
+        .config_builder()
+        .map_err(anyhow::Error::msg)?
+        .build()
         .await
         .context("failed to load configuration")?;
 
@@ -322,10 +321,10 @@ async fn run_remove(config_overrides: &CliConfigOverrides, remove_args: RemoveAr
 }
 
 async fn run_login(config_overrides: &CliConfigOverrides, login_args: LoginArgs) -> Result<()> {
-    let overrides = config_overrides
-        .parse_overrides()
-        .map_err(anyhow::Error::msg)?;
-    let config = Config::load_with_cli_overrides(overrides)
+    let config = config_overrides
+        .config_builder()
+        .map_err(anyhow::Error::msg)?
+        .build()
         .await
         .context("failed to load configuration")?;
 
@@ -359,10 +358,10 @@ async fn run_login(config_overrides: &CliConfigOverrides, login_args: LoginArgs)
 }
 
 async fn run_logout(config_overrides: &CliConfigOverrides, logout_args: LogoutArgs) -> Result<()> {
-    let overrides = config_overrides
-        .parse_overrides()
-        .map_err(anyhow::Error::msg)?;
-    let config = Config::load_with_cli_overrides(overrides)
+    let config = config_overrides
+        .config_builder()
+        .map_err(anyhow::Error::msg)?
+        .build()
         .await
         .context("failed to load configuration")?;
 
@@ -388,10 +387,10 @@ async fn run_logout(config_overrides: &CliConfigOverrides, logout_args: LogoutAr
 }
 
 async fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) -> Result<()> {
-    let overrides = config_overrides
-        .parse_overrides()
-        .map_err(anyhow::Error::msg)?;
-    let config = Config::load_with_cli_overrides(overrides)
+    let config = config_overrides
+        .config_builder()
+        .map_err(anyhow::Error::msg)?
+        .build()
         .await
         .context("failed to load configuration")?;
 
@@ -645,10 +644,10 @@ async fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) ->
 }
 
 async fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Result<()> {
-    let overrides = config_overrides
-        .parse_overrides()
-        .map_err(anyhow::Error::msg)?;
-    let config = Config::load_with_cli_overrides(overrides)
+    let config = config_overrides
+        .config_builder()
+        .map_err(anyhow::Error::msg)?
+        .build()
         .await
         .context("failed to load configuration")?;
 
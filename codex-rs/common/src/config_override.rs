@@ -9,14 +9,133 @@
 
 use clap::ArgAction;
 use clap::Parser;
+use codex_core::config::Config;
+use codex_core::config::ConfigBuilder;
 use codex_core::config::set_codex_home_override;
-use codex_core::config::set_config_file_override;
+use codex_core::config_loader::LoaderOverrides;
+use codex_core::config_loader::apply_config_override;
+use codex_core::config_loader::merge_config_override;
+use codex_core::config_loader::merge_toml_values;
+use codex_core::config_loader::parse_config_file_contents;
+use codex_core::config_loader::resolve_override_references;
+use codex_core::config_loader::value_at_path;
+use codex_core::git_info::get_git_repo_root;
 use serde::de::Error as SerdeError;
+use std::collections::HashMap;
 use std::env;
+use std::fmt;
 use std::path::Path;
 use std::path::PathBuf;
 use toml::Value;
 
+/// Comma-separated dotted config paths (e.g. `approval_policy,sandbox_mode`)
+/// that `-c` overrides may not set. Meant to be exported by administrators of
+/// managed environments rather than by end users.
+pub const FORBIDDEN_OVERRIDES_ENV_VAR: &str = "CODEX_FORBIDDEN_OVERRIDES";
+
+/// Key suffix (`-c key[]=value`) that collects every value given for `key`
+/// into one array, in the order the overrides appear.
+const ACCUMULATE_SUFFIX: &str = "[]";
+
+/// Key suffix (`-c key:bool=YES`) that parses the value as a boolean,
+/// accepting `true`/`false`, `yes`/`no`, `on`/`off`, and `1`/`0` in any case.
+const BOOL_TYPE_TAG: &str = ":bool";
+
+/// Why a single `-c key=value` string could not be split into a key and a
+/// value. Each variant carries the raw override and the byte offset within it
+/// that a UI should highlight.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverrideParseError {
+    /// No `=` separates the key from the value; `position` is the end of the
+    /// raw string, where the `=` was expected.
+    MissingEquals { raw: String, position: usize },
+    /// Nothing but whitespace precedes the `=`; `position` is the offset of
+    /// that `=`.
+    EmptyKey { raw: String, position: usize },
+    /// A `key:bool` override whose value is not a recognized boolean
+    /// spelling; `position` is the start of the value.
+    InvalidBool { raw: String, position: usize },
+}
+
+impl OverrideParseError {
+    /// The override exactly as it was passed on the command line.
+    pub fn raw(&self) -> &str {
+        match self {
+            Self::MissingEquals { raw, .. }
+            | Self::EmptyKey { raw, .. }
+            | Self::InvalidBool { raw, .. } => raw,
+        }
+    }
+
+    /// Byte offset into [`OverrideParseError::raw`] of the offending part.
+    pub fn position(&self) -> usize {
+        match self {
+            Self::MissingEquals { position, .. }
+            | Self::EmptyKey { position, .. }
+            | Self::InvalidBool { position, .. } => *position,
+        }
+    }
+}
+
+impl fmt::Display for OverrideParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingEquals { raw, .. } => write!(f, "Invalid override (missing '='): {raw}"),
+            Self::EmptyKey { raw, .. } => write!(f, "Empty key in override: {raw}"),
+            Self::InvalidBool { raw, .. } => write!(
+                f,
+                "Invalid boolean in override (expected true/false, yes/no, on/off, or 1/0): {raw}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OverrideParseError {}
+
+/// Parse one raw `key=value` override as passed to `-c`.
+///
+/// Only the *first* `=` splits the string so values are free to contain the
+/// character. The value is parsed as TOML; if that fails it is treated as a
+/// raw string, which allows convenient usage such as `-c model=o3` without
+/// the quotes. A key ending in `:bool` instead requires a boolean spelling
+/// (`yes`, `off`, `1`, ... in any case) and yields a TOML boolean.
+pub fn parse_override(raw: &str) -> Result<(String, Value), OverrideParseError> {
+    let Some((key, value_str)) = raw.split_once('=') else {
+        return Err(OverrideParseError::MissingEquals {
+            raw: raw.to_string(),
+            position: raw.len(),
+        });
+    };
+    let key = key.trim();
+    let tagged_bool = key.strip_suffix(BOOL_TYPE_TAG).map(str::trim_end);
+    if key.is_empty() || tagged_bool.is_some_and(str::is_empty) {
+        return Err(OverrideParseError::EmptyKey {
+            raw: raw.to_string(),
+            position: raw.len() - value_str.len() - 1,
+        });
+    }
+    if let Some(key) = tagged_bool {
+        let Some(value) = parse_bool_literal(value_str.trim()) else {
+            return Err(OverrideParseError::InvalidBool {
+                raw: raw.to_string(),
+                position: raw.len() - value_str.trim_start().len(),
+            });
+        };
+        return Ok((key.to_string(), Value::Boolean(value)));
+    }
+
+    let value_str = value_str.trim();
+    let value = match parse_toml_value(value_str) {
+        Ok(v) => v,
+        Err(_) => {
+            // Strip leading/trailing quotes if present
+            let trimmed = value_str.trim_matches(|c| c == '"' || c == '\'');
+            Value::String(trimmed.to_string())
+        }
+    };
+    Ok((key.to_string(), value))
+}
+
 /// CLI option that captures arbitrary configuration overrides specified as
 /// `-c key=value`. It intentionally keeps both halves **unparsed** so that the
 /// calling code can decide how to interpret the right-hand side.
@@ -24,13 +143,20 @@ use toml::Value;
 pub struct CliConfigOverrides {
     /// Override a configuration value that would otherwise be loaded from
     /// `~/.codex/config.toml`. Use a dotted path (`foo.bar.baz`) to override
-    /// nested values. The `value` portion is parsed as TOML. If it fails to
+    /// nested values; numeric segments (`notify.1`) index into an existing
+    /// array. The `value` portion is parsed as TOML. If it fails to
     /// parse as TOML, the raw string is used as a literal.
     ///
     /// Examples:
     ///   - `-c model="o3"`
     ///   - `-c 'sandbox_permissions=["disk-full-read-access"]'`
     ///   - `-c shell_environment_policy.inherit=all`
+    ///
+    /// `-c path@=FILE` instead deep-merges the table in FILE under `path`,
+    /// keeping keys already there (e.g. `-c mcp_servers@=servers.toml`).
+    ///
+    /// `-c path[]=value` may be repeated to build an array at `path` from
+    /// every value, in order (e.g. `-c sandbox_permissions[]=network-access`).
     #[arg(
         short = 'c',
         long = "config",
@@ -40,6 +166,18 @@ pub struct CliConfigOverrides {
     )]
     pub raw_overrides: Vec<String>,
 
+    /// Like `-c key=value`, but only sets `key` when the merged config does
+    /// not already define it. Applied after every `-c` override, so a default
+    /// supplied this way never clobbers user config.
+    #[arg(
+        long = "config-set-if-unset",
+        value_name = "key=value",
+        action = ArgAction::Append,
+        global = true,
+        help = "Set key=value only if the merged config does not already define key"
+    )]
+    pub raw_set_if_unset: Vec<String>,
+
     /// Override the Codex config directory (`CODEX_HOME`) for this invocation.
     #[arg(
         long = "config-home",
@@ -57,58 +195,309 @@ pub struct CliConfigOverrides {
         help = "Use FILE instead of config.toml (can be outside of $CODEX_HOME)"
     )]
     pub config_file: Option<PathBuf>,
+
+    /// Start from an empty configuration instead of reading any config file,
+    /// so only `-c` overrides (and managed settings) apply. Cannot be combined
+    /// with `--config-file`.
+    #[arg(
+        long = "config-no-file",
+        global = true,
+        help = "Ignore config.toml (and any other config file); only -c overrides apply"
+    )]
+    pub config_no_file: bool,
+
+    /// Layer a `[profiles.NAME]` table over the config files before `-c`
+    /// overrides are applied. The config loader applies it; see
+    /// [`CliConfigOverrides::loader_overrides`].
+    ///
+    /// Not named `config_profile`: CLIs that flatten these overrides already
+    /// use that id for `--profile`.
+    #[arg(
+        long = "config-profile",
+        value_name = "NAME",
+        global = true,
+        help = "Merge [profiles.NAME] over the base config, then apply -c overrides on top"
+    )]
+    pub profile_overlay: Option<String>,
+
+    /// Directory of `*.toml` fragments deep-merged in lexical file-name order
+    /// before `-c` overrides. See [`load_config_dir`].
+    #[arg(
+        long = "config-dir",
+        value_name = "DIR",
+        global = true,
+        help = "Merge every *.toml file in DIR (in lexical order) over the base config, before -c overrides"
+    )]
+    pub config_dir: Option<PathBuf>,
+
+    /// Once the config is loaded, write the effective configuration to this
+    /// file (for example as a CI artifact) and keep running.
+    #[arg(
+        long = "config-dump-path",
+        value_name = "FILE",
+        global = true,
+        help = "Write the effective config (every config layer plus overrides) to FILE as TOML once it is loaded"
+    )]
+    pub config_dump_path: Option<PathBuf>,
+
+    /// Resolve `${path.to.key}` references in plain `-c key=value` string
+    /// values against the merged config. Off by default so literal `${VAR}`
+    /// strings keep working; `@=` file values, `--config-dir` fragments, and
+    /// config files are never expanded.
+    #[arg(
+        long = "config-expand-refs",
+        global = true,
+        help = "Resolve ${path.to.key} references in -c values against the merged config"
+    )]
+    pub expand_references: bool,
+
+    /// Dotted path prefixes that `-c` overrides may not touch. When `None`,
+    /// the list is read from [`FORBIDDEN_OVERRIDES_ENV_VAR`].
+    #[arg(skip)]
+    pub forbidden_override_prefixes: Option<Vec<String>>,
 }
 
 impl CliConfigOverrides {
+    /// Build overrides without going through clap, e.g. from a wrapper that
+    /// already has the raw `key=value` strings. `raw_overrides` use the same
+    /// syntax as `-c`.
+    pub fn from_raw(
+        raw_overrides: Vec<String>,
+        config_home: Option<PathBuf>,
+        config_file: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            raw_overrides,
+            config_home,
+            config_file,
+            ..Default::default()
+        }
+    }
+
     /// Parse the raw strings captured from the CLI into a list of `(path,
     /// value)` tuples where `value` is a `serde_json::Value`.
+    ///
+    /// Fails if any key is covered by the forbidden override prefixes.
+    ///
+    /// `--config-set-if-unset` entries are not returned: they are part of
+    /// [`CliConfigOverrides::loader_overrides`], and the loader applies each
+    /// one only when no config layer (system, user, project, managed, or
+    /// these overrides) sets its path.
     pub fn parse_overrides(&self) -> Result<Vec<(String, Value)>, String> {
         self.apply_config_location_overrides()?;
-        self.raw_overrides
+        let mut errors = Vec::new();
+        let overrides = self.collect_cli_overrides(&mut errors);
+        first_error(errors)?;
+        // Fragments go first so `-c` still wins.
+        let mut fragment_overrides = self.config_dir_overrides()?;
+        self.reject_forbidden_overrides(&fragment_overrides)?;
+        fragment_overrides.extend(overrides);
+        Ok(fragment_overrides)
+    }
+
+    fn parse_set_if_unset(&self) -> Result<Vec<(String, Value)>, String> {
+        let mut errors = Vec::new();
+        let overrides = self.collect_set_if_unset(&mut errors);
+        first_error(errors)?;
+        Ok(overrides)
+    }
+
+    /// Parse the `-c` overrides, expanding merge overrides and accumulating
+    /// array appends, and check them against the forbidden prefixes. Every
+    /// problem is pushed onto `errors` so [`CliConfigOverrides::validate`] can
+    /// report them all; the result is only meaningful when none were.
+    fn collect_cli_overrides(&self, errors: &mut Vec<String>) -> Vec<(String, Value)> {
+        let mut overrides = Vec::new();
+        for raw in &self.raw_overrides {
+            let parsed = match expand_merge_override(raw) {
+                Some(merged) => merged,
+                None => parse_override(raw)
+                    .map(|parsed| vec![parsed])
+                    .map_err(|err| err.to_string()),
+            };
+            match parsed {
+                Ok(parsed) => overrides.extend(parsed),
+                Err(err) => errors.push(err),
+            }
+        }
+        let overrides = accumulate_array_overrides(overrides).unwrap_or_else(|err| {
+            errors.push(err);
+            Vec::new()
+        });
+        if let Err(err) = self.reject_forbidden_overrides(&overrides) {
+            errors.push(err);
+        }
+        overrides
+    }
+
+    /// Like [`CliConfigOverrides::collect_cli_overrides`], for the
+    /// `--config-set-if-unset` entries.
+    fn collect_set_if_unset(&self, errors: &mut Vec<String>) -> Vec<(String, Value)> {
+        let mut overrides = Vec::new();
+        for raw in &self.raw_set_if_unset {
+            match parse_override(raw) {
+                Ok(parsed) => overrides.push(parsed),
+                Err(err) => errors.push(err.to_string()),
+            }
+        }
+        if let Err(err) = self.reject_forbidden_overrides(&overrides) {
+            errors.push(err);
+        }
+        overrides
+    }
+
+    /// The `--config-dir` fragments flattened into `(path, value)` overrides.
+    fn config_dir_overrides(&self) -> Result<Vec<(String, Value)>, String> {
+        let mut overrides = Vec::new();
+        if let Some(dir) = &self.config_dir {
+            flatten_fragment(&load_config_dir(dir)?, "", &mut overrides)?;
+        }
+        Ok(overrides)
+    }
+
+    fn reject_forbidden_overrides(&self, overrides: &[(String, Value)]) -> Result<(), String> {
+        let forbidden = match &self.forbidden_override_prefixes {
+            Some(prefixes) => prefixes.clone(),
+            None => env::var(FORBIDDEN_OVERRIDES_ENV_VAR)
+                .map(|raw| parse_forbidden_prefixes(&raw))
+                .unwrap_or_default(),
+        };
+        let blocked: Vec<String> = overrides
             .iter()
-            .map(|s| {
-                // Only split on the *first* '=' so values are free to contain
-                // the character.
-                let mut parts = s.splitn(2, '=');
-                let key = match parts.next() {
-                    Some(k) => k.trim(),
-                    None => return Err("Override missing key".to_string()),
-                };
-                let value_str = parts
-                    .next()
-                    .ok_or_else(|| format!("Invalid override (missing '='): {s}"))?
-                    .trim();
-
-                if key.is_empty() {
-                    return Err(format!("Empty key in override: {s}"));
-                }
-
-                // Attempt to parse as TOML. If that fails, treat it as a raw
-                // string. This allows convenient usage such as
-                // `-c model=o3` without the quotes.
-                let value: Value = match parse_toml_value(value_str) {
-                    Ok(v) => v,
-                    Err(_) => {
-                        // Strip leading/trailing quotes if present
-                        let trimmed = value_str.trim().trim_matches(|c| c == '"' || c == '\'');
-                        Value::String(trimmed.to_string())
-                    }
-                };
-
-                Ok((key.to_string(), value))
+            .map(|(path, _)| path)
+            .filter(|path| {
+                forbidden
+                    .iter()
+                    .any(|prefix| override_touches_prefix(path, prefix))
             })
-            .collect()
+            .map(|path| format!("`{path}`"))
+            .collect();
+        if blocked.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Overriding {} is not allowed in this environment",
+                blocked.join(", ")
+            ))
+        }
     }
 
     /// Apply all parsed overrides onto `target`. Intermediate objects will be
     /// created as necessary. Values located at the destination path will be
-    /// replaced.
+    /// replaced, except that `--config-dir` fragments and `-c path@=FILE`
+    /// merges deep-merge their tables into existing ones (as config layers
+    /// are). `--config-set-if-unset` entries are applied last and only when
+    /// their path is absent from `target`.
+    ///
+    /// With `--config-expand-refs`, `${path.to.key}` references in plain `-c`
+    /// string values are then replaced with the merged value at that key (see
+    /// [`resolve_override_references`]).
     pub fn apply_on_value(&self, target: &mut Value) -> Result<(), String> {
+        self.apply_on_value_with_paths(target).map(|_| ())
+    }
+
+    /// Like [`CliConfigOverrides::apply_on_value`], but returns the dotted
+    /// paths that were set, in the order they were applied (`--config-dir`
+    /// fragments first, then `-c` overrides, then `--config-set-if-unset`
+    /// entries that took effect). A path set twice is listed twice.
+    pub fn apply_on_value_with_paths(&self, target: &mut Value) -> Result<Vec<String>, String> {
         let overrides = self.parse_overrides()?;
+        let merge_paths = self.merge_paths()?;
+        let mut applied = Vec::with_capacity(overrides.len());
         for (path, value) in overrides {
-            apply_single_override(target, &path, value);
+            let applied_override = if merge_paths.contains(&path) {
+                merge_config_override(target, &path, value)
+            } else {
+                apply_config_override(target, &path, value)
+            };
+            applied_override.map_err(|err| err.to_string())?;
+            applied.push(path);
+        }
+        for (path, value) in self.parse_set_if_unset()? {
+            if value_at_path(target, &path).is_none() {
+                apply_config_override(target, &path, value).map_err(|err| err.to_string())?;
+                applied.push(path);
+            }
+        }
+        resolve_override_references(target, &self.reference_paths())?;
+        Ok(applied)
+    }
+
+    /// When `--config-dump-path` is set, write the effective configuration of
+    /// the loaded `config` (every config layer merged, including the profile
+    /// and `-c` overrides) to that file as TOML, creating parent directories
+    /// as needed. Call this once the config has been loaded.
+    pub fn write_config_dump(&self, config: &Config) -> Result<(), String> {
+        let Some(dump_path) = &self.config_dump_path else {
+            return Ok(());
+        };
+        let effective = config.config_layer_stack.effective_config();
+        let write = || -> std::io::Result<()> {
+            if let Some(parent) = dump_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let contents = toml::to_string_pretty(&effective).map_err(std::io::Error::other)?;
+            std::fs::write(dump_path, contents)
+        };
+        write().map_err(|err| {
+            format!(
+                "Failed to write --config-dump-path `{}`: {err}",
+                dump_path.display()
+            )
+        })
+    }
+
+    fn resolved_config_home(&self) -> Result<Option<PathBuf>, String> {
+        let Some(path) = &self.config_home else {
+            return Ok(None);
+        };
+        canonicalize_or_absolute(path).map(Some).map_err(|err| {
+            format!(
+                "Failed to resolve --config-home path `{}`: {err}",
+                path.display()
+            )
+        })
+    }
+
+    fn resolved_config_file(&self) -> Result<Option<PathBuf>, String> {
+        let Some(path) = &self.config_file else {
+            return Ok(None);
+        };
+        resolve_config_file_override(path).map(Some).map_err(|err| {
+            format!(
+                "Failed to resolve --config-file path `{}`: {err}",
+                path.display()
+            )
+        })
+    }
+
+    /// Check every override and location flag without applying anything,
+    /// reporting all problems at once instead of stopping at the first.
+    /// Nothing is written and no process-wide location override is set.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if let Err(err) = self.check_config_file_flags() {
+            errors.push(err);
+        }
+        if let Err(err) = self.resolved_config_home() {
+            errors.push(err);
+        }
+        if let Err(err) = self.resolved_config_file() {
+            errors.push(err);
+        }
+        self.collect_set_if_unset(&mut errors);
+        self.collect_cli_overrides(&mut errors);
+        if let Err(err) = self
+            .config_dir_overrides()
+            .and_then(|fragments| self.reject_forbidden_overrides(&fragments))
+        {
+            errors.push(err);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
-        Ok(())
     }
 
     /// Merge root-level overrides (e.g., parsed before a subcommand) into this
@@ -116,34 +505,239 @@ impl CliConfigOverrides {
     /// Values already set on `self` take precedence.
     pub fn prepend_from(&mut self, other: &CliConfigOverrides) {
         self.raw_overrides.splice(0..0, other.raw_overrides.clone());
+        self.raw_set_if_unset
+            .splice(0..0, other.raw_set_if_unset.clone());
 
         inherit_if_absent(&mut self.config_home, other.config_home.clone());
         inherit_if_absent(&mut self.config_file, other.config_file.clone());
+        self.config_no_file |= other.config_no_file;
+        self.expand_references |= other.expand_references;
+        inherit_if_absent(&mut self.profile_overlay, other.profile_overlay.clone());
+        inherit_if_absent(&mut self.config_dir, other.config_dir.clone());
+        inherit_if_absent(&mut self.config_dump_path, other.config_dump_path.clone());
+        inherit_if_absent(
+            &mut self.forbidden_override_prefixes,
+            other.forbidden_override_prefixes.clone(),
+        );
     }
 
+    /// Loader options for the flags that change how config layers are read
+    /// rather than adding overrides: `--config-file` replaces the user config
+    /// file, `--config-no-file` skips every config file, `--config-profile` merges a profile under the `-c` values,
+    /// `--config-set-if-unset` entries only fill paths no layer sets, and
+    /// `--config-expand-refs` resolves references in plain `-c` values. Pass
+    /// the result to the config loader alongside
+    /// [`CliConfigOverrides::parse_overrides`].
+    pub fn loader_overrides(&self) -> Result<LoaderOverrides, String> {
+        self.check_config_file_flags()?;
+        Ok(LoaderOverrides {
+            config_file: self.resolved_config_file()?,
+            ignore_config_files: self.config_no_file,
+            profile_overlay: self.profile_overlay.clone(),
+            cli_defaults: self.parse_set_if_unset()?,
+            reference_paths: self.reference_paths(),
+            merge_paths: self.merge_paths()?,
+            ..Default::default()
+        })
+    }
+
+    /// Paths of the plain `-c key=value` overrides whose values may hold
+    /// `${...}` references, or none without `--config-expand-refs`.
+    fn reference_paths(&self) -> Vec<String> {
+        if !self.expand_references {
+            return Vec::new();
+        }
+        self.raw_overrides
+            .iter()
+            .filter(|raw| expand_merge_override(raw).is_none())
+            .filter_map(|raw| parse_override(raw).ok())
+            .map(|(path, _)| path)
+            .filter(|path| !path.ends_with(ACCUMULATE_SUFFIX))
+            .collect()
+    }
+
+    /// Paths of the `--config-dir` and `-c path@=FILE` overrides that carry a
+    /// table, which is deep-merged into the table already at that path
+    /// instead of replacing it.
+    fn merge_paths(&self) -> Result<Vec<String>, String> {
+        let mut merges = self.config_dir_overrides()?;
+        for raw in &self.raw_overrides {
+            if let Some(expanded) = expand_merge_override(raw) {
+                merges.extend(expanded?);
+            }
+        }
+        Ok(merges
+            .into_iter()
+            .filter(|(_, value)| value.is_table())
+            .map(|(path, _)| path)
+            .collect())
+    }
+
+    /// A [`ConfigBuilder`] preloaded with the parsed overrides and loader
+    /// options, for callers that need nothing else from the raw flags.
+    pub fn config_builder(&self) -> Result<ConfigBuilder, String> {
+        Ok(ConfigBuilder::default()
+            .cli_overrides(self.parse_overrides()?)
+            .loader_overrides(self.loader_overrides()?))
+    }
+
+    fn check_config_file_flags(&self) -> Result<(), String> {
+        if self.config_no_file && self.config_file.is_some() {
+            return Err("--config-no-file cannot be used with --config-file".to_string());
+        }
+        Ok(())
+    }
+
+    /// Point [`codex_core::config::find_codex_home`] at `--config-home`, so
+    /// auth, logs, and sessions move along with the config. `--config-file`
+    /// is not process-wide; it reaches the loader through
+    /// [`CliConfigOverrides::loader_overrides`].
     fn apply_config_location_overrides(&self) -> Result<(), String> {
-        if let Some(path) = &self.config_home {
-            let normalized = canonicalize_or_absolute(path).map_err(|err| {
-                format!(
-                    "Failed to resolve --config-home path `{}`: {err}",
-                    path.display()
-                )
-            })?;
+        self.check_config_file_flags()?;
+        if let Some(normalized) = self.resolved_config_home()? {
             set_codex_home_override(normalized);
         }
+        Ok(())
+    }
+}
 
-        if let Some(path) = &self.config_file {
-            let resolved = resolve_config_file_override(path).map_err(|err| {
-                format!(
-                    "Failed to resolve --config-file path `{}`: {err}",
-                    path.display()
-                )
-            })?;
-            set_config_file_override(resolved);
+/// Fail with the first of `errors`, if any.
+fn first_error(errors: Vec<String>) -> Result<(), String> {
+    errors.into_iter().next().map_or(Ok(()), Err)
+}
+
+/// Load every `*.toml` file directly inside `dir`, sorted by file name, and
+/// deep-merge them so later files win. Other files and subdirectories are
+/// ignored; an empty directory yields an empty table.
+pub fn load_config_dir(dir: &Path) -> Result<Value, String> {
+    let read_error =
+        |err: std::io::Error| format!("Failed to read --config-dir `{}`: {err}", dir.display());
+    let mut fragments = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(read_error)? {
+        let path = entry.map_err(read_error)?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "toml") {
+            fragments.push(path);
         }
+    }
+    fragments.sort();
 
-        Ok(())
+    let mut merged = Value::Table(Default::default());
+    for path in fragments {
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|err| format!("Failed to read config fragment `{}`: {err}", path.display()))?;
+        let fragment =
+            parse_config_file_contents(&path, &contents).map_err(|err| err.to_string())?;
+        merge_toml_values(&mut merged, &fragment);
+    }
+    Ok(merged)
+}
+
+/// Expand `path@=FILE` into overrides that deep-merge the table in FILE under
+/// `path`, so keys already at `path` that FILE does not mention survive.
+/// Returns `None` for ordinary `key=value` overrides.
+fn expand_merge_override(raw: &str) -> Option<Result<Vec<(String, Value)>, String>> {
+    let (key, file) = raw.split_once("@=")?;
+    if key.contains('=') {
+        // The first `=` comes earlier, so `@=` is part of an ordinary value.
+        return None;
+    }
+    let key = key.trim();
+    if key.is_empty() {
+        return Some(Err(format!("Empty key in override: {raw}")));
+    }
+    let path = Path::new(file.trim());
+    let merged = std::fs::read_to_string(path)
+        .map_err(|err| {
+            format!(
+                "Failed to read merge file `{}` for override `{key}`: {err}",
+                path.display()
+            )
+        })
+        .and_then(|contents| {
+            parse_config_file_contents(path, &contents).map_err(|err| err.to_string())
+        })
+        .and_then(|value| {
+            let mut overrides = Vec::new();
+            flatten_fragment(&value, key, &mut overrides)?;
+            Ok(overrides)
+        });
+    Some(merged)
+}
+
+/// Collapse every `key[]` override into a single `(key, array)` entry at the
+/// position of its first occurrence, keeping the values in order. Fails when
+/// an earlier plain override already set `key`, since it is unclear whether
+/// that value should start the array or be discarded.
+fn accumulate_array_overrides(
+    overrides: Vec<(String, Value)>,
+) -> Result<Vec<(String, Value)>, String> {
+    let mut collapsed: Vec<(String, Value)> = Vec::with_capacity(overrides.len());
+    let mut arrays: HashMap<String, usize> = HashMap::new();
+    for (path, value) in overrides {
+        let Some(base) = path.strip_suffix(ACCUMULATE_SUFFIX) else {
+            collapsed.push((path, value));
+            continue;
+        };
+        let base = base.trim_end();
+        if base.is_empty() {
+            return Err(format!("Empty key in override: {path}"));
+        }
+        if let Some(&index) = arrays.get(base) {
+            if let Some((_, Value::Array(items))) = collapsed.get_mut(index) {
+                items.push(value);
+            }
+            continue;
+        }
+        if collapsed.iter().any(|(earlier, _)| earlier == base) {
+            return Err(format!(
+                "Cannot accumulate `{path}`: `{base}` was already set by an earlier override"
+            ));
+        }
+        arrays.insert(base.to_string(), collapsed.len());
+        collapsed.push((base.to_string(), Value::Array(vec![value])));
     }
+    Ok(collapsed)
+}
+
+/// Turn a merged fragment table into dotted `(path, value)` overrides whose
+/// sequential application deep-merges it into the config. Empty tables are
+/// emitted as such so they still create the table, and keys that cannot be
+/// written as a dotted path (they contain `.`) are emitted as a table holding
+/// just those keys; both are merged into any table already at that path (see
+/// [`CliConfigOverrides::merge_paths`]).
+fn flatten_fragment(
+    value: &Value,
+    prefix: &str,
+    out: &mut Vec<(String, Value)>,
+) -> Result<(), String> {
+    let Value::Table(table) = value else {
+        out.push((prefix.to_string(), value.clone()));
+        return Ok(());
+    };
+    if table.is_empty() && !prefix.is_empty() {
+        out.push((prefix.to_string(), value.clone()));
+        return Ok(());
+    }
+    let dotted: toml::value::Table = table
+        .iter()
+        .filter(|(key, _)| key.contains('.'))
+        .map(|(key, child)| (key.clone(), child.clone()))
+        .collect();
+    if !dotted.is_empty() {
+        if prefix.is_empty() {
+            return Err("Top-level keys in --config-dir fragments may not contain `.`".to_string());
+        }
+        out.push((prefix.to_string(), Value::Table(dotted)));
+    }
+    for (key, child) in table.iter().filter(|(key, _)| !key.contains('.')) {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        flatten_fragment(child, &path, out)?;
+    }
+    Ok(())
 }
 
 fn canonicalize_or_absolute(path: &Path) -> std::io::Result<PathBuf> {
@@ -174,6 +768,12 @@ fn resolve_config_file_override(path: &Path) -> std::io::Result<PathBuf> {
     let cwd = env::current_dir()?;
     let mut candidates = vec![cwd.join(path)];
     candidates.push(cwd.join(".codex").join(path));
+    // A shared config often lives at the root of the repository the command
+    // runs in, which may be several directories up.
+    if let Some(repo_root) = get_git_repo_root(&cwd).filter(|root| *root != cwd) {
+        candidates.push(repo_root.join(path));
+        candidates.push(repo_root.join(".codex").join(path));
+    }
     if let Some(home) = dirs::home_dir() {
         candidates.push(home.join(".codex").join(path));
     }
@@ -215,6 +815,21 @@ mod config_file_resolution_tests {
         });
     }
 
+    #[test]
+    fn finds_config_at_git_repo_root_from_nested_dir() {
+        let tmp = tempdir().expect("tempdir");
+        fs::create_dir_all(tmp.path().join(".git")).expect("mkdir .git");
+        let cfg = tmp.path().join("codex.toml");
+        fs::write(&cfg, "model = \"test\"").expect("write");
+        let nested = tmp.path().join("crates").join("app");
+        fs::create_dir_all(&nested).expect("mkdir nested");
+
+        with_cwd(&nested, || {
+            let resolved = resolve_config_file_override(Path::new("codex.toml")).expect("resolved");
+            assert_eq!(resolved, fs::canonicalize(&cfg).unwrap());
+        });
+    }
+
     #[test]
     fn falls_back_to_dot_codex_folder() {
         let tmp = tempdir().expect("tempdir");
@@ -237,59 +852,87 @@ fn inherit_if_absent<T: Clone>(target: &mut Option<T>, candidate: Option<T>) {
     }
 }
 
-/// Apply a single override onto `root`, creating intermediate objects as
-/// necessary.
-fn apply_single_override(root: &mut Value, path: &str, value: Value) {
-    use toml::value::Table;
-
-    let parts: Vec<&str> = path.split('.').collect();
-    let mut current = root;
-
-    for (i, part) in parts.iter().enumerate() {
-        let is_last = i == parts.len() - 1;
-
-        if is_last {
-            match current {
-                Value::Table(tbl) => {
-                    tbl.insert((*part).to_string(), value);
-                }
-                _ => {
-                    let mut tbl = Table::new();
-                    tbl.insert((*part).to_string(), value);
-                    *current = Value::Table(tbl);
-                }
-            }
-            return;
-        }
+fn parse_forbidden_prefixes(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|prefix| !prefix.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
 
-        // Traverse or create intermediate table.
-        match current {
-            Value::Table(tbl) => {
-                current = tbl
-                    .entry((*part).to_string())
-                    .or_insert_with(|| Value::Table(Table::new()));
-            }
-            _ => {
-                *current = Value::Table(Table::new());
-                if let Value::Table(tbl) = current {
-                    current = tbl
-                        .entry((*part).to_string())
-                        .or_insert_with(|| Value::Table(Table::new()));
-                }
-            }
-        }
+/// True when overriding `path` would set `prefix` or anything beneath it.
+/// Overriding a parent table (e.g. `sandbox_workspace_write` when
+/// `sandbox_workspace_write.network_access` is forbidden) counts as well,
+/// since it replaces the forbidden child.
+fn override_touches_prefix(path: &str, prefix: &str) -> bool {
+    let is_within = |outer: &str, inner: &str| {
+        inner == outer
+            || inner
+                .strip_prefix(outer)
+                .is_some_and(|rest| rest.starts_with('.'))
+    };
+    is_within(prefix, path) || is_within(path, prefix)
+}
+
+/// Case-insensitive boolean spellings accepted by `key:bool` overrides,
+/// optionally quoted.
+fn parse_bool_literal(raw: &str) -> Option<bool> {
+    let unquoted = raw.trim_matches(|c| c == '"' || c == '\'');
+    match unquoted.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
     }
 }
 
 fn parse_toml_value(raw: &str) -> Result<Value, toml::de::Error> {
     let wrapped = format!("_x_ = {raw}");
-    let table: toml::Table = toml::from_str(&wrapped)?;
+    let table: toml::Table = match toml::from_str(&wrapped) {
+        Ok(table) => table,
+        Err(err) => {
+            return parse_signed_radix_integer(raw)
+                .map(Value::Integer)
+                .ok_or(err);
+        }
+    };
     table
         .get("_x_")
         .cloned()
         .ok_or_else(|| SerdeError::custom("missing sentinel key"))
 }
 
+/// TOML only allows a sign on decimal numbers, so `-0x10` is rejected by the
+/// sentinel parser. Accept signed `0x`/`0o`/`0b` literals here so they become
+/// integers instead of silently turning into strings.
+fn parse_signed_radix_integer(raw: &str) -> Option<i64> {
+    let raw = raw.trim();
+    let (negative, unsigned) = match raw.as_bytes().first() {
+        Some(b'-') => (true, &raw[1..]),
+        Some(b'+') => (false, &raw[1..]),
+        _ => return None,
+    };
+    let (radix, digits) = if let Some(digits) = unsigned.strip_prefix("0x") {
+        (16, digits)
+    } else if let Some(digits) = unsigned.strip_prefix("0o") {
+        (8, digits)
+    } else if let Some(digits) = unsigned.strip_prefix("0b") {
+        (2, digits)
+    } else {
+        return None;
+    };
+    if digits.is_empty()
+        || digits.starts_with('_')
+        || digits.ends_with('_')
+        || !digits
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return None;
+    }
+    let magnitude = i64::from_str_radix(&digits.replace('_', ""), radix).ok()?;
+    Some(if negative { -magnitude } else { magnitude })
+}
+
 #[cfg(all(test, feature = "cli"))]
 mod tests {
     use super::*;
@@ -309,6 +952,60 @@ mod tests {
         assert_eq!(false_literal.as_bool(), Some(false));
     }
 
+    #[test]
+    fn parses_negative_float() {
+        let v = parse_toml_value("-0.2").expect("parse");
+        assert_eq!(v.as_float(), Some(-0.2));
+    }
+
+    #[test]
+    fn parses_negative_integer() {
+        let v = parse_toml_value("-42").expect("parse");
+        assert_eq!(v.as_integer(), Some(-42));
+    }
+
+    #[test]
+    fn parses_radix_integers() {
+        assert_eq!(
+            parse_toml_value("0xff").expect("parse").as_integer(),
+            Some(255)
+        );
+        assert_eq!(
+            parse_toml_value("0o17").expect("parse").as_integer(),
+            Some(15)
+        );
+        assert_eq!(
+            parse_toml_value("0b101").expect("parse").as_integer(),
+            Some(5)
+        );
+        assert_eq!(
+            parse_toml_value("-0x10").expect("parse").as_integer(),
+            Some(-16)
+        );
+        assert_eq!(
+            parse_toml_value("+0b1_0").expect("parse").as_integer(),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn fails_on_invalid_signed_radix_integer() {
+        assert!(parse_toml_value("-0xzz").is_err());
+    }
+
+    #[test]
+    fn negative_override_is_stored_as_number() {
+        let overrides = CliConfigOverrides {
+            raw_overrides: vec!["temperature=-0.2".to_string()],
+            ..Default::default()
+        };
+        let parsed = overrides.parse_overrides().expect("parse overrides");
+        assert_eq!(
+            parsed,
+            vec![("temperature".to_string(), Value::Float(-0.2))]
+        );
+    }
+
     #[test]
     fn fails_on_unquoted_string() {
         assert!(parse_toml_value("hello").is_err());
@@ -328,4 +1025,510 @@ mod tests {
         assert_eq!(tbl.get("a").unwrap().as_integer(), Some(1));
         assert_eq!(tbl.get("b").unwrap().as_integer(), Some(2));
     }
+
+    #[test]
+    fn from_raw_round_trips_through_parse_overrides() {
+        let overrides = CliConfigOverrides::from_raw(
+            vec![
+                "model=o3".to_string(),
+                "sandbox_workspace_write.network_access=true".to_string(),
+            ],
+            None,
+            None,
+        );
+
+        assert_eq!(
+            overrides.parse_overrides().expect("parse"),
+            vec![
+                ("model".to_string(), Value::String("o3".to_string())),
+                (
+                    "sandbox_workspace_write.network_access".to_string(),
+                    Value::Boolean(true)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_on_value_with_paths_reports_paths_in_order() {
+        let overrides = CliConfigOverrides {
+            raw_overrides: vec![
+                "model=o3".to_string(),
+                r#"sandbox_permissions=["disk-full-read-access"]"#.to_string(),
+                "shell_environment_policy.inherit=all".to_string(),
+            ],
+            ..Default::default()
+        };
+        let mut target = Value::Table(Default::default());
+
+        assert_eq!(
+            overrides
+                .apply_on_value_with_paths(&mut target)
+                .expect("apply"),
+            vec![
+                "model".to_string(),
+                "sandbox_permissions".to_string(),
+                "shell_environment_policy.inherit".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn loader_overrides_carry_the_loader_only_flags() {
+        let overrides = CliConfigOverrides {
+            raw_overrides: vec!["approval_policy=never".to_string()],
+            raw_set_if_unset: vec!["model=o3".to_string()],
+            config_no_file: true,
+            profile_overlay: Some("ci".to_string()),
+            ..Default::default()
+        };
+
+        let loader_overrides = overrides.loader_overrides().expect("loader overrides");
+
+        assert_eq!(
+            (
+                overrides.parse_overrides().expect("overrides"),
+                loader_overrides.ignore_config_files,
+                loader_overrides.profile_overlay,
+                loader_overrides.cli_defaults,
+            ),
+            (
+                vec![(
+                    "approval_policy".to_string(),
+                    Value::String("never".to_string())
+                )],
+                true,
+                Some("ci".to_string()),
+                vec![("model".to_string(), Value::String("o3".to_string()))],
+            )
+        );
+    }
+
+    #[test]
+    fn validate_reports_every_problem_at_once() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let missing = dir.path().join("missing.toml");
+        let overrides = CliConfigOverrides {
+            raw_overrides: vec![
+                "model=o3".to_string(),
+                "no_equals".to_string(),
+                " =value".to_string(),
+                format!("mcp_servers@={}", missing.display()),
+                "approval_policy=never".to_string(),
+            ],
+            config_file: Some(dir.path().join("ci.toml")),
+            config_no_file: true,
+            forbidden_override_prefixes: Some(vec!["approval_policy".to_string()]),
+            ..Default::default()
+        };
+
+        let errors = overrides.validate().expect_err("invalid overrides");
+
+        assert_eq!(
+            errors,
+            vec![
+                "--config-no-file cannot be used with --config-file".to_string(),
+                format!(
+                    "Failed to resolve --config-file path `{ci}`: Config file `{ci}` does not exist",
+                    ci = dir.path().join("ci.toml").display()
+                ),
+                "Invalid override (missing '='): no_equals".to_string(),
+                "Empty key in override:  =value".to_string(),
+                format!(
+                    "Failed to read merge file `{}` for override `mcp_servers`: {}",
+                    missing.display(),
+                    std::fs::read_to_string(&missing).expect_err("missing file")
+                ),
+                "Overriding `approval_policy` is not allowed in this environment".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn config_no_file_conflicts_with_config_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let overrides = CliConfigOverrides {
+            config_file: Some(dir.path().join("ci.toml")),
+            config_no_file: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            overrides.parse_overrides(),
+            Err("--config-no-file cannot be used with --config-file".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_overrides_does_not_write_the_config_dump() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let dump_path = dir.path().join("merged.toml");
+        let overrides = CliConfigOverrides {
+            raw_overrides: vec!["approval_policy=never".to_string()],
+            config_dump_path: Some(dump_path.clone()),
+            ..Default::default()
+        };
+
+        overrides.parse_overrides().expect("parse");
+
+        assert!(!dump_path.exists());
+    }
+
+    #[tokio::test]
+    async fn write_config_dump_writes_the_loaded_effective_config() {
+        // `--config-no-file` keeps the test independent of any config file
+        // on the machine.
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let dump_path = codex_home.path().join("artifacts").join("merged.toml");
+        let overrides = CliConfigOverrides {
+            raw_overrides: vec![
+                "model=gpt-5".to_string(),
+                "approval_policy=never".to_string(),
+            ],
+            raw_set_if_unset: vec!["model=o3".to_string(), "model_verbosity=low".to_string()],
+            config_no_file: true,
+            config_dump_path: Some(dump_path.clone()),
+            ..Default::default()
+        };
+        let config = overrides
+            .config_builder()
+            .expect("builder")
+            .codex_home(codex_home.path().to_path_buf())
+            .build()
+            .await
+            .expect("load config");
+
+        overrides.write_config_dump(&config).expect("dump");
+
+        let dumped: Value =
+            toml::from_str(&std::fs::read_to_string(&dump_path).expect("read dump"))
+                .expect("dump is toml");
+        let get = |key: &str| dumped.get(key).and_then(Value::as_str);
+        assert_eq!(get("model"), Some("gpt-5"));
+        assert_eq!(get("approval_policy"), Some("never"));
+        assert_eq!(get("model_verbosity"), Some("low"));
+    }
+
+    #[test]
+    fn config_dir_fragments_merge_in_lexical_order() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            dir.path().join("20-team.toml"),
+            r#"
+model = "o3"
+
+[tui]
+notifications = false
+"#,
+        )
+        .expect("write fragment");
+        std::fs::write(
+            dir.path().join("10-base.toml"),
+            r#"
+model = "gpt-5"
+approval_policy = "on-request"
+
+[tui]
+notifications = true
+animations = false
+"#,
+        )
+        .expect("write fragment");
+        std::fs::write(dir.path().join("README.md"), "model = \"ignored\"").expect("write");
+
+        let expected: Value = toml::from_str(
+            r#"
+model = "o3"
+approval_policy = "on-request"
+
+[tui]
+notifications = false
+animations = false
+"#,
+        )
+        .expect("expected");
+        assert_eq!(load_config_dir(dir.path()).expect("load"), expected);
+    }
+
+    #[test]
+    fn empty_config_dir_is_a_no_op() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let overrides = CliConfigOverrides {
+            config_dir: Some(dir.path().to_path_buf()),
+            forbidden_override_prefixes: Some(Vec::new()),
+            ..Default::default()
+        };
+
+        assert_eq!(overrides.parse_overrides().expect("parse"), Vec::new());
+    }
+
+    #[test]
+    fn merge_operator_only_applies_before_the_first_equals() {
+        let overrides = CliConfigOverrides {
+            raw_overrides: vec!["notify_note=a@=b".to_string()],
+            forbidden_override_prefixes: Some(Vec::new()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            overrides.parse_overrides().expect("parse"),
+            vec![("notify_note".to_string(), Value::String("a@=b".to_string()))]
+        );
+    }
+
+    #[test]
+    fn repeated_array_suffix_accumulates_values_in_order() {
+        let overrides = CliConfigOverrides {
+            raw_overrides: vec![
+                "sandbox_permissions[]=disk-full-read-access".to_string(),
+                "model=o3".to_string(),
+                "sandbox_permissions[]=network-access".to_string(),
+            ],
+            forbidden_override_prefixes: Some(Vec::new()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            overrides.parse_overrides().expect("parse"),
+            vec![
+                (
+                    "sandbox_permissions".to_string(),
+                    Value::Array(vec![
+                        Value::String("disk-full-read-access".to_string()),
+                        Value::String("network-access".to_string()),
+                    ])
+                ),
+                ("model".to_string(), Value::String("o3".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn array_suffix_after_plain_override_of_same_key_is_rejected() {
+        let overrides = CliConfigOverrides {
+            raw_overrides: vec![
+                "sandbox_permissions=disk-full-read-access".to_string(),
+                "sandbox_permissions[]=network-access".to_string(),
+            ],
+            forbidden_override_prefixes: Some(Vec::new()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            overrides.parse_overrides(),
+            Err("Cannot accumulate `sandbox_permissions[]`: `sandbox_permissions` was already set by an earlier override".to_string())
+        );
+    }
+
+    #[test]
+    fn literal_reference_syntax_is_kept_without_expand_refs() {
+        let overrides = CliConfigOverrides {
+            raw_overrides: vec!["model=o3".to_string(), "log_dir=${model}-logs".to_string()],
+            forbidden_override_prefixes: Some(Vec::new()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            (
+                overrides.parse_overrides().expect("parse"),
+                overrides
+                    .loader_overrides()
+                    .expect("loader")
+                    .reference_paths
+            ),
+            (
+                vec![
+                    ("model".to_string(), Value::String("o3".to_string())),
+                    (
+                        "log_dir".to_string(),
+                        Value::String("${model}-logs".to_string())
+                    ),
+                ],
+                Vec::new()
+            )
+        );
+    }
+
+    #[test]
+    fn expand_refs_leaves_file_and_fragment_values_alone() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let servers = dir.path().join("servers.toml");
+        std::fs::write(&servers, "[docs]\ncommand = \"${HOME}/bin/docs\"\n").expect("write");
+        let fragments = dir.path().join("conf.d");
+        std::fs::create_dir(&fragments).expect("mkdir");
+        std::fs::write(
+            fragments.join("10-base.toml"),
+            "notify_note = \"${model}\"\n",
+        )
+        .expect("write fragment");
+        let overrides = CliConfigOverrides {
+            raw_overrides: vec![
+                "model=o3".to_string(),
+                format!("mcp_servers@={}", servers.display()),
+                "log_dir=${model}-logs".to_string(),
+            ],
+            config_dir: Some(fragments),
+            expand_references: true,
+            forbidden_override_prefixes: Some(Vec::new()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            overrides
+                .loader_overrides()
+                .expect("loader")
+                .reference_paths,
+            vec!["model".to_string(), "log_dir".to_string()]
+        );
+    }
+
+    #[test]
+    fn bool_type_tag_accepts_common_spellings() {
+        let spellings = [
+            "True", "YES", "on", "1", "'yes'", "No", "off", "FALSE", "0", "\"Off\"",
+        ];
+
+        let parsed: Vec<(&str, Result<(String, Value), OverrideParseError>)> = spellings
+            .iter()
+            .map(|spelling| (*spelling, parse_override(&format!("login:bool={spelling}"))))
+            .collect();
+
+        let login = |value: bool| Ok(("login".to_string(), Value::Boolean(value)));
+        assert_eq!(
+            parsed,
+            vec![
+                ("True", login(true)),
+                ("YES", login(true)),
+                ("on", login(true)),
+                ("1", login(true)),
+                ("'yes'", login(true)),
+                ("No", login(false)),
+                ("off", login(false)),
+                ("FALSE", login(false)),
+                ("0", login(false)),
+                ("\"Off\"", login(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn bool_type_tag_rejects_other_values() {
+        let err = parse_override("login:bool= maybe").expect_err("not a boolean");
+        assert_eq!(
+            err,
+            OverrideParseError::InvalidBool {
+                raw: "login:bool= maybe".to_string(),
+                position: 12,
+            }
+        );
+        assert_eq!(
+            parse_override("login=YES"),
+            Ok(("login".to_string(), Value::String("YES".to_string())))
+        );
+    }
+
+    #[test]
+    fn missing_equals_points_past_the_key() {
+        let err = parse_override("model").expect_err("missing '='");
+        assert_eq!(
+            err,
+            OverrideParseError::MissingEquals {
+                raw: "model".to_string(),
+                position: 5,
+            }
+        );
+        assert_eq!(err.to_string(), "Invalid override (missing '='): model");
+    }
+
+    #[test]
+    fn empty_key_points_at_the_equals_sign() {
+        let err = parse_override("  =o3").expect_err("empty key");
+        assert_eq!(
+            err,
+            OverrideParseError::EmptyKey {
+                raw: "  =o3".to_string(),
+                position: 2,
+            }
+        );
+        assert_eq!(err.to_string(), "Empty key in override:   =o3");
+    }
+
+    #[test]
+    fn parse_overrides_reports_structured_errors_as_strings() {
+        let overrides = CliConfigOverrides::from_raw(vec!["=x".to_string()], None, None);
+        assert_eq!(
+            overrides.parse_overrides(),
+            Err("Empty key in override: =x".to_string())
+        );
+    }
+
+    fn overrides_with_deny_list(raw: &[&str], forbidden: &str) -> CliConfigOverrides {
+        CliConfigOverrides {
+            raw_overrides: raw.iter().map(ToString::to_string).collect(),
+            forbidden_override_prefixes: Some(parse_forbidden_prefixes(forbidden)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn forbidden_override_is_rejected() {
+        let overrides = overrides_with_deny_list(
+            &[
+                "model=o3",
+                "approval_policy=never",
+                "sandbox_workspace_write={}",
+            ],
+            "approval_policy, sandbox_workspace_write.network_access",
+        );
+
+        assert_eq!(
+            overrides.parse_overrides(),
+            Err("Overriding `approval_policy`, `sandbox_workspace_write` is not allowed in this environment".to_string())
+        );
+    }
+
+    #[test]
+    fn forbidden_key_in_config_dir_fragment_is_rejected() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            dir.path().join("10-policy.toml"),
+            "model = \"o3\"\napproval_policy = \"never\"\n",
+        )
+        .expect("write fragment");
+        let overrides = CliConfigOverrides {
+            config_dir: Some(dir.path().to_path_buf()),
+            ..overrides_with_deny_list(&[], "approval_policy")
+        };
+
+        let err = overrides
+            .parse_overrides()
+            .expect_err("forbidden fragment key");
+
+        assert_eq!(
+            (err.as_str(), overrides.validate()),
+            (
+                "Overriding `approval_policy` is not allowed in this environment",
+                Err(vec![
+                    "Overriding `approval_policy` is not allowed in this environment".to_string()
+                ]),
+            )
+        );
+    }
+
+    #[test]
+    fn allowed_override_passes_deny_list() {
+        let overrides =
+            overrides_with_deny_list(&["model=o3", "approval_policy_notes=x"], "approval_policy");
+
+        assert_eq!(
+            overrides.parse_overrides().expect("allowed"),
+            vec![
+                ("model".to_string(), Value::String("o3".to_string())),
+                (
+                    "approval_policy_notes".to_string(),
+                    Value::String("x".to_string())
+                ),
+            ]
+        );
+    }
 }
@@ -0,0 +1,59 @@
+//! Hot reload of the config file for long-running sessions.
+//!
+//! [`CliConfigOverrides::watch_config_file`] only reports that the file
+//! changed. Callers rebuild their config with the same flags they started
+//! with, so the reloaded values go through the regular layered merge and
+//! higher-precedence sources keep winning over the file.
+
+use crate::CliConfigOverrides;
+use codex_core::config::CONFIG_TOML_FILE;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Watcher;
+use std::path::Path;
+
+/// Keeps the watch running; dropping it stops further notifications.
+pub struct ConfigFileWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl CliConfigOverrides {
+    /// Watch the file the config loader reads as the user layer
+    /// (`--config-file`, or `config.toml` in `codex_home`) and call
+    /// `on_change` after each edit to it.
+    pub fn watch_config_file(
+        &self,
+        codex_home: &Path,
+        on_change: impl Fn() + Send + 'static,
+    ) -> Result<ConfigFileWatcher, String> {
+        let path = self
+            .loader_overrides()?
+            .config_file
+            .unwrap_or_else(|| codex_home.join(CONFIG_TOML_FILE));
+        let file_name = path.file_name().map(ToOwned::to_owned);
+
+        let watch_error =
+            |err: notify::Error| format!("Failed to watch config file `{}`: {err}", path.display());
+        // Watch the parent directory: editors often save by replacing the file,
+        // which would silently end a watch on the file itself.
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let Ok(event) = res else {
+                return;
+            };
+            if event
+                .paths
+                .iter()
+                .any(|changed| changed.file_name() == file_name.as_deref())
+            {
+                on_change();
+            }
+        })
+        .map_err(watch_error)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+
+        Ok(ConfigFileWatcher { _watcher: watcher })
+    }
+}
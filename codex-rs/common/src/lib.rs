@@ -21,6 +21,15 @@ mod config_override;
 
 #[cfg(feature = "cli")]
 pub use config_override::CliConfigOverrides;
+#[cfg(feature = "cli")]
+pub use config_override::OverrideParseError;
+#[cfg(feature = "cli")]
+pub use config_override::load_config_dir;
+#[cfg(feature = "cli")]
+pub use config_override::parse_override;
+
+#[cfg(feature = "config_watch")]
+pub mod config_watch;
 
 mod sandbox_summary;
 
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt::Debug;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
@@ -90,9 +92,12 @@ use crate::error::Result as CodexResult;
 use crate::exec::StreamOutput;
 use crate::exec_policy::ExecPolicyUpdateError;
 use crate::feedback_tags;
+use crate::git_info::collect_worktree_status;
 use crate::mcp::auth::compute_auth_statuses;
 use crate::mcp_connection_manager::McpConnectionManager;
 use crate::model_provider_info::CHAT_WIRE_API_DEPRECATION_SUMMARY;
+use crate::pending_tools;
+use crate::pending_tools::PendingToolHook;
 use crate::pending_tools::PendingToolManager;
 use crate::pending_tools::PendingToolMetadata;
 use crate::project_doc::get_user_instructions;
@@ -143,10 +148,15 @@ use crate::tasks::SessionTask;
 use crate::tasks::SessionTaskContext;
 use crate::tools::ToolRouter;
 use crate::tools::context::SharedTurnDiffTracker;
+use crate::tools::hooks::SnapshotRedactor;
 use crate::tools::hooks::StopHook;
 use crate::tools::hooks::StopHookEvent;
+use crate::tools::hooks::ToolCallSnapshot;
 use crate::tools::hooks::ToolHook;
+use crate::tools::hooks::ToolHookEvent;
+use crate::tools::hooks::resolve_hook_path;
 use crate::tools::parallel::ToolCallRuntime;
+use crate::tools::rate_limit::ShellRateLimiter;
 use crate::tools::sandboxing::ApprovalStore;
 use crate::tools::spec::ToolsConfig;
 use crate::tools::spec::ToolsConfigParams;
@@ -383,7 +393,15 @@ pub(crate) struct TurnContext {
     pub(crate) tool_call_gate: Arc<ReadinessFlag>,
     pub(crate) truncation_policy: TruncationPolicy,
     pub(crate) tool_hook: Option<ToolHook>,
+    /// Set once a `ToolHookMode::OncePerTurn` before-hook has run. Lives here
+    /// rather than on the router, which is rebuilt for every sampling request.
+    pub(crate) tool_hook_before_fired: AtomicBool,
     pub(crate) stop_hook: Option<StopHook>,
+    pub(crate) shell_calls_per_minute: Option<u32>,
+    pub(crate) tool_default_timeouts_ms: HashMap<String, u64>,
+    pub(crate) tool_dry_run: bool,
+    pub(crate) tool_hook_redactor: SnapshotRedactor,
+    pub(crate) enabled_tools: Option<HashSet<String>>,
 }
 
 impl TurnContext {
@@ -498,13 +516,29 @@ impl Session {
     pub(crate) async fn mark_tool_pending(
         &self,
         turn: &Arc<TurnContext>,
-        call_id: String,
-        tool_name: String,
+        call: ToolCallSnapshot,
         note: Option<String>,
     ) {
+        // Remember the turn's hook so it still hears about the delivered
+        // result after the turn has moved on.
+        let hook = turn
+            .tool_hook
+            .clone()
+            .filter(|hook| hook.applies_to(call.tool_name()))
+            .map(|hook| PendingToolHook {
+                hook,
+                call: call.clone().redacted(&turn.tool_hook_redactor),
+                sandbox_policy: turn.sandbox_policy.clone(),
+            });
         let metadata = self
             .pending_tools
-            .register(call_id, tool_name, turn.sub_id.clone(), note)
+            .register(
+                call.call_id().to_string(),
+                call.tool_name().to_string(),
+                turn.sub_id.clone(),
+                note,
+                hook,
+            )
             .await;
         let event = EventMsg::PendingToolState(PendingToolStateEvent {
             call_id: metadata.call_id,
@@ -526,27 +560,125 @@ impl Session {
         self.pending_tools.take_receiver(call_id).await
     }
 
+    /// Await the result for a pending tool call. Resolves to `None` when the
+    /// turn is cancelled or the pending entry is cancelled before delivery.
+    pub(crate) async fn wait_for_pending_tool(
+        &self,
+        call_id: &str,
+        cancellation_token: &CancellationToken,
+    ) -> Option<FunctionCallOutputPayload> {
+        let (_, receiver) = self.take_pending_tool_receiver(call_id).await?;
+        pending_tools::wait_for_result(receiver, cancellation_token).await
+    }
+
+    /// Cancel every pending tool call registered by `turn`, unblocking any
+    /// waiters and emitting a `Cancelled` state for each.
+    pub(crate) async fn cancel_pending_tools(&self, turn: &TurnContext) {
+        for metadata in self.pending_tools.cancel_turn(&turn.sub_id).await {
+            let event = EventMsg::PendingToolState(PendingToolStateEvent {
+                call_id: metadata.call_id,
+                tool_name: metadata.tool_name,
+                turn_id: metadata.turn_id,
+                status: PendingToolStatus::Cancelled,
+                note: metadata.note,
+            });
+            self.send_event(turn, event).await;
+        }
+    }
+
+    /// Re-announce a pending call as waiting with interim output as its note
+    /// so UIs can show progress. The call is not resolved.
+    pub(crate) async fn update_pending_tool_interim(
+        &self,
+        call_id: &str,
+        payload: FunctionCallOutputPayload,
+    ) -> Option<PendingToolMetadata> {
+        let metadata = self.pending_tools.metadata(call_id).await?;
+        let event = EventMsg::PendingToolState(PendingToolStateEvent {
+            call_id: metadata.call_id.clone(),
+            tool_name: metadata.tool_name.clone(),
+            turn_id: metadata.turn_id.clone(),
+            status: PendingToolStatus::Waiting,
+            note: Some(payload.content),
+        });
+        self.send_event_raw(Event {
+            id: metadata.turn_id.clone(),
+            msg: event,
+        })
+        .await;
+        Some(metadata)
+    }
+
     pub(crate) async fn complete_pending_tool(
         &self,
         call_id: &str,
         payload: FunctionCallOutputPayload,
     ) -> Option<PendingToolMetadata> {
-        let metadata = self.pending_tools.resolve(call_id, payload).await;
-        if let Some(meta) = &metadata {
-            let event = EventMsg::PendingToolState(PendingToolStateEvent {
-                call_id: meta.call_id.clone(),
-                tool_name: meta.tool_name.clone(),
-                turn_id: meta.turn_id.clone(),
-                status: PendingToolStatus::Resolved,
-                note: None,
+        let (metadata, delivered) = self.pending_tools.resolve(call_id, payload).await?;
+        let event = EventMsg::PendingToolState(PendingToolStateEvent {
+            call_id: metadata.call_id.clone(),
+            tool_name: metadata.tool_name.clone(),
+            turn_id: metadata.turn_id.clone(),
+            status: PendingToolStatus::Resolved,
+            note: None,
+        });
+        self.send_event_raw(Event {
+            id: metadata.turn_id.clone(),
+            msg: event,
+        })
+        .await;
+        // Runs off the submission loop so a slow hook cannot stall other ops.
+        if let Some(pending) = metadata.hook.clone() {
+            tokio::spawn(async move {
+                pending
+                    .hook
+                    .emit(
+                        ToolHookEvent::replaced(pending.call, delivered)
+                            .with_sandbox(&pending.sandbox_policy),
+                    )
+                    .await;
             });
-            self.send_event_raw(Event {
-                id: meta.turn_id.clone(),
-                msg: event,
-            })
-            .await;
         }
-        metadata
+        Some(metadata)
+    }
+
+    /// Build the tool hook once per session: resolving the program (and
+    /// warning when it is missing) every turn would repeat the warning.
+    fn build_tool_hook(config: &Config) -> Option<ToolHook> {
+        config
+            .tool_hook_command
+            .clone()
+            .and_then(|command| ToolHook::new_in(command, &config.codex_home))
+            .or_else(|| config.tool_hook_debug_echo.then(ToolHook::debug_echo))
+            .map(|hook| {
+                hook.with_mode(config.tool_hook_mode)
+                    .with_phases(&config.tool_hook_phases)
+                    .with_format(config.tool_hook_format)
+                    .with_stderr(config.hook_stderr)
+                    .with_env_policy(config.hook_environment_policy.clone())
+                    .with_tool_filter(&config.tool_hook_include, &config.tool_hook_exclude)
+            })
+    }
+
+    /// See [`Session::build_tool_hook`].
+    fn build_stop_hook(config: &Config) -> Option<StopHook> {
+        config
+            .stop_hook_socket
+            .clone()
+            .map(StopHook::daemon)
+            .or_else(|| {
+                config
+                    .stop_hook_command
+                    .clone()
+                    .and_then(|command| StopHook::new_in(command, &config.codex_home))
+            })
+            .map(|hook| {
+                hook.with_response_items(config.stop_hook_response_items)
+                    .with_stderr(config.hook_stderr)
+                    .with_git_status(config.stop_hook_git_status)
+                    .with_final_message_max_chars(config.stop_hook_final_message_max_chars)
+                    .with_env_policy(config.hook_environment_policy.clone())
+            })
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -559,6 +691,8 @@ impl Session {
         model_family: ModelFamily,
         conversation_id: ConversationId,
         sub_id: String,
+        tool_hook: Option<ToolHook>,
+        stop_hook: Option<StopHook>,
     ) -> TurnContext {
         let otel_manager = otel_manager.clone().with_model(
             session_configuration.model.as_str(),
@@ -604,14 +738,14 @@ impl Session {
                 per_turn_config.as_ref(),
                 model_family.truncation_policy,
             ),
-            tool_hook: per_turn_config
-                .tool_hook_command
-                .clone()
-                .and_then(ToolHook::new),
-            stop_hook: per_turn_config
-                .stop_hook_command
-                .clone()
-                .and_then(StopHook::new),
+            tool_hook,
+            tool_hook_before_fired: AtomicBool::new(false),
+            stop_hook,
+            shell_calls_per_minute: per_turn_config.shell_calls_per_minute,
+            tool_default_timeouts_ms: per_turn_config.tool_default_timeouts_ms.clone(),
+            tool_dry_run: per_turn_config.tool_dry_run,
+            tool_hook_redactor: per_turn_config.tool_hook_redactor.clone(),
+            enabled_tools: per_turn_config.enabled_tools.clone(),
         }
     }
 
@@ -747,6 +881,9 @@ impl Session {
             models_manager: Arc::clone(&models_manager),
             tool_approvals: Mutex::new(ApprovalStore::default()),
             skills_manager,
+            shell_rate_limiter: ShellRateLimiter::default(),
+            tool_hook: Session::build_tool_hook(&config),
+            stop_hook: Session::build_stop_hook(&config),
         };
 
         let sess = Arc::new(Session {
@@ -1064,6 +1201,8 @@ impl Session {
             model_family,
             self.conversation_id,
             sub_id,
+            self.services.tool_hook.clone(),
+            self.services.stop_hook.clone(),
         );
         if let Some(final_schema) = final_output_json_schema {
             turn_context.final_output_json_schema = final_schema;
@@ -1683,12 +1822,13 @@ impl Session {
         server: &str,
         tool: &str,
         arguments: Option<serde_json::Value>,
+        meta: Option<serde_json::Map<String, serde_json::Value>>,
     ) -> anyhow::Result<CallToolResult> {
         self.services
             .mcp_connection_manager
             .read()
             .await
-            .call_tool(server, tool, arguments)
+            .call_tool(server, tool, arguments, meta)
             .await
     }
 
@@ -1817,8 +1957,15 @@ async fn submission_loop(sess: Arc<Session>, config: Arc<Config>, rx_sub: Receiv
             Op::Review { review_request } => {
                 handlers::review(&sess, &config, id.clone(), review_request).await;
             }
-            Op::DeliverPendingToolResult { call_id, output } => {
-                handlers::deliver_pending_tool_result(&sess, call_id, output).await;
+            Op::DeliverPendingToolResult {
+                call_id,
+                output,
+                keep_pending,
+            } => {
+                handlers::deliver_pending_tool_result(&sess, call_id, output, keep_pending).await;
+            }
+            Op::AppendPendingToolOutput { call_id, chunk } => {
+                handlers::append_pending_tool_output(&sess, call_id, chunk).await;
             }
             _ => {} // Ignore unknown ops; enum is non_exhaustive to allow extensions.
         }
@@ -2200,12 +2347,29 @@ mod handlers {
         sess: &Arc<Session>,
         call_id: String,
         output: FunctionCallOutputPayload,
+        keep_pending: bool,
     ) {
+        if keep_pending {
+            if sess
+                .update_pending_tool_interim(&call_id, output)
+                .await
+                .is_none()
+            {
+                warn!("interim pending tool result received for unknown call_id {call_id}");
+            }
+            return;
+        }
         if sess.complete_pending_tool(&call_id, output).await.is_none() {
             warn!("pending tool result received for unknown call_id {call_id}");
         }
     }
 
+    pub async fn append_pending_tool_output(sess: &Arc<Session>, call_id: String, chunk: String) {
+        if sess.pending_tools.append(&call_id, &chunk).await.is_none() {
+            warn!("pending tool output chunk received for unknown call_id {call_id}");
+        }
+    }
+
     pub async fn shutdown(sess: &Arc<Session>, sub_id: String) -> bool {
         crate::codex::shutdown_session(sess, sub_id).await
     }
@@ -2351,7 +2515,13 @@ async fn spawn_review_thread(
         tool_call_gate: Arc::new(ReadinessFlag::new()),
         truncation_policy: TruncationPolicy::new(&per_turn_config, model_family.truncation_policy),
         tool_hook: parent_turn_context.tool_hook.clone(),
+        tool_hook_before_fired: AtomicBool::new(false),
         stop_hook: parent_turn_context.stop_hook.clone(),
+        shell_calls_per_minute: parent_turn_context.shell_calls_per_minute,
+        tool_default_timeouts_ms: parent_turn_context.tool_default_timeouts_ms.clone(),
+        tool_dry_run: parent_turn_context.tool_dry_run,
+        tool_hook_redactor: parent_turn_context.tool_hook_redactor.clone(),
+        enabled_tools: parent_turn_context.enabled_tools.clone(),
     };
 
     // Seed the child task with the review prompt as the initial user message.
@@ -2515,19 +2685,7 @@ pub(crate) async fn run_task(
 
                 if !needs_follow_up {
                     last_agent_message = turn_last_agent_message;
-                    if let Some(stop_hook) = turn_context.stop_hook.as_ref() {
-                        let response_items = sess.clone_history().await.get_history();
-                        let token_usage = sess.latest_token_usage().await;
-                        let event = StopHookEvent::new(
-                            sess.conversation_id.to_string(),
-                            turn_context.sub_id.clone(),
-                            turn_context.cwd.display().to_string(),
-                            last_agent_message.clone(),
-                            response_items,
-                            token_usage,
-                        );
-                        stop_hook.emit(event).await;
-                    }
+                    emit_stop_hook(&sess, &turn_context, last_agent_message.clone(), None).await;
                     sess.notifier()
                         .notify(&UserNotification::AgentTurnComplete {
                             thread_id: sess.conversation_id.to_string(),
@@ -2553,6 +2711,7 @@ pub(crate) async fn run_task(
             }
             Err(e) => {
                 info!("Turn error: {e:#}");
+                emit_stop_hook(&sess, &turn_context, None, Some(e.to_string())).await;
                 let event = EventMsg::Error(e.to_error_event(None));
                 sess.send_event(&turn_context, event).await;
                 // let the user continue the conversation
@@ -2564,6 +2723,82 @@ pub(crate) async fn run_task(
     last_agent_message
 }
 
+/// Run the turn's stop hook, if any. Called when a turn finishes normally and
+/// when it ends in an error, so audit hooks see every turn.
+async fn emit_stop_hook(
+    sess: &Arc<Session>,
+    turn_context: &Arc<TurnContext>,
+    final_message: Option<String>,
+    error: Option<String>,
+) {
+    let Some(stop_hook) = turn_context.stop_hook.as_ref() else {
+        return;
+    };
+    let response_items = sess.clone_history().await.get_history();
+    let token_usage = sess.latest_token_usage().await;
+    let git = if stop_hook.includes_git_status() {
+        collect_worktree_status(&turn_context.cwd).await
+    } else {
+        None
+    };
+    let event = StopHookEvent::new(
+        sess.conversation_id.to_string(),
+        turn_context.sub_id.clone(),
+        turn_context.cwd.display().to_string(),
+        final_message,
+        response_items,
+        stop_hook.response_items(),
+        token_usage,
+    )
+    .with_error(error)
+    .with_git(git)
+    .with_final_message_limit(stop_hook.final_message_max_chars());
+    let Some(export_to) = stop_hook
+        .emit(event)
+        .await
+        .and_then(|directive| directive.export_to)
+    else {
+        return;
+    };
+    let Some(export_to) = resolve_hook_path(&export_to, &turn_context.cwd) else {
+        warn!(
+            "path" = %export_to.display(),
+            "ignoring stop hook export outside of the working directory"
+        );
+        return;
+    };
+    if let Err(err) = export_conversation(sess, &export_to).await {
+        warn!(
+            "path" = %export_to.display(),
+            "error" = %err,
+            "failed to export conversation requested by stop hook"
+        );
+    }
+}
+
+/// Write the session's rollout to `path`. Sessions without a rollout (for
+/// example ephemeral ones) get their history as JSON lines instead.
+async fn export_conversation(sess: &Session, path: &Path) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    sess.flush_rollout().await;
+    let rollout_path = {
+        let guard = sess.services.rollout.lock().await;
+        guard.as_ref().map(|rec| rec.rollout_path.clone())
+    };
+    if let Some(rollout_path) = rollout_path {
+        tokio::fs::copy(&rollout_path, path).await?;
+        return Ok(());
+    }
+    let mut contents = Vec::new();
+    for item in sess.clone_history().await.get_history() {
+        serde_json::to_writer(&mut contents, &item).map_err(std::io::Error::other)?;
+        contents.push(b'\n');
+    }
+    tokio::fs::write(path, contents).await
+}
+
 async fn run_auto_compact(sess: &Arc<Session>, turn_context: &Arc<TurnContext>) {
     if should_use_remote_compact_task(sess.as_ref(), &turn_context.client.get_provider()) {
         run_inline_remote_auto_compact_task(Arc::clone(sess), Arc::clone(turn_context)).await;
@@ -2595,15 +2830,20 @@ async fn run_turn(
         .list_all_tools()
         .or_cancel(&cancellation_token)
         .await?;
-    let router = Arc::new(ToolRouter::from_config(
-        &turn_context.tools_config,
-        Some(
-            mcp_tools
-                .into_iter()
-                .map(|(name, tool)| (name, tool.tool))
-                .collect(),
-        ),
-    ));
+    let router = Arc::new(
+        ToolRouter::from_config(
+            &turn_context.tools_config,
+            Some(
+                mcp_tools
+                    .into_iter()
+                    .map(|(name, tool)| (name, tool.tool))
+                    .collect(),
+            ),
+        )
+        .with_default_timeouts(turn_context.tool_default_timeouts_ms.clone())
+        .with_dry_run(turn_context.tool_dry_run)
+        .with_snapshot_redactor(turn_context.tool_hook_redactor.clone()),
+    );
 
     let model_supports_parallel = turn_context
         .client
@@ -2612,7 +2852,10 @@ async fn run_turn(
 
     let prompt = Prompt {
         input,
-        tools: router.specs(),
+        tools: match &turn_context.enabled_tools {
+            Some(names) => router.specs_for(names),
+            None => router.specs(),
+        },
         parallel_tool_calls: model_supports_parallel && sess.enabled(Feature::ParallelToolCalls),
         base_instructions_override: turn_context.base_instructions.clone(),
         output_schema: turn_context.final_output_json_schema.clone(),
@@ -3001,6 +3244,7 @@ mod tests {
     use crate::tools::handlers::ShellHandler;
     use crate::tools::handlers::UnifiedExecHandler;
     use crate::tools::registry::ToolHandler;
+    use crate::tools::router::ToolCall;
     use crate::turn_diff_tracker::TurnDiffTracker;
     use codex_app_server_protocol::AuthMode;
     use codex_protocol::models::ContentItem;
@@ -3563,6 +3807,9 @@ mod tests {
             models_manager: Arc::clone(&models_manager),
             tool_approvals: Mutex::new(ApprovalStore::default()),
             skills_manager,
+            shell_rate_limiter: ShellRateLimiter::default(),
+            tool_hook: Session::build_tool_hook(&config),
+            stop_hook: Session::build_stop_hook(&config),
         };
 
         let turn_context = Session::make_turn_context(
@@ -3574,6 +3821,8 @@ mod tests {
             model_family,
             conversation_id,
             "turn_id".to_string(),
+            services.tool_hook.clone(),
+            services.stop_hook.clone(),
         );
 
         let session = Session {
@@ -3645,6 +3894,9 @@ mod tests {
             models_manager: Arc::clone(&models_manager),
             tool_approvals: Mutex::new(ApprovalStore::default()),
             skills_manager,
+            shell_rate_limiter: ShellRateLimiter::default(),
+            tool_hook: Session::build_tool_hook(&config),
+            stop_hook: Session::build_stop_hook(&config),
         };
 
         let turn_context = Session::make_turn_context(
@@ -3656,6 +3908,8 @@ mod tests {
             model_family,
             conversation_id,
             "turn_id".to_string(),
+            services.tool_hook.clone(),
+            services.stop_hook.clone(),
         );
 
         let session = Session {
@@ -3733,6 +3987,9 @@ mod tests {
             models_manager: Arc::clone(&models_manager),
             tool_approvals: Mutex::new(ApprovalStore::default()),
             skills_manager,
+            shell_rate_limiter: ShellRateLimiter::default(),
+            tool_hook: Session::build_tool_hook(&config),
+            stop_hook: Session::build_stop_hook(&config),
         };
 
         let turn_context = Arc::new(Session::make_turn_context(
@@ -3744,6 +4001,8 @@ mod tests {
             model_family,
             conversation_id,
             "turn_id".to_string(),
+            services.tool_hook.clone(),
+            services.stop_hook.clone(),
         ));
 
         let session = Arc::new(Session {
@@ -3974,6 +4233,135 @@ mod tests {
         }
     }
 
+    /// Tool hook that runs `script` under `sh -c` with `args` bound to `$0`,
+    /// `$1`, and so on.
+    #[cfg(unix)]
+    fn sh_tool_hook(script: &str, args: &[&Path]) -> Option<ToolHook> {
+        let mut command = vec!["sh".to_string(), "-c".to_string(), script.to_string()];
+        command.extend(args.iter().map(|arg| arg.to_string_lossy().into_owned()));
+        ToolHook::new(command)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn delivering_pending_result_fires_replaced_hook_event() {
+        let (session, mut turn_context, _rx) = make_session_and_context_with_rx().await;
+        let hook_dir = tempfile::tempdir().expect("tempdir");
+        let log_path = hook_dir.path().join("hook.json");
+        Arc::get_mut(&mut turn_context)
+            .expect("turn context is not shared")
+            .tool_hook = sh_tool_hook(r#"cat > "$0.tmp" && mv "$0.tmp" "$0""#, &[&log_path]);
+        let call = ToolCall {
+            tool_name: "wait_for_reply".to_string(),
+            call_id: "call-pending".to_string(),
+            payload: ToolPayload::Function {
+                arguments: "{}".to_string(),
+            },
+        };
+        session
+            .mark_tool_pending(&turn_context, ToolCallSnapshot::from_call(&call), None)
+            .await;
+
+        session
+            .complete_pending_tool(
+                "call-pending",
+                FunctionCallOutputPayload {
+                    content: "approved by ops".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("pending call");
+
+        // The replaced event is emitted in the background.
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !log_path.exists() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("hook ran");
+        let event: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&log_path).expect("read hook log"))
+                .expect("hook event");
+        assert_eq!(
+            event,
+            json!({
+                "phase": "replaced",
+                "call": {
+                    "tool_name": "wait_for_reply",
+                    "call_id": "call-pending",
+                    "payload": {
+                        "kind": "function",
+                        "arguments": "{}",
+                        "parsed_arguments": {},
+                    },
+                },
+                "parallel": false,
+                "dry_run": false,
+                "sandbox": turn_context.sandbox_policy.to_string(),
+                "outcome": {
+                    "success": {
+                        "response": {
+                            "type": "function_call_output",
+                            "call_id": "call-pending",
+                            "output": "approved by ops",
+                        },
+                    },
+                },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn interim_pending_result_is_announced_without_resolving_the_call() {
+        let (session, turn_context, rx) = make_session_and_context_with_rx().await;
+        let call = ToolCall {
+            tool_name: "wait_for_reply".to_string(),
+            call_id: "call-pending".to_string(),
+            payload: ToolPayload::Function {
+                arguments: "{}".to_string(),
+            },
+        };
+        session
+            .mark_tool_pending(&turn_context, ToolCallSnapshot::from_call(&call), None)
+            .await;
+        let (_, mut receiver) = session
+            .take_pending_tool_receiver("call-pending")
+            .await
+            .expect("receiver");
+
+        session
+            .update_pending_tool_interim(
+                "call-pending",
+                FunctionCallOutputPayload {
+                    content: "2 of 3 reviewers approved".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("pending call");
+
+        let states: Vec<(PendingToolStatus, Option<String>)> =
+            std::iter::from_fn(|| rx.try_recv().ok())
+                .filter_map(|event| match event.msg {
+                    EventMsg::PendingToolState(state) => Some((state.status, state.note)),
+                    _ => None,
+                })
+                .collect();
+        assert_eq!(
+            states,
+            vec![
+                (PendingToolStatus::Waiting, None),
+                (
+                    PendingToolStatus::Waiting,
+                    Some("2 of 3 reviewers approved".to_string())
+                ),
+            ]
+        );
+        assert!(receiver.try_recv().is_err());
+    }
+
     fn sample_rollout(
         session: &Session,
         turn_context: &TurnContext,
@@ -2,6 +2,7 @@ use crate::auth::AuthCredentialsStoreMode;
 use crate::config::types::CustomToolToml;
 use crate::config::types::DEFAULT_OTEL_ENVIRONMENT;
 use crate::config::types::History;
+use crate::config::types::HookStderr;
 use crate::config::types::McpServerConfig;
 use crate::config::types::Notice;
 use crate::config::types::Notifications;
@@ -12,6 +13,10 @@ use crate::config::types::SandboxWorkspaceWrite;
 use crate::config::types::ScrollInputMode;
 use crate::config::types::ShellEnvironmentPolicy;
 use crate::config::types::ShellEnvironmentPolicyToml;
+use crate::config::types::StopHookResponseItems;
+use crate::config::types::ToolHookFormat;
+use crate::config::types::ToolHookMode;
+use crate::config::types::ToolHookStage;
 use crate::config::types::Tui;
 use crate::config::types::UriBasedFileOpener;
 use crate::config_loader::ConfigLayerStack;
@@ -31,6 +36,7 @@ use crate::project_doc::DEFAULT_PROJECT_DOC_FILENAME;
 use crate::project_doc::LOCAL_PROJECT_DOC_FILENAME;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
+use crate::tools::hooks::SnapshotRedactor;
 use codex_app_server_protocol::Tools;
 use codex_app_server_protocol::UserSavedConfig;
 use codex_protocol::config_types::ForcedLoginMethod;
@@ -76,7 +82,6 @@ pub use service::ConfigService;
 pub use service::ConfigServiceError;
 
 static CODEX_HOME_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
-static CONFIG_FILE_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
 const OPENAI_DEFAULT_REVIEW_MODEL: &str = "gpt-5.1-codex-max";
 
 pub use codex_git::GhostSnapshotConfig;
@@ -288,12 +293,77 @@ pub struct Config {
     /// `tool_hook_command = ["python3", "~/capture_tool_calls.py"]`
     pub tool_hook_command: Option<Vec<String>>,
 
+    /// Log every tool hook event through `tracing` instead of running a
+    /// command. Ignored when `tool_hook_command` is set.
+    pub tool_hook_debug_echo: bool,
+
+    /// Whether the `before_execution` tool hook runs for every call or only
+    /// the first call of each turn.
+    pub tool_hook_mode: ToolHookMode,
+
+    /// Which tool hook phases spawn the hook. Defaults to both.
+    pub tool_hook_phases: Vec<ToolHookStage>,
+
+    /// How events are passed to the tool hook: JSON on stdin, or scalar
+    /// fields as environment variables.
+    pub tool_hook_format: ToolHookFormat,
+
+    /// Environment for tool and stop hook subprocesses. `None` passes the
+    /// agent's full environment through unchanged.
+    pub hook_environment_policy: Option<ShellEnvironmentPolicy>,
+
+    /// Tool names (exact or `*` globs) the tool hook runs for. Empty means
+    /// every tool.
+    pub tool_hook_include: Vec<String>,
+
+    /// Tool names (exact or `*` globs) the tool hook never runs for. Takes
+    /// precedence over `tool_hook_include`.
+    pub tool_hook_exclude: Vec<String>,
+
+    /// Masks secrets in the tool call snapshots sent to tool hooks and
+    /// dispatch observers.
+    pub tool_hook_redactor: SnapshotRedactor,
+
+    /// Maximum number of shell commands a conversation may start within any
+    /// one-minute window. `None` means unlimited.
+    pub shell_calls_per_minute: Option<u32>,
+
+    /// Timeout (ms) applied to calls of the named tools when the model did not
+    /// pass one and the tool hook did not override it.
+    pub tool_default_timeouts_ms: HashMap<String, u64>,
+
+    /// Preview tool calls instead of running them: hooks see both phases
+    /// flagged `dry_run` and the model receives a placeholder output.
+    pub tool_dry_run: bool,
+
+    /// When set, advertise only the tools with these names to the model.
+    pub enabled_tools: Option<HashSet<String>>,
+
     /// When set, Codex will invoke this command once per turn right after the final
     /// assistant response is produced. The hook receives a JSON payload containing
     /// the conversation/turn identifiers, final message text, response items, and
     /// latest token usage snapshot.
     pub stop_hook_command: Option<Vec<String>>,
 
+    /// Unix socket of a long-lived stop hook process. When set, stop events
+    /// are streamed there instead of spawning `stop_hook_command`.
+    pub stop_hook_socket: Option<PathBuf>,
+
+    /// Which response items are forwarded to `stop_hook_command`.
+    pub stop_hook_response_items: StopHookResponseItems,
+
+    /// Whether stop hook payloads include the git branch and `git status
+    /// --porcelain` lines of the turn's working directory.
+    pub stop_hook_git_status: bool,
+
+    /// Longest `final_message` (in characters) sent to `stop_hook_command`;
+    /// longer messages are cut and flagged. `None` sends it whole.
+    pub stop_hook_final_message_max_chars: Option<usize>,
+
+    /// Whether hook commands inherit Codex's stderr or have it captured and
+    /// forwarded through `tracing`.
+    pub hook_stderr: HookStderr,
+
     /// Directory containing all Codex state (defaults to `~/.codex` but can be
     /// overridden by the `CODEX_HOME` environment variable).
     pub codex_home: PathBuf,
@@ -389,14 +459,64 @@ pub struct Config {
 pub struct CustomToolConfig {
     pub name: String,
     pub command: Vec<String>,
+    /// Disabled tools are neither advertised to the model nor runnable.
+    pub enabled: bool,
     pub description: Option<String>,
     pub parameters: JsonValue,
+    /// Argument names a call must provide; calls missing any are rejected
+    /// before the command runs.
+    pub required: Vec<String>,
     pub cwd: Option<String>,
     pub env: HashMap<String, String>,
+    /// Patterns filtering the inherited environment down to matching names.
+    /// Empty keeps everything the shell environment policy allows.
+    pub env_include: Vec<String>,
+    /// Patterns for inherited variables removed before `env` is applied.
+    pub env_exclude: Vec<String>,
+    /// Patterns for parent-process variables copied into the tool's
+    /// environment as-is, bypassing the shell environment policy and
+    /// `env_include`/`env_exclude`.
+    pub env_passthrough: Vec<String>,
+    /// `.env`-style file (relative to the turn cwd) whose variables override
+    /// inherited ones but yield to `env`.
+    pub env_file: Option<String>,
     pub timeout_ms: Option<u64>,
     pub with_escalated_permissions: Option<bool>,
     pub parallel: bool,
     pub hibernate_after_call: bool,
+    /// Write the output of a successful run to a file under
+    /// `.codex/tool-output/` in the turn cwd (or the temp dir when the sandbox
+    /// cannot write there) and return a short message with its path instead.
+    pub output_as_file: bool,
+    /// Longest output (in bytes) returned to the model; longer output is cut
+    /// and ends with a `[truncated N bytes]` marker.
+    pub max_output_bytes: Option<usize>,
+}
+
+/// Matches an entry in `config.toml` that only sets `command`.
+impl Default for CustomToolConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            command: Vec::new(),
+            enabled: true,
+            description: None,
+            parameters: default_custom_tool_parameters(),
+            required: Vec::new(),
+            cwd: None,
+            env: HashMap::new(),
+            env_include: Vec::new(),
+            env_exclude: Vec::new(),
+            env_passthrough: Vec::new(),
+            env_file: None,
+            timeout_ms: None,
+            with_escalated_permissions: None,
+            parallel: false,
+            hibernate_after_call: false,
+            output_as_file: false,
+            max_output_bytes: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -501,12 +621,13 @@ pub async fn load_config_as_toml_with_cli_overrides(
     codex_home: &Path,
     cwd: &AbsolutePathBuf,
     cli_overrides: Vec<(String, TomlValue)>,
+    loader_overrides: LoaderOverrides,
 ) -> std::io::Result<ConfigToml> {
     let config_layer_stack = load_config_layers_state(
         codex_home,
         Some(cwd.clone()),
         &cli_overrides,
-        LoaderOverrides::default(),
+        loader_overrides,
     )
     .await?;
 
@@ -808,9 +929,69 @@ pub struct ConfigToml {
     /// Command to run before/after each tool call.
     pub tool_hook_command: Option<Vec<String>>,
 
+    /// Log tool hook events instead of running a command, to see their shape.
+    pub tool_hook_debug_echo: Option<bool>,
+
+    /// Set to `"once-per-turn"` to only run the before-hook on the first tool
+    /// call of each turn.
+    pub tool_hook_mode: Option<ToolHookMode>,
+
+    /// Set to `["before"]` or `["after"]` to only run the tool hook for that
+    /// phase.
+    pub tool_hook_phases: Option<Vec<ToolHookStage>>,
+
+    /// Set to `"env"` to receive the phase, tool name and call id as
+    /// `CODEX_HOOK_*` environment variables instead of inside the JSON event.
+    pub tool_hook_format: Option<ToolHookFormat>,
+
+    /// Build hook subprocess environments like `shell_environment_policy`
+    /// instead of passing the full environment through.
+    pub hook_environment_policy: Option<ShellEnvironmentPolicyToml>,
+
+    /// Only run the tool hook for these tool names, e.g. `["mcp__github__*"]`.
+    pub tool_hook_include: Option<Vec<String>>,
+
+    /// Never run the tool hook for these tool names.
+    pub tool_hook_exclude: Option<Vec<String>>,
+
+    /// Regexes whose matches are masked in tool call snapshots given to hooks.
+    pub tool_hook_redact_patterns: Option<Vec<String>>,
+
+    /// JSON argument keys whose values are masked in tool call snapshots.
+    pub tool_hook_redact_keys: Option<Vec<String>>,
+
+    /// Reject shell commands beyond this many per minute in a conversation.
+    pub shell_calls_per_minute: Option<u32>,
+
+    /// Default `timeout_ms` per tool name, e.g. `{ shell_command = 30000 }`.
+    pub tool_default_timeouts_ms: Option<HashMap<String, u64>>,
+
+    /// Skip executing tool calls while still running the tool hook.
+    pub tool_dry_run: Option<bool>,
+
+    /// Advertise only these tools to the model, e.g. `["shell", "apply_patch"]`.
+    pub enabled_tools: Option<Vec<String>>,
+
     /// Command to run once a turn completes (after the final assistant reply).
     pub stop_hook_command: Option<Vec<String>>,
 
+    /// Send stop events to the hook process listening on this Unix socket
+    /// instead of spawning `stop_hook_command`.
+    pub stop_hook_socket: Option<PathBuf>,
+
+    /// Limits the `response_items` sent to `stop_hook_command`.
+    pub stop_hook_response_items: Option<StopHookResponseItems>,
+
+    /// Add a `git` object (branch and dirty files) to stop hook payloads.
+    pub stop_hook_git_status: Option<bool>,
+
+    /// Truncate the stop hook's `final_message` to this many characters.
+    pub stop_hook_final_message_max_chars: Option<usize>,
+
+    /// Set to `"capture"` to log hook stderr through `tracing` instead of
+    /// inheriting Codex's stderr.
+    pub hook_stderr: Option<HookStderr>,
+
     /// Profile to use from the `profiles` map.
     pub profile: Option<String>,
 
@@ -1378,6 +1559,16 @@ impl Config {
 
         let check_for_update_on_startup = cfg.check_for_update_on_startup.unwrap_or(true);
         let custom_tools = build_custom_tools(cfg.custom_tools.clone())?;
+        let tool_hook_redactor = SnapshotRedactor::new(
+            cfg.tool_hook_redact_patterns.as_deref().unwrap_or_default(),
+            cfg.tool_hook_redact_keys.as_deref().unwrap_or_default(),
+        )
+        .map_err(|err| {
+            std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid tool_hook_redact_patterns entry: {err}"),
+            )
+        })?;
         let experimental_supported_tools = {
             let list = config_profile
                 .experimental_supported_tools
@@ -1458,10 +1649,75 @@ impl Config {
                 .tool_hook_command
                 .clone()
                 .or_else(|| cfg.tool_hook_command.clone()),
+            tool_hook_debug_echo: config_profile
+                .tool_hook_debug_echo
+                .or(cfg.tool_hook_debug_echo)
+                .unwrap_or(false),
+            tool_hook_mode: config_profile
+                .tool_hook_mode
+                .or(cfg.tool_hook_mode)
+                .unwrap_or_default(),
+            tool_hook_phases: config_profile
+                .tool_hook_phases
+                .clone()
+                .or_else(|| cfg.tool_hook_phases.clone())
+                .unwrap_or_else(|| vec![ToolHookStage::Before, ToolHookStage::After]),
+            tool_hook_format: config_profile
+                .tool_hook_format
+                .or(cfg.tool_hook_format)
+                .unwrap_or_default(),
+            hook_environment_policy: cfg.hook_environment_policy.clone().map(Into::into),
+            tool_hook_include: config_profile
+                .tool_hook_include
+                .clone()
+                .or_else(|| cfg.tool_hook_include.clone())
+                .unwrap_or_default(),
+            tool_hook_exclude: config_profile
+                .tool_hook_exclude
+                .clone()
+                .or_else(|| cfg.tool_hook_exclude.clone())
+                .unwrap_or_default(),
+            tool_hook_redactor,
+            shell_calls_per_minute: config_profile
+                .shell_calls_per_minute
+                .or(cfg.shell_calls_per_minute),
+            tool_default_timeouts_ms: config_profile
+                .tool_default_timeouts_ms
+                .clone()
+                .or_else(|| cfg.tool_default_timeouts_ms.clone())
+                .unwrap_or_default(),
+            tool_dry_run: config_profile
+                .tool_dry_run
+                .or(cfg.tool_dry_run)
+                .unwrap_or(false),
+            enabled_tools: config_profile
+                .enabled_tools
+                .clone()
+                .or_else(|| cfg.enabled_tools.clone())
+                .map(|names| names.into_iter().collect()),
             stop_hook_command: config_profile
                 .stop_hook_command
                 .clone()
                 .or_else(|| cfg.stop_hook_command.clone()),
+            stop_hook_socket: config_profile
+                .stop_hook_socket
+                .clone()
+                .or_else(|| cfg.stop_hook_socket.clone()),
+            stop_hook_response_items: config_profile
+                .stop_hook_response_items
+                .or(cfg.stop_hook_response_items)
+                .unwrap_or_default(),
+            stop_hook_git_status: config_profile
+                .stop_hook_git_status
+                .or(cfg.stop_hook_git_status)
+                .unwrap_or(false),
+            stop_hook_final_message_max_chars: config_profile
+                .stop_hook_final_message_max_chars
+                .or(cfg.stop_hook_final_message_max_chars),
+            hook_stderr: config_profile
+                .hook_stderr
+                .or(cfg.hook_stderr)
+                .unwrap_or_default(),
             codex_home,
             config_layer_stack,
             history,
@@ -1638,20 +1894,20 @@ pub fn find_codex_home() -> std::io::Result<PathBuf> {
     Ok(p)
 }
 
-/// Returns the config.toml path, honoring the `CODEX_CONFIG_FILE` override
-/// when present.
+/// Returns the path of `config.toml` in `codex_home`, which is where config
+/// writers persist their edits. A `--config-file` override only changes what
+/// the loader reads (see [`LoaderOverrides::config_file`]).
 pub fn config_file_path(codex_home: impl AsRef<Path>) -> PathBuf {
-    if let Some(path) = CONFIG_FILE_OVERRIDE.get() {
-        return path.clone();
-    }
+    codex_home.as_ref().join(CONFIG_TOML_FILE)
+}
 
-    if let Ok(val) = std::env::var("CODEX_CONFIG_FILE")
-        && !val.is_empty()
-    {
-        PathBuf::from(val)
-    } else {
-        codex_home.as_ref().join(CONFIG_TOML_FILE)
-    }
+/// Schema advertised for custom tools that don't declare `parameters`.
+fn default_custom_tool_parameters() -> JsonValue {
+    json!({
+        "type": "object",
+        "properties": {},
+        "additionalProperties": true
+    })
 }
 
 fn build_custom_tools(
@@ -1671,27 +1927,31 @@ fn build_custom_tools(
             .filter(|value| !value.trim().is_empty())
             .or_else(|| entry.command.first().map(|cmd| format!("Run {cmd}")));
 
-        let parameters = entry.parameters.unwrap_or_else(|| {
-            json!({
-                "type": "object",
-                "properties": {},
-                "additionalProperties": true
-            })
-        });
+        let parameters = entry
+            .parameters
+            .unwrap_or_else(default_custom_tool_parameters);
 
         tools.insert(
             name.clone(),
             CustomToolConfig {
                 name,
                 command: entry.command,
+                enabled: entry.enabled.unwrap_or(true),
                 description,
                 parameters,
+                required: entry.required.unwrap_or_default(),
                 cwd: entry.cwd,
                 env: entry.env.unwrap_or_default(),
+                env_include: entry.env_include.unwrap_or_default(),
+                env_exclude: entry.env_exclude.unwrap_or_default(),
+                env_passthrough: entry.env_passthrough.unwrap_or_default(),
+                env_file: entry.env_file,
                 timeout_ms: entry.timeout_ms,
                 with_escalated_permissions: entry.with_escalated_permissions,
                 parallel: entry.parallel.unwrap_or(false),
                 hibernate_after_call: entry.hibernate_after_call.unwrap_or(false),
+                output_as_file: entry.output_as_file.unwrap_or(false),
+                max_output_bytes: entry.max_output_bytes,
             },
         );
     }
@@ -1704,12 +1964,6 @@ pub fn set_codex_home_override(path: PathBuf) {
     let _ = CODEX_HOME_OVERRIDE.set(path);
 }
 
-/// Override the config.toml file path for the running process. The first
-/// invocation wins; subsequent calls are ignored.
-pub fn set_config_file_override(path: PathBuf) {
-    let _ = CONFIG_FILE_OVERRIDE.set(path);
-}
-
 /// Returns the path to the folder where Codex logs are stored. Does not verify
 /// that the directory exists.
 pub fn log_dir(cfg: &Config) -> std::io::Result<PathBuf> {
@@ -2039,6 +2293,39 @@ trust_level = "trusted"
         Ok(())
     }
 
+    #[test]
+    fn profile_enabled_tools_overrides_root_value() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+
+        let mut cfg = ConfigToml::default();
+        cfg.enabled_tools = Some(vec!["shell".to_string()]);
+        cfg.profile = Some("review".to_string());
+        let mut profiles_map = HashMap::new();
+        profiles_map.insert(
+            "review".to_string(),
+            ConfigProfile {
+                enabled_tools: Some(vec!["read_file".to_string(), "grep_files".to_string()]),
+                ..ConfigProfile::default()
+            },
+        );
+        cfg.profiles = profiles_map;
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+
+        assert_eq!(
+            config.enabled_tools,
+            Some(HashSet::from([
+                "read_file".to_string(),
+                "grep_files".to_string()
+            ]))
+        );
+        Ok(())
+    }
+
     #[test]
     fn cli_cwd_override_wins_over_defaults() -> std::io::Result<()> {
         let codex_home = TempDir::new()?;
@@ -2373,6 +2660,7 @@ trust_level = "trusted"
             managed_config_path: Some(managed_path.clone()),
             #[cfg(target_os = "macos")]
             managed_preferences_base64: None,
+            ..Default::default()
         };
 
         let cwd = AbsolutePathBuf::try_from(codex_home.path())?;
@@ -2490,6 +2778,7 @@ trust_level = "trusted"
             managed_config_path: Some(managed_path),
             #[cfg(target_os = "macos")]
             managed_preferences_base64: None,
+            ..Default::default()
         };
 
         let cwd = AbsolutePathBuf::try_from(codex_home.path())?;
@@ -3491,7 +3780,24 @@ model_verbosity = "high"
                 project_doc_fallback_filenames: Vec::new(),
                 tool_output_token_limit: None,
                 tool_hook_command: None,
+                tool_hook_debug_echo: false,
+                tool_hook_mode: ToolHookMode::EveryCall,
+                tool_hook_phases: vec![ToolHookStage::Before, ToolHookStage::After],
+                tool_hook_format: ToolHookFormat::Json,
+                hook_environment_policy: None,
+                tool_hook_include: Vec::new(),
+                tool_hook_exclude: Vec::new(),
+                tool_hook_redactor: SnapshotRedactor::default(),
+                shell_calls_per_minute: None,
+                tool_default_timeouts_ms: HashMap::new(),
+                tool_dry_run: false,
+                enabled_tools: None,
                 stop_hook_command: None,
+                stop_hook_socket: None,
+                stop_hook_response_items: StopHookResponseItems::Full,
+                stop_hook_git_status: false,
+                stop_hook_final_message_max_chars: None,
+                hook_stderr: HookStderr::Inherit,
                 codex_home: fixture.codex_home(),
                 config_layer_stack: Default::default(),
                 history: History::default(),
@@ -3579,7 +3885,24 @@ model_verbosity = "high"
             project_doc_fallback_filenames: Vec::new(),
             tool_output_token_limit: None,
             tool_hook_command: None,
+            tool_hook_debug_echo: false,
+            tool_hook_mode: ToolHookMode::EveryCall,
+            tool_hook_phases: vec![ToolHookStage::Before, ToolHookStage::After],
+            tool_hook_format: ToolHookFormat::Json,
+            hook_environment_policy: None,
+            tool_hook_include: Vec::new(),
+            tool_hook_exclude: Vec::new(),
+            tool_hook_redactor: SnapshotRedactor::default(),
+            shell_calls_per_minute: None,
+            tool_default_timeouts_ms: HashMap::new(),
+            tool_dry_run: false,
+            enabled_tools: None,
             stop_hook_command: None,
+            stop_hook_socket: None,
+            stop_hook_response_items: StopHookResponseItems::Full,
+            stop_hook_git_status: false,
+            stop_hook_final_message_max_chars: None,
+            hook_stderr: HookStderr::Inherit,
             codex_home: fixture.codex_home(),
             config_layer_stack: Default::default(),
             history: History::default(),
@@ -3682,7 +4005,24 @@ model_verbosity = "high"
             project_doc_fallback_filenames: Vec::new(),
             tool_output_token_limit: None,
             tool_hook_command: None,
+            tool_hook_debug_echo: false,
+            tool_hook_mode: ToolHookMode::EveryCall,
+            tool_hook_phases: vec![ToolHookStage::Before, ToolHookStage::After],
+            tool_hook_format: ToolHookFormat::Json,
+            hook_environment_policy: None,
+            tool_hook_include: Vec::new(),
+            tool_hook_exclude: Vec::new(),
+            tool_hook_redactor: SnapshotRedactor::default(),
+            shell_calls_per_minute: None,
+            tool_default_timeouts_ms: HashMap::new(),
+            tool_dry_run: false,
+            enabled_tools: None,
             stop_hook_command: None,
+            stop_hook_socket: None,
+            stop_hook_response_items: StopHookResponseItems::Full,
+            stop_hook_git_status: false,
+            stop_hook_final_message_max_chars: None,
+            hook_stderr: HookStderr::Inherit,
             codex_home: fixture.codex_home(),
             config_layer_stack: Default::default(),
             history: History::default(),
@@ -3771,7 +4111,24 @@ model_verbosity = "high"
             project_doc_fallback_filenames: Vec::new(),
             tool_output_token_limit: None,
             tool_hook_command: None,
+            tool_hook_debug_echo: false,
+            tool_hook_mode: ToolHookMode::EveryCall,
+            tool_hook_phases: vec![ToolHookStage::Before, ToolHookStage::After],
+            tool_hook_format: ToolHookFormat::Json,
+            hook_environment_policy: None,
+            tool_hook_include: Vec::new(),
+            tool_hook_exclude: Vec::new(),
+            tool_hook_redactor: SnapshotRedactor::default(),
+            shell_calls_per_minute: None,
+            tool_default_timeouts_ms: HashMap::new(),
+            tool_dry_run: false,
+            enabled_tools: None,
             stop_hook_command: None,
+            stop_hook_socket: None,
+            stop_hook_response_items: StopHookResponseItems::Full,
+            stop_hook_git_status: false,
+            stop_hook_final_message_max_chars: None,
+            hook_stderr: HookStderr::Inherit,
             codex_home: fixture.codex_home(),
             config_layer_stack: Default::default(),
             history: History::default(),
@@ -1,9 +1,15 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use codex_utils_absolute_path::AbsolutePathBuf;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::config::types::HookStderr;
+use crate::config::types::StopHookResponseItems;
+use crate::config::types::ToolHookFormat;
+use crate::config::types::ToolHookMode;
+use crate::config::types::ToolHookStage;
 use crate::protocol::AskForApproval;
 use codex_protocol::config_types::ReasoningSummary;
 use codex_protocol::config_types::SandboxMode;
@@ -32,7 +38,22 @@ pub struct ConfigProfile {
     pub tools_web_search: Option<bool>,
     pub tools_view_image: Option<bool>,
     pub tool_hook_command: Option<Vec<String>>,
+    pub tool_hook_debug_echo: Option<bool>,
+    pub tool_hook_mode: Option<ToolHookMode>,
+    pub tool_hook_phases: Option<Vec<ToolHookStage>>,
+    pub tool_hook_format: Option<ToolHookFormat>,
+    pub tool_hook_include: Option<Vec<String>>,
+    pub tool_hook_exclude: Option<Vec<String>>,
+    pub shell_calls_per_minute: Option<u32>,
+    pub tool_default_timeouts_ms: Option<HashMap<String, u64>>,
+    pub tool_dry_run: Option<bool>,
+    pub enabled_tools: Option<Vec<String>>,
     pub stop_hook_command: Option<Vec<String>>,
+    pub stop_hook_socket: Option<PathBuf>,
+    pub stop_hook_response_items: Option<StopHookResponseItems>,
+    pub stop_hook_git_status: Option<bool>,
+    pub stop_hook_final_message_max_chars: Option<usize>,
+    pub hook_stderr: Option<HookStderr>,
     /// Additional experimental tools to expose to the model. These are merged with the
     /// model-family defaults (if any).
     pub experimental_supported_tools: Option<Vec<String>>,
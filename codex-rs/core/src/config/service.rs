@@ -114,8 +114,7 @@ impl ConfigService {
         }
     }
 
-    #[cfg(test)]
-    fn with_overrides(
+    pub fn with_overrides(
         codex_home: PathBuf,
         cli_overrides: Vec<(String, TomlValue)>,
         loader_overrides: LoaderOverrides,
@@ -755,6 +754,7 @@ remote_compaction = true
                 managed_config_path: Some(managed_path.clone()),
                 #[cfg(target_os = "macos")]
                 managed_preferences_base64: None,
+                ..Default::default()
             },
         );
 
@@ -835,6 +835,7 @@ remote_compaction = true
                 managed_config_path: Some(managed_path.clone()),
                 #[cfg(target_os = "macos")]
                 managed_preferences_base64: None,
+                ..Default::default()
             },
         );
 
@@ -937,6 +938,7 @@ remote_compaction = true
                 managed_config_path: Some(managed_path.clone()),
                 #[cfg(target_os = "macos")]
                 managed_preferences_base64: None,
+                ..Default::default()
             },
         );
 
@@ -984,6 +986,7 @@ remote_compaction = true
                 managed_config_path: Some(managed_path.clone()),
                 #[cfg(target_os = "macos")]
                 managed_preferences_base64: None,
+                ..Default::default()
             },
         );
 
@@ -1029,6 +1032,7 @@ remote_compaction = true
                 managed_config_path: Some(managed_path.clone()),
                 #[cfg(target_os = "macos")]
                 managed_preferences_base64: None,
+                ..Default::default()
             },
         );
 
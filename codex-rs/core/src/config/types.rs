@@ -21,14 +21,34 @@ pub const DEFAULT_OTEL_ENVIRONMENT: &str = "dev";
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 pub struct CustomToolToml {
     pub command: Vec<String>,
+    /// Set to `false` to keep the definition without exposing the tool.
+    #[serde(default)]
+    pub enabled: Option<bool>,
     #[serde(default)]
     pub description: Option<String>,
     #[serde(default)]
     pub parameters: Option<JsonValue>,
+    /// Argument names that must be present (and non-null) in every call.
+    #[serde(default)]
+    pub required: Option<Vec<String>>,
     #[serde(default)]
     pub cwd: Option<String>,
     #[serde(default)]
     pub env: Option<HashMap<String, String>>,
+    /// Wildcard patterns; only matching inherited variables are passed on.
+    #[serde(default)]
+    pub env_include: Option<Vec<String>>,
+    /// Wildcard patterns for inherited variables the tool must not see.
+    #[serde(default)]
+    pub env_exclude: Option<Vec<String>>,
+    /// Wildcard patterns for parent-process variables forwarded to the tool
+    /// even when the shell environment policy would drop them.
+    #[serde(default)]
+    pub env_passthrough: Option<Vec<String>>,
+    /// `.env`-style file, relative to the turn cwd, loaded into the tool's
+    /// environment on every call.
+    #[serde(default)]
+    pub env_file: Option<String>,
     #[serde(default)]
     pub timeout_ms: Option<u64>,
     #[serde(default)]
@@ -37,6 +57,13 @@ pub struct CustomToolToml {
     pub parallel: Option<bool>,
     #[serde(default, alias = "shutdown_after_call")]
     pub hibernate_after_call: Option<bool>,
+    /// Save the output to a file and give the model its path instead of the
+    /// output itself.
+    #[serde(default)]
+    pub output_as_file: Option<bool>,
+    /// Cut output returned to the model to this many bytes.
+    #[serde(default)]
+    pub max_output_bytes: Option<usize>,
 }
 
 #[derive(Serialize, Debug, Clone, PartialEq)]
@@ -295,6 +322,68 @@ pub enum HistoryPersistence {
     None,
 }
 
+/// Controls how often `tool_hook_command` runs its `before_execution` phase.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ToolHookMode {
+    /// Run the before-hook for every tool call.
+    #[default]
+    EveryCall,
+    /// Run the before-hook only for the first tool call of each turn. The
+    /// after-hook still runs for every call.
+    OncePerTurn,
+}
+
+/// A phase of `tool_hook_command` that `tool_hook_phases` can enable.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ToolHookStage {
+    /// The `before_execution` event, whose stdout may carry a directive.
+    Before,
+    /// The `after_execution` and `replaced` events.
+    After,
+}
+
+/// How each event is handed to `tool_hook_command`.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ToolHookFormat {
+    /// The whole event as one JSON document on stdin.
+    #[default]
+    Json,
+    /// The same document followed by a newline, for line-oriented readers.
+    Jsonl,
+    /// Scalar fields (phase, tool name, call id, parallel) as `CODEX_HOOK_*`
+    /// environment variables; only the payload and outcome go on stdin.
+    Env,
+}
+
+/// Where `tool_hook_command` and `stop_hook_command` write their stderr.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookStderr {
+    /// Hook stderr goes straight to Codex's stderr.
+    #[default]
+    Inherit,
+    /// Hook stderr is captured and logged through `tracing` with a
+    /// `hook_stderr` field.
+    Capture,
+}
+
+/// Controls which `response_items` are included in the `stop_hook_command`
+/// payload.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum StopHookResponseItems {
+    /// Send the full conversation history.
+    #[default]
+    Full,
+    /// Send only the last assistant message.
+    FinalMessageOnly,
+    /// Send only tool calls and their outputs.
+    ToolCallsOnly,
+}
+
 // ===== OTEL configuration =====
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -5,6 +5,7 @@ mod layer_io;
 mod macos;
 mod merge;
 mod overrides;
+mod references;
 mod state;
 
 #[cfg(test)]
@@ -19,6 +20,7 @@ use codex_protocol::config_types::SandboxMode;
 use codex_protocol::protocol::AskForApproval;
 use codex_utils_absolute_path::AbsolutePathBuf;
 use codex_utils_absolute_path::AbsolutePathBufGuard;
+use codex_utils_json_to_toml::json_to_toml;
 use serde::Deserialize;
 use std::io;
 use std::path::Path;
@@ -26,6 +28,10 @@ use toml::Value as TomlValue;
 
 pub use config_requirements::ConfigRequirements;
 pub use merge::merge_toml_values;
+pub use overrides::apply_config_override;
+pub use overrides::merge_config_override;
+pub use overrides::value_at_path;
+pub use references::resolve_override_references;
 pub use state::ConfigLayerEntry;
 pub use state::ConfigLayerStack;
 pub use state::ConfigLayerStackOrdering;
@@ -74,8 +80,14 @@ pub async fn load_config_layers_state(
     codex_home: &Path,
     cwd: Option<AbsolutePathBuf>,
     cli_overrides: &[(String, TomlValue)],
-    overrides: LoaderOverrides,
+    mut overrides: LoaderOverrides,
 ) -> io::Result<ConfigLayerStack> {
+    let skip_config_files = overrides.ignore_config_files;
+    let user_config_file = overrides.config_file.take();
+    let profile_overlay = overrides.profile_overlay.take();
+    let cli_defaults = std::mem::take(&mut overrides.cli_defaults);
+    let reference_paths = std::mem::take(&mut overrides.reference_paths);
+    let merge_paths = std::mem::take(&mut overrides.merge_paths);
     let mut config_requirements_toml = ConfigRequirementsToml::default();
 
     // TODO(gt): Support an entry in MDM for config requirements and use it
@@ -105,7 +117,7 @@ pub async fn load_config_layers_state(
 
     // Include an entry for the "system" config folder, loading its config.toml,
     // if it exists.
-    let system_config_toml_file = if cfg!(unix) {
+    let system_config_toml_file = if cfg!(unix) && !skip_config_files {
         Some(AbsolutePathBuf::from_absolute_path(
             SYSTEM_CONFIG_TOML_FILE_UNIX,
         )?)
@@ -127,22 +139,37 @@ pub async fn load_config_layers_state(
         layers.push(system_layer);
     }
 
-    // Add a layer for $CODEX_HOME/config.toml if it exists. Note if the file
-    // exists, but is malformed, then this error should be propagated to the
-    // user.
-    let user_file = AbsolutePathBuf::resolve_path_against_base(CONFIG_TOML_FILE, codex_home)?;
-    let user_layer = load_config_toml_for_required_layer(&user_file, |config_toml| {
+    // Add a layer for $CODEX_HOME/config.toml (or the `--config-file`
+    // override) if it exists. Note if the file exists, but is malformed, then
+    // this error should be propagated to the user. With `ignore_config_files`
+    // the layer is kept but starts out empty.
+    let user_file = AbsolutePathBuf::resolve_path_against_base(
+        user_config_file.unwrap_or_else(|| codex_home.join(CONFIG_TOML_FILE)),
+        codex_home,
+    )?;
+    let user_layer = if skip_config_files {
         ConfigLayerEntry::new(
             ConfigLayerSource::User {
                 file: user_file.clone(),
             },
-            config_toml,
+            TomlValue::Table(toml::map::Map::new()),
         )
-    })
-    .await?;
+    } else {
+        load_config_toml_for_required_layer(&user_file, |config_toml| {
+            ConfigLayerEntry::new(
+                ConfigLayerSource::User {
+                    file: user_file.clone(),
+                },
+                config_toml,
+            )
+        })
+        .await?
+    };
     layers.push(user_layer);
 
-    if let Some(cwd) = cwd {
+    if let Some(cwd) = cwd
+        && !skip_config_files
+    {
         let mut merged_so_far = TomlValue::Table(toml::map::Map::new());
         for layer in &layers {
             merge_toml_values(&mut merged_so_far, &layer.config);
@@ -155,12 +182,30 @@ pub async fn load_config_layers_state(
         layers.extend(project_layers);
     }
 
-    // Add a layer for runtime overrides from the CLI or UI, if any exist.
-    if !cli_overrides.is_empty() {
-        let cli_overrides_layer = overrides::build_cli_overrides_layer(cli_overrides);
+    // Add a layer for runtime overrides from the CLI or UI, if any exist. A
+    // `--config-profile` table goes into the same layer, underneath them.
+    let mut session_flags = match profile_overlay.as_deref() {
+        Some(name) => profile_overlay_table(&layers, name)?,
+        None => overrides::default_empty_table(),
+    };
+    let mut below_session_flags = overrides::default_empty_table();
+    for layer in &layers {
+        merge_toml_values(&mut below_session_flags, &layer.config);
+    }
+    overrides::apply_cli_overrides(
+        &mut session_flags,
+        &below_session_flags,
+        cli_overrides,
+        &merge_paths,
+    )?;
+    let session_flags_index = layers.len();
+    if session_flags
+        .as_table()
+        .is_some_and(|table| !table.is_empty())
+    {
         layers.push(ConfigLayerEntry::new(
             ConfigLayerSource::SessionFlags,
-            cli_overrides_layer,
+            session_flags,
         ));
     }
 
@@ -197,9 +242,149 @@ pub async fn load_config_layers_state(
         ));
     }
 
+    apply_cli_defaults(&mut layers, session_flags_index, &cli_defaults)?;
+    resolve_session_flag_references(&mut layers, session_flags_index, &reference_paths)?;
+
     ConfigLayerStack::new(layers, config_requirements_toml.try_into()?)
 }
 
+/// Add each of `cli_defaults` whose path is unset in every layer to the
+/// runtime layer at `session_flags_index`, creating that layer if needed.
+fn apply_cli_defaults(
+    layers: &mut Vec<ConfigLayerEntry>,
+    session_flags_index: usize,
+    cli_defaults: &[(String, TomlValue)],
+) -> io::Result<()> {
+    let mut effective = overrides::default_empty_table();
+    for layer in layers.iter() {
+        merge_toml_values(&mut effective, &layer.config);
+    }
+    let unset: Vec<(String, TomlValue)> = cli_defaults
+        .iter()
+        .filter(|(path, _)| !overrides::has_path(&effective, path))
+        .cloned()
+        .collect();
+    if unset.is_empty() {
+        return Ok(());
+    }
+    let existing = layers
+        .get(session_flags_index)
+        .filter(|layer| matches!(layer.name, ConfigLayerSource::SessionFlags));
+    let mut session_flags = existing
+        .map(|layer| layer.config.clone())
+        .unwrap_or_else(overrides::default_empty_table);
+    let replace = existing.is_some();
+    overrides::apply_cli_overrides(&mut session_flags, &effective, &unset, &[])?;
+    let entry = ConfigLayerEntry::new(ConfigLayerSource::SessionFlags, session_flags);
+    if replace {
+        layers[session_flags_index] = entry;
+    } else {
+        layers.insert(session_flags_index, entry);
+    }
+    Ok(())
+}
+
+/// Resolve `${...}` references in the runtime layer's values at
+/// `reference_paths` against the effective config of `layers`.
+fn resolve_session_flag_references(
+    layers: &mut [ConfigLayerEntry],
+    session_flags_index: usize,
+    reference_paths: &[String],
+) -> io::Result<()> {
+    let Some(layer) = layers
+        .get(session_flags_index)
+        .filter(|layer| matches!(layer.name, ConfigLayerSource::SessionFlags))
+    else {
+        return Ok(());
+    };
+    let unresolved: Vec<String> = reference_paths
+        .iter()
+        .filter(|path| {
+            matches!(
+                overrides::value_at_path(&layer.config, path),
+                Some(TomlValue::String(raw)) if raw.contains("${")
+            )
+        })
+        .cloned()
+        .collect();
+    if unresolved.is_empty() {
+        return Ok(());
+    }
+    let mut effective = overrides::default_empty_table();
+    for layer in layers.iter() {
+        merge_toml_values(&mut effective, &layer.config);
+    }
+    resolve_override_references(&mut effective, &unresolved)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut session_flags = layers[session_flags_index].config.clone();
+    for path in &unresolved {
+        if let Some(value) = overrides::value_at_path(&effective, path) {
+            overrides::apply_toml_override(&mut session_flags, path, value.clone())?;
+        }
+    }
+    layers[session_flags_index] =
+        ConfigLayerEntry::new(ConfigLayerSource::SessionFlags, session_flags);
+    Ok(())
+}
+
+/// The `[profiles.NAME]` table of `layers` merged together, for a
+/// `--config-profile NAME` overlay.
+fn profile_overlay_table(layers: &[ConfigLayerEntry], name: &str) -> io::Result<TomlValue> {
+    let mut merged = overrides::default_empty_table();
+    for layer in layers {
+        merge_toml_values(&mut merged, &layer.config);
+    }
+    let profiles = merged.get("profiles").and_then(TomlValue::as_table);
+    if let Some(profile @ TomlValue::Table(_)) = profiles.and_then(|profiles| profiles.get(name)) {
+        return Ok(profile.clone());
+    }
+    let available = profiles
+        .map(|profiles| profiles.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    let available = if available.is_empty() {
+        "none defined".to_string()
+    } else {
+        available.join(", ")
+    };
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Unknown config profile `{name}` (available profiles: {available})"),
+    ))
+}
+
+/// Parse the contents of a config file. Files with a `.json`, `.yaml`, or
+/// `.yml` extension are converted into the equivalent TOML value so they layer
+/// like any other config.
+pub fn parse_config_file_contents(path: &Path, contents: &str) -> io::Result<TomlValue> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    let parsed = match extension.as_deref() {
+        Some("json") => serde_json::from_str::<serde_json::Value>(contents)
+            .map(json_to_toml)
+            .map_err(|e| e.to_string()),
+        Some("yaml" | "yml") => parse_yaml_config(contents),
+        _ => toml::from_str::<TomlValue>(contents).map_err(|e| e.to_string()),
+    };
+    parsed.map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Error parsing config file {}: {e}", path.display()),
+        )
+    })
+}
+
+/// Parse YAML config contents. Aliases are expanded and `<<` merge keys
+/// applied before the tree is converted, so anchors behave like copies.
+fn parse_yaml_config(contents: &str) -> Result<TomlValue, String> {
+    let mut value: serde_yaml::Value = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+    value.apply_merge().map_err(|e| e.to_string())?;
+    serde_json::to_value(value)
+        .map(json_to_toml)
+        .map_err(|e| e.to_string())
+}
+
 /// Attempts to load a config.toml file from `config_toml`.
 /// - If the file exists and is valid TOML, passes the parsed `toml::Value` to
 ///   `create_entry` and returns the resulting layer entry.
@@ -214,12 +399,7 @@ async fn load_config_toml_for_required_layer(
     let toml_file = config_toml.as_ref();
     let toml_value = match tokio::fs::read_to_string(toml_file).await {
         Ok(contents) => {
-            let config: TomlValue = toml::from_str(&contents).map_err(|e| {
-                io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("Error parsing config file {}: {e}", toml_file.display()),
-                )
-            })?;
+            let config = parse_config_file_contents(toml_file, &contents)?;
             let config_parent = toml_file.parent().ok_or_else(|| {
                 io::Error::new(
                     io::ErrorKind::InvalidData,
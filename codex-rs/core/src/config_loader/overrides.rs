@@ -1,39 +1,181 @@
+use super::merge::merge_toml_values;
+use std::io;
 use toml::Value as TomlValue;
 
 pub(super) fn default_empty_table() -> TomlValue {
     TomlValue::Table(Default::default())
 }
 
-pub(super) fn build_cli_overrides_layer(cli_overrides: &[(String, TomlValue)]) -> TomlValue {
-    let mut root = default_empty_table();
+/// Apply `cli_overrides` in order onto `root`, the runtime layer. `below` is
+/// the merged config of the layers underneath it: a numeric path segment
+/// indexes into an array found in `root` or `below`, and an array that only
+/// exists in `below` is copied into `root` first so its other elements are
+/// kept. A table value at one of `merge_paths` is deep-merged into the table
+/// already at that path; every other value replaces what was there.
+pub(super) fn apply_cli_overrides(
+    root: &mut TomlValue,
+    below: &TomlValue,
+    cli_overrides: &[(String, TomlValue)],
+    merge_paths: &[String],
+) -> io::Result<()> {
     for (path, value) in cli_overrides {
-        apply_toml_override(&mut root, path, value.clone());
+        copy_indexed_arrays(root, below, path)?;
+        let merge = merge_paths.contains(path);
+        set_toml_override(root, path, value.clone(), merge)?;
     }
-    root
+    Ok(())
 }
 
-/// Apply a single dotted-path override onto a TOML value.
-fn apply_toml_override(root: &mut TomlValue, path: &str, value: TomlValue) {
+/// Apply a single dotted-path override onto a complete config tree, replacing
+/// the value at `path`. A numeric segment indexes into an array already in
+/// `root`; indexing past its end or into anything but an array or table
+/// fails instead of creating a new key.
+pub fn apply_config_override(root: &mut TomlValue, path: &str, value: TomlValue) -> io::Result<()> {
+    check_indexed_parents(root, path)?;
+    set_toml_override(root, path, value, false)
+}
+
+/// Like [`apply_config_override`], but a table value is deep-merged into the
+/// table already at `path` (as config layers combine) instead of replacing
+/// it. Used for `--config-dir` fragments and `-c path@=FILE` merges.
+pub fn merge_config_override(root: &mut TomlValue, path: &str, value: TomlValue) -> io::Result<()> {
+    check_indexed_parents(root, path)?;
+    set_toml_override(root, path, value, true)
+}
+
+/// Whether `root` has a value at the dotted `path`.
+pub(super) fn has_path(root: &TomlValue, path: &str) -> bool {
+    value_at_path(root, path).is_some()
+}
+
+/// The value at the dotted `path`, following numeric segments into arrays.
+pub fn value_at_path<'a>(root: &'a TomlValue, path: &str) -> Option<&'a TomlValue> {
+    path.split('.')
+        .try_fold(root, |current, segment| match current {
+            TomlValue::Table(table) => table.get(segment),
+            TomlValue::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+/// For every numeric segment of `path` whose parent is not a table, make sure
+/// the parent array is present in `root`, copying it from `below` if needed.
+fn copy_indexed_arrays(root: &mut TomlValue, below: &TomlValue, path: &str) -> io::Result<()> {
+    for (parent, segment) in indexed_parents(path) {
+        match value_at_path(root, &parent) {
+            Some(TomlValue::Table(_) | TomlValue::Array(_)) => continue,
+            Some(_) => return Err(not_an_array(path, &parent, segment)),
+            None => {}
+        }
+        match value_at_path(below, &parent) {
+            Some(TomlValue::Table(_)) => {}
+            Some(array @ TomlValue::Array(_)) => {
+                set_toml_override(root, &parent, array.clone(), false)?;
+            }
+            _ => return Err(not_an_array(path, &parent, segment)),
+        }
+    }
+    Ok(())
+}
+
+/// Fail unless every numeric segment of `path` lands on an array or table
+/// that is already in `root`.
+fn check_indexed_parents(root: &TomlValue, path: &str) -> io::Result<()> {
+    for (parent, segment) in indexed_parents(path) {
+        if !matches!(
+            value_at_path(root, &parent),
+            Some(TomlValue::Table(_) | TomlValue::Array(_))
+        ) {
+            return Err(not_an_array(path, &parent, segment));
+        }
+    }
+    Ok(())
+}
+
+/// The `(parent path, segment)` pairs for each numeric segment of `path`
+/// after the first.
+fn indexed_parents(path: &str) -> Vec<(String, &str)> {
+    let segments: Vec<&str> = path.split('.').collect();
+    (1..segments.len())
+        .filter(|&i| segments[i].parse::<usize>().is_ok())
+        .map(|i| (segments[..i].join("."), segments[i]))
+        .collect()
+}
+
+fn not_an_array(path: &str, parent: &str, segment: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "Cannot apply override `{path}`: `{parent}` is not an array, so it cannot be indexed with `{segment}`"
+        ),
+    )
+}
+
+/// Apply a single dotted-path override onto a TOML value, replacing the value
+/// at `path`. A numeric segment indexes into the array it lands on; anywhere
+/// else it is a table key.
+pub(super) fn apply_toml_override(
+    root: &mut TomlValue,
+    path: &str,
+    value: TomlValue,
+) -> io::Result<()> {
+    set_toml_override(root, path, value, false)
+}
+
+/// Store `value` at the dotted `path`. With `merge`, a table value is
+/// deep-merged into a table already there; otherwise it replaces it.
+fn set_toml_override(
+    root: &mut TomlValue,
+    path: &str,
+    value: TomlValue,
+    merge: bool,
+) -> io::Result<()> {
     use toml::value::Table;
 
     let mut current = root;
     let mut segments_iter = path.split('.').peekable();
+    let mut parent = Vec::new();
 
     while let Some(segment) = segments_iter.next() {
         let is_last = segments_iter.peek().is_none();
 
+        if let Ok(index) = segment.parse::<usize>()
+            && let TomlValue::Array(items) = current
+        {
+            let len = items.len();
+            let Some(item) = items.get_mut(index) else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Cannot apply override `{path}`: index {index} is out of bounds for `{}` (length {len})",
+                        parent.join(".")
+                    ),
+                ));
+            };
+            if is_last {
+                store_override_value(item, value, merge);
+                return Ok(());
+            }
+            current = item;
+            parent.push(segment);
+            continue;
+        }
+
         if is_last {
             match current {
-                TomlValue::Table(table) => {
-                    table.insert(segment.to_string(), value);
-                }
+                TomlValue::Table(table) => match table.get_mut(segment) {
+                    Some(existing) => store_override_value(existing, value, merge),
+                    None => {
+                        table.insert(segment.to_string(), value);
+                    }
+                },
                 _ => {
                     let mut table = Table::new();
                     table.insert(segment.to_string(), value);
                     *current = TomlValue::Table(table);
                 }
             }
-            return;
+            return Ok(());
         }
 
         match current {
@@ -51,5 +193,15 @@ fn apply_toml_override(root: &mut TomlValue, path: &str, value: TomlValue) {
                 }
             }
         }
+        parent.push(segment);
+    }
+    Ok(())
+}
+
+fn store_override_value(slot: &mut TomlValue, value: TomlValue, merge: bool) {
+    if merge && slot.is_table() && value.is_table() {
+        merge_toml_values(slot, &value);
+    } else {
+        *slot = value;
     }
 }
@@ -0,0 +1,146 @@
+use super::overrides::apply_toml_override;
+use super::overrides::value_at_path;
+use std::collections::HashSet;
+use toml::Value as TomlValue;
+
+/// Replace `${path.to.key}` references in the string values at `paths` with
+/// the value at that key in `root`. A value that is a single reference takes
+/// the referenced value as is; otherwise each referenced value must be a
+/// string, number, or boolean and is spliced into the text. `$${` stands for
+/// a literal `${`. References to other paths in `paths` are resolved first,
+/// so chains work and cycles are reported instead of looping.
+///
+/// Values at any other path are left alone, so only the `-c` overrides a
+/// caller opted in are rewritten.
+pub fn resolve_override_references(root: &mut TomlValue, paths: &[String]) -> Result<(), String> {
+    let mut resolved = HashSet::new();
+    for path in paths {
+        resolve_references_at(root, path, paths, &mut resolved, &mut Vec::new())?;
+    }
+    Ok(())
+}
+
+fn resolve_references_at(
+    root: &mut TomlValue,
+    path: &str,
+    overridden: &[String],
+    resolved: &mut HashSet<String>,
+    stack: &mut Vec<String>,
+) -> Result<(), String> {
+    if resolved.contains(path) {
+        return Ok(());
+    }
+    if stack.iter().any(|pending| pending == path) {
+        return Err(format!(
+            "Cycle in config override references: {} -> {path}",
+            stack.join(" -> ")
+        ));
+    }
+    let raw = match value_at_path(root, path) {
+        Some(TomlValue::String(raw)) if raw.contains("${") => raw.clone(),
+        _ => {
+            resolved.insert(path.to_string());
+            return Ok(());
+        }
+    };
+    stack.push(path.to_string());
+    for reference in reference_names(&raw) {
+        if overridden.iter().any(|candidate| candidate == reference) {
+            resolve_references_at(root, reference, overridden, resolved, stack)?;
+        }
+    }
+    stack.pop();
+    let value = substitute_references(root, path, &raw)?;
+    apply_toml_override(root, path, value).map_err(|err| err.to_string())?;
+    resolved.insert(path.to_string());
+    Ok(())
+}
+
+/// A piece of an override string value.
+#[derive(Debug)]
+enum ReferencePart<'a> {
+    Text(&'a str),
+    /// The trimmed name inside `${...}`.
+    Reference(&'a str),
+}
+
+/// Split `raw` into literal text and `${name}` references. `$${` is an
+/// escaped `${` and an unterminated `${` is kept as text. Empty text pieces
+/// are dropped.
+fn reference_parts(raw: &str) -> Vec<ReferencePart<'_>> {
+    fn push_text<'a>(parts: &mut Vec<ReferencePart<'a>>, text: &'a str) {
+        if !text.is_empty() {
+            parts.push(ReferencePart::Text(text));
+        }
+    }
+
+    let mut parts = Vec::new();
+    let mut rest = raw;
+    while let Some(start) = rest.find("${") {
+        if let Some(before) = rest[..start].strip_suffix('$') {
+            push_text(&mut parts, before);
+            push_text(&mut parts, "${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        push_text(&mut parts, &rest[..start]);
+        parts.push(ReferencePart::Reference(
+            rest[start + 2..start + 2 + len].trim(),
+        ));
+        rest = &rest[start + len + 3..];
+    }
+    push_text(&mut parts, rest);
+    parts
+}
+
+/// Names of the `${name}` references in `raw`, trimmed.
+fn reference_names(raw: &str) -> Vec<&str> {
+    reference_parts(raw)
+        .into_iter()
+        .filter_map(|part| match part {
+            ReferencePart::Reference(name) => Some(name),
+            ReferencePart::Text(_) => None,
+        })
+        .collect()
+}
+
+fn substitute_references(root: &TomlValue, path: &str, raw: &str) -> Result<TomlValue, String> {
+    let lookup = |reference: &str| {
+        value_at_path(root, reference).ok_or_else(|| {
+            format!(
+                "Cannot resolve `${{{reference}}}` in override `{path}`: the merged config has no `{reference}` key"
+            )
+        })
+    };
+    let parts = reference_parts(raw);
+    if let [ReferencePart::Reference(reference)] = parts.as_slice() {
+        return lookup(reference).cloned();
+    }
+
+    let mut result = String::with_capacity(raw.len());
+    for part in parts {
+        let reference = match part {
+            ReferencePart::Text(text) => {
+                result.push_str(text);
+                continue;
+            }
+            ReferencePart::Reference(reference) => reference,
+        };
+        let value = lookup(reference)?;
+        match value {
+            TomlValue::String(text) => result.push_str(text),
+            TomlValue::Integer(_) | TomlValue::Float(_) | TomlValue::Boolean(_) => {
+                result.push_str(&value.to_string());
+            }
+            _ => {
+                return Err(format!(
+                    "Cannot splice `${{{reference}}}` into override `{path}`: `{reference}` is not a string, number, or boolean"
+                ));
+            }
+        }
+    }
+    Ok(TomlValue::String(result))
+}
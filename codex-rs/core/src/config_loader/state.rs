@@ -17,6 +17,29 @@ pub struct LoaderOverrides {
     pub managed_config_path: Option<PathBuf>,
     #[cfg(target_os = "macos")]
     pub managed_preferences_base64: Option<String>,
+    /// Read the user layer from this file instead of
+    /// `$CODEX_HOME/config.toml` (`--config-file`). Relative paths resolve
+    /// against the Codex home.
+    pub config_file: Option<PathBuf>,
+    /// Skip every config file (system, user, and project) so only runtime
+    /// overrides apply (`--config-no-file`). Managed configuration and
+    /// requirements are still enforced.
+    pub ignore_config_files: bool,
+    /// Merge the `[profiles.NAME]` table of the layered config into the
+    /// runtime layer, underneath the CLI overrides (`--config-profile NAME`).
+    pub profile_overlay: Option<String>,
+    /// Overrides applied only to paths that no config layer sets
+    /// (`--config-set-if-unset`), after every other layer is loaded.
+    pub cli_defaults: Vec<(String, TomlValue)>,
+    /// Paths of the CLI overrides whose string values may hold
+    /// `${path.to.key}` references (`--config-expand-refs`). They are
+    /// resolved against the effective config once every layer is loaded;
+    /// values at other paths are never rewritten.
+    pub reference_paths: Vec<String>,
+    /// Paths of the CLI overrides whose table values are deep-merged into
+    /// the table already at that path (`--config-dir` fragments and
+    /// `-c path@=FILE`) instead of replacing it.
+    pub merge_paths: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -8,6 +8,7 @@ use crate::config_loader::ConfigRequirements;
 use crate::config_loader::config_requirements::ConfigRequirementsToml;
 use crate::config_loader::fingerprint::version_for_toml;
 use crate::config_loader::load_requirements_toml;
+use codex_protocol::openai_models::ReasoningEffort;
 use codex_protocol::protocol::AskForApproval;
 use codex_utils_absolute_path::AbsolutePathBuf;
 use pretty_assertions::assert_eq;
@@ -43,6 +44,7 @@ extra = true
         managed_config_path: Some(managed_path),
         #[cfg(target_os = "macos")]
         managed_preferences_base64: None,
+        ..Default::default()
     };
 
     let cwd = AbsolutePathBuf::try_from(tmp.path()).expect("cwd");
@@ -77,6 +79,7 @@ async fn returns_empty_when_all_layers_missing() {
         managed_config_path: Some(managed_path),
         #[cfg(target_os = "macos")]
         managed_preferences_base64: None,
+        ..Default::default()
     };
 
     let cwd = AbsolutePathBuf::try_from(tmp.path()).expect("cwd");
@@ -169,6 +172,7 @@ flag = true
     let overrides = LoaderOverrides {
         managed_config_path: Some(managed_path),
         managed_preferences_base64: Some(encoded),
+        ..Default::default()
     };
 
     let cwd = AbsolutePathBuf::try_from(tmp.path()).expect("cwd");
@@ -434,3 +438,383 @@ project_root_markers = [".hg"]
 
     Ok(())
 }
+
+#[tokio::test]
+async fn json_config_file_matches_toml_equivalent() -> std::io::Result<()> {
+    let tmp = tempdir()?;
+    let json_path = tmp.path().join("config.json");
+    let toml_path = tmp.path().join("config.toml");
+    std::fs::write(
+        &json_path,
+        r#"{"model": "gpt-5", "profiles": {"fast": {"model_reasoning_effort": "low"}}}"#,
+    )?;
+    std::fs::write(
+        &toml_path,
+        r#"model = "gpt-5"
+
+[profiles.fast]
+model_reasoning_effort = "low"
+"#,
+    )?;
+
+    let from_json = super::load_config_toml_for_required_layer(&json_path, config_entry).await?;
+    let from_toml = super::load_config_toml_for_required_layer(&toml_path, config_entry).await?;
+
+    assert_eq!(from_json.config, from_toml.config);
+    Ok(())
+}
+
+#[tokio::test]
+async fn yaml_config_file_with_anchors_matches_toml_equivalent() -> std::io::Result<()> {
+    let tmp = tempdir()?;
+    let yaml_path = tmp.path().join("config.yaml");
+    let toml_path = tmp.path().join("config.toml");
+    std::fs::write(
+        &yaml_path,
+        r#"model: gpt-5
+defaults: &defaults
+  model_reasoning_effort: low
+profiles:
+  fast:
+    <<: *defaults
+    model: o3
+  review: *defaults
+"#,
+    )?;
+    std::fs::write(
+        &toml_path,
+        r#"model = "gpt-5"
+
+[defaults]
+model_reasoning_effort = "low"
+
+[profiles.fast]
+model_reasoning_effort = "low"
+model = "o3"
+
+[profiles.review]
+model_reasoning_effort = "low"
+"#,
+    )?;
+
+    let from_yaml = super::load_config_toml_for_required_layer(&yaml_path, config_entry).await?;
+    let from_toml = super::load_config_toml_for_required_layer(&toml_path, config_entry).await?;
+
+    assert_eq!(from_yaml.config, from_toml.config);
+    Ok(())
+}
+
+#[tokio::test]
+async fn malformed_json_config_file_reports_path() -> std::io::Result<()> {
+    let tmp = tempdir()?;
+    let json_path = tmp.path().join("config.json");
+    std::fs::write(&json_path, "{ not json")?;
+
+    let err = super::load_config_toml_for_required_layer(&json_path, config_entry)
+        .await
+        .expect_err("malformed JSON should fail");
+
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(
+        err.to_string().contains(&json_path.display().to_string()),
+        "unexpected error: {err}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn ignore_config_files_keeps_only_cli_overrides() -> std::io::Result<()> {
+    let tmp = tempdir()?;
+    let project_root = tmp.path().join("project");
+    tokio::fs::create_dir_all(project_root.join(".codex")).await?;
+    tokio::fs::write(project_root.join(".git"), "gitdir: here").await?;
+    tokio::fs::write(
+        project_root.join(".codex").join(CONFIG_TOML_FILE),
+        "foo = \"project\"\n",
+    )
+    .await?;
+    let codex_home = tmp.path().join("home");
+    tokio::fs::create_dir_all(&codex_home).await?;
+    tokio::fs::write(
+        codex_home.join(CONFIG_TOML_FILE),
+        "foo = \"user\"\nbar = \"user\"\n",
+    )
+    .await?;
+    let cwd = AbsolutePathBuf::from_absolute_path(&project_root)?;
+
+    let layers = load_config_layers_state(
+        &codex_home,
+        Some(cwd),
+        &[("baz".to_string(), TomlValue::String("cli".to_string()))],
+        LoaderOverrides {
+            managed_config_path: Some(tmp.path().join("managed_config.toml")),
+            ignore_config_files: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let expected: TomlValue = toml::from_str("baz = \"cli\"").expect("expected config");
+    assert_eq!(layers.effective_config(), expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn config_file_override_replaces_the_user_config_file() -> std::io::Result<()> {
+    let tmp = tempdir()?;
+    let codex_home = tmp.path().join("home");
+    tokio::fs::create_dir_all(&codex_home).await?;
+    tokio::fs::write(codex_home.join(CONFIG_TOML_FILE), "foo = \"home\"\n").await?;
+    let config_file = tmp.path().join("ci.json");
+    tokio::fs::write(&config_file, r#"{"foo": "ci"}"#).await?;
+
+    let layers = load_config_layers_state(
+        &codex_home,
+        None,
+        &[] as &[(String, TomlValue)],
+        LoaderOverrides {
+            managed_config_path: Some(tmp.path().join("managed_config.toml")),
+            config_file: Some(config_file.clone()),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let expected: TomlValue = toml::from_str("foo = \"ci\"").expect("expected config");
+    assert_eq!(
+        layers
+            .get_user_layer()
+            .map(|layer| (layer.name.clone(), layer.config.clone())),
+        Some((
+            super::ConfigLayerSource::User {
+                file: AbsolutePathBuf::from_absolute_path(&config_file)?,
+            },
+            expected,
+        ))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn profile_overlay_sits_between_config_files_and_cli_overrides() -> anyhow::Result<()> {
+    let tmp = tempdir()?;
+    let codex_home = tmp.path().join("home");
+    tokio::fs::create_dir_all(&codex_home).await?;
+    tokio::fs::write(
+        codex_home.join(CONFIG_TOML_FILE),
+        r#"
+model = "base-model"
+
+[profiles.fast]
+model = "fast-model"
+model_reasoning_effort = "low"
+"#,
+    )
+    .await?;
+    let load = |profile: &str| {
+        ConfigBuilder::default()
+            .codex_home(codex_home.clone())
+            .cli_overrides(vec![(
+                "model_reasoning_effort".to_string(),
+                TomlValue::String("high".to_string()),
+            )])
+            .harness_overrides(ConfigOverrides {
+                cwd: Some(tmp.path().to_path_buf()),
+                ..ConfigOverrides::default()
+            })
+            .loader_overrides(LoaderOverrides {
+                profile_overlay: Some(profile.to_string()),
+                ..Default::default()
+            })
+            .build()
+    };
+
+    let config = load("fast").await?;
+    let unknown = load("slow").await.expect_err("unknown profile");
+
+    assert_eq!(
+        (
+            config.model.as_deref(),
+            config.model_reasoning_effort,
+            unknown.to_string()
+        ),
+        (
+            Some("fast-model"),
+            Some(ReasoningEffort::High),
+            "Unknown config profile `slow` (available profiles: fast)".to_string()
+        )
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn cli_defaults_only_fill_paths_no_layer_sets() -> std::io::Result<()> {
+    let tmp = tempdir()?;
+    let project_root = tmp.path().join("project");
+    tokio::fs::create_dir_all(project_root.join(".codex")).await?;
+    tokio::fs::write(project_root.join(".git"), "gitdir: here").await?;
+    tokio::fs::write(
+        project_root.join(".codex").join(CONFIG_TOML_FILE),
+        "foo = \"project\"\n",
+    )
+    .await?;
+    let codex_home = tmp.path().join("home");
+    tokio::fs::create_dir_all(&codex_home).await?;
+    let cwd = AbsolutePathBuf::from_absolute_path(&project_root)?;
+
+    let layers = load_config_layers_state(
+        &codex_home,
+        Some(cwd),
+        &[("bar".to_string(), TomlValue::String("cli".to_string()))],
+        LoaderOverrides {
+            cli_defaults: vec![
+                ("foo".to_string(), TomlValue::String("default".to_string())),
+                ("bar".to_string(), TomlValue::String("default".to_string())),
+                ("baz".to_string(), TomlValue::String("default".to_string())),
+            ],
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let config = layers.effective_config();
+    let value = |key: &str| config.get(key).and_then(TomlValue::as_str);
+    assert_eq!(
+        (value("foo"), value("bar"), value("baz")),
+        (Some("project"), Some("cli"), Some("default"))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn cli_overrides_index_into_arrays_from_lower_layers() -> std::io::Result<()> {
+    let tmp = tempdir()?;
+    tokio::fs::write(
+        tmp.path().join(CONFIG_TOML_FILE),
+        r#"
+notify = ["notify-send", "--urgency", "low"]
+
+[[servers]]
+name = "a"
+port = 1
+
+[[servers]]
+name = "b"
+port = 2
+"#,
+    )
+    .await?;
+
+    let layers = load_config_layers_state(
+        tmp.path(),
+        None,
+        &[
+            (
+                "notify.2".to_string(),
+                TomlValue::String("high".to_string()),
+            ),
+            ("servers.1.port".to_string(), TomlValue::Integer(3)),
+        ],
+        LoaderOverrides {
+            managed_config_path: Some(tmp.path().join("managed_config.toml")),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let expected: TomlValue = toml::from_str(
+        r#"
+notify = ["notify-send", "--urgency", "high"]
+
+[[servers]]
+name = "a"
+port = 1
+
+[[servers]]
+name = "b"
+port = 3
+"#,
+    )
+    .expect("expected config");
+    assert_eq!(layers.effective_config(), expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn reference_paths_resolve_against_the_effective_config() -> std::io::Result<()> {
+    let tmp = tempdir()?;
+    tokio::fs::write(
+        tmp.path().join(CONFIG_TOML_FILE),
+        "model = \"o3\"\nlog_dir = \"${HOME}/logs\"\n",
+    )
+    .await?;
+
+    let layers = load_config_layers_state(
+        tmp.path(),
+        None,
+        &[
+            (
+                "model_alias".to_string(),
+                TomlValue::String("${model}-alias".to_string()),
+            ),
+            (
+                "notify_note".to_string(),
+                TomlValue::String("${PATH}".to_string()),
+            ),
+        ],
+        LoaderOverrides {
+            managed_config_path: Some(tmp.path().join("managed_config.toml")),
+            reference_paths: vec!["model_alias".to_string()],
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let expected: TomlValue = toml::from_str(
+        r#"
+model = "o3"
+log_dir = "${HOME}/logs"
+model_alias = "o3-alias"
+notify_note = "${PATH}"
+"#,
+    )
+    .expect("expected config");
+    assert_eq!(layers.effective_config(), expected);
+    Ok(())
+}
+
+#[test]
+fn apply_cli_overrides_rejects_bad_array_indices() {
+    let below: TomlValue =
+        toml::from_str("notify = [\"notify-send\"]\nmodel = \"o3\"\n").expect("below");
+    let err = |path: &str| {
+        let mut root = TomlValue::Table(Default::default());
+        super::overrides::apply_cli_overrides(
+            &mut root,
+            &below,
+            &[(path.to_string(), TomlValue::Integer(1))],
+            &[],
+        )
+        .expect_err(path)
+        .to_string()
+    };
+
+    assert_eq!(
+        (err("notify.3"), err("model.0"), err("missing.0")),
+        (
+            "Cannot apply override `notify.3`: index 3 is out of bounds for `notify` (length 1)"
+                .to_string(),
+            "Cannot apply override `model.0`: `model` is not an array, so it cannot be indexed with `0`"
+                .to_string(),
+            "Cannot apply override `missing.0`: `missing` is not an array, so it cannot be indexed with `0`"
+                .to_string(),
+        )
+    );
+}
+
+fn config_entry(config: TomlValue) -> ConfigLayerEntry {
+    ConfigLayerEntry::new(
+        codex_app_server_protocol::ConfigLayerSource::SessionFlags,
+        config,
+    )
+}
@@ -15,6 +15,35 @@ pub fn create_env(policy: &ShellEnvironmentPolicy) -> HashMap<String, String> {
     populate_env(std::env::vars(), policy)
 }
 
+/// Narrow an inherited environment for a single tool: drop variables matching
+/// any `exclude` pattern and, when `include` is non-empty, keep only variables
+/// matching one of its patterns.
+pub fn filter_inherited_env(
+    env: &mut HashMap<String, String>,
+    include: &[EnvironmentVariablePattern],
+    exclude: &[EnvironmentVariablePattern],
+) {
+    env.retain(|name, _| {
+        !exclude.iter().any(|pattern| pattern.matches(name))
+            && (include.is_empty() || include.iter().any(|pattern| pattern.matches(name)))
+    });
+}
+
+/// Variables from `vars` (normally the parent process environment) whose
+/// names match any of `patterns`. Used for a custom tool's `env_passthrough`,
+/// which forwards them regardless of the shell environment policy.
+pub fn passthrough_env<I>(vars: I, patterns: &[EnvironmentVariablePattern]) -> Vec<(String, String)>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    if patterns.is_empty() {
+        return Vec::new();
+    }
+    vars.into_iter()
+        .filter(|(name, _)| patterns.iter().any(|pattern| pattern.matches(name)))
+        .collect()
+}
+
 fn populate_env<I>(vars: I, policy: &ShellEnvironmentPolicy) -> HashMap<String, String>
 where
     I: IntoIterator<Item = (String, String)>,
@@ -126,6 +155,31 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn passthrough_forwards_only_matching_variables() {
+        let vars = make_vars(&[
+            ("GITHUB_TOKEN", "ghp_abc"),
+            ("GITHUB_REPOSITORY", "openai/codex"),
+            ("GITLAB_TOKEN", "glpat"),
+            ("MY_GITHUB_USER", "octocat"),
+            ("PATH", "/usr/bin"),
+        ]);
+
+        let mut forwarded = passthrough_env(
+            vars,
+            &[EnvironmentVariablePattern::new_case_insensitive("GITHUB_*")],
+        );
+        forwarded.sort();
+
+        assert_eq!(
+            forwarded,
+            make_vars(&[
+                ("GITHUB_REPOSITORY", "openai/codex"),
+                ("GITHUB_TOKEN", "ghp_abc"),
+            ])
+        );
+    }
+
     #[test]
     fn test_include_only() {
         let vars = make_vars(&[("PATH", "/usr/bin"), ("FOO", "bar")]);
@@ -217,4 +271,31 @@ mod tests {
         };
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn filter_inherited_env_applies_tool_include_and_exclude() {
+        let mut env: HashMap<String, String> = hashmap! {
+            "PATH".to_string() => "/usr/bin".to_string(),
+            "AWS_ACCESS_KEY_ID".to_string() => "akid".to_string(),
+            "aws_profile".to_string() => "dev".to_string(),
+            "GITHUB_TOKEN".to_string() => "ghp".to_string(),
+        };
+        let patterns = |raw: &[&str]| -> Vec<EnvironmentVariablePattern> {
+            raw.iter()
+                .map(|pattern| EnvironmentVariablePattern::new_case_insensitive(pattern))
+                .collect()
+        };
+
+        filter_inherited_env(
+            &mut env,
+            &patterns(&["PATH", "AWS_*"]),
+            &patterns(&["AWS_ACCESS_*"]),
+        );
+
+        let expected: HashMap<String, String> = hashmap! {
+            "PATH".to_string() => "/usr/bin".to_string(),
+            "aws_profile".to_string() => "dev".to_string(),
+        };
+        assert_eq!(env, expected);
+    }
 }
@@ -1,3 +1,4 @@
+use codex_rmcp_client::McpServerError;
 use thiserror::Error;
 
 #[derive(Debug, Error, PartialEq)]
@@ -11,4 +12,18 @@ pub enum FunctionCallError {
     MissingLocalShellCallId,
     #[error("Fatal error: {0}")]
     Fatal(String),
+    /// An MCP server rejected the tool call with a JSON-RPC error.
+    #[error("{0}")]
+    Mcp(McpServerError),
+}
+
+impl FunctionCallError {
+    /// Content returned to the model when the call fails without aborting the
+    /// turn. MCP errors keep their code/message/data as a JSON object.
+    pub(crate) fn model_output(&self) -> String {
+        match self {
+            Self::Mcp(error) => serde_json::json!({ "error": error }).to_string(),
+            other => other.to_string(),
+        }
+    }
 }
@@ -109,6 +109,38 @@ pub async fn collect_git_info(cwd: &Path) -> Option<GitInfo> {
     Some(git_info)
 }
 
+/// Branch and uncommitted changes of a worktree, as reported to stop hooks.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GitWorktreeStatus {
+    /// Checked-out branch; `None` on a detached HEAD.
+    pub branch: Option<String>,
+    /// `git status --porcelain` lines, e.g. ` M src/lib.rs` or `?? notes.md`.
+    pub dirty_files: Vec<String>,
+}
+
+/// Collect the current branch and `git status --porcelain` output for `cwd`.
+/// Returns `None` outside a git repository or if `git status` fails.
+pub async fn collect_worktree_status(cwd: &Path) -> Option<GitWorktreeStatus> {
+    let (status_result, branch) = tokio::join!(
+        run_git_command_with_timeout(&["status", "--porcelain"], cwd),
+        current_branch_name(cwd)
+    );
+    let output = status_result?;
+    if !output.status.success() {
+        return None;
+    }
+    let dirty_files = String::from_utf8(output.stdout)
+        .ok()?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect();
+    Some(GitWorktreeStatus {
+        branch,
+        dirty_files,
+    })
+}
+
 /// A minimal commit summary entry used for pickers (subject + timestamp + sha).
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CommitLogEntry {
@@ -856,6 +888,50 @@ mod tests {
         // This is acceptable behavior
     }
 
+    #[tokio::test]
+    async fn test_worktree_status_reaches_stop_hook_payload() {
+        use crate::config::types::StopHookResponseItems;
+        use crate::tools::hooks::StopHookEvent;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let repo_path = create_test_git_repo(&temp_dir).await;
+        Command::new("git")
+            .args(["checkout", "-b", "stop-hook-branch"])
+            .current_dir(&repo_path)
+            .output()
+            .await
+            .expect("git checkout");
+        fs::write(repo_path.join("test.txt"), "changed").unwrap();
+        fs::write(repo_path.join("new.txt"), "untracked").unwrap();
+
+        let status = collect_worktree_status(&repo_path).await;
+        let event = StopHookEvent::new(
+            "conversation".to_string(),
+            "turn".to_string(),
+            repo_path.display().to_string(),
+            None,
+            Vec::new(),
+            StopHookResponseItems::Full,
+            None,
+        )
+        .with_git(status);
+
+        let payload = serde_json::to_value(event).unwrap();
+        assert_eq!(
+            payload["git"],
+            serde_json::json!({
+                "branch": "stop-hook-branch",
+                "dirty_files": [" M test.txt", "?? new.txt"],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_worktree_status_non_git_directory() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        assert_eq!(collect_worktree_status(temp_dir.path()).await, None);
+    }
+
     #[tokio::test]
     async fn test_collect_git_info_with_remote() {
         let temp_dir = TempDir::new().expect("Failed to create temp dir");
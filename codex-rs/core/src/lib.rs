@@ -84,11 +84,18 @@ mod tools;
 pub mod turn_diff_tracker;
 pub use rollout::ARCHIVED_SESSIONS_SUBDIR;
 pub use rollout::INTERACTIVE_SESSION_SOURCES;
+pub use rollout::PENDING_TOOL_OUTPUT_SENTINEL;
 pub use rollout::PatchedToolCall;
+pub use rollout::PatchedToolCalls;
+pub use rollout::REPLAY_TURN_ID;
+pub use rollout::ROLLOUT_ARCHIVE_EXTENSION;
+pub use rollout::RolloutArchive;
 pub use rollout::RolloutRecorder;
 pub use rollout::SESSIONS_SUBDIR;
 pub use rollout::SessionMeta;
 pub use rollout::ToolResultKind;
+pub use rollout::ToolResultTarget;
+pub use rollout::archive_rollout;
 pub use rollout::find_conversation_path_by_id_str;
 pub use rollout::find_conversation_path_by_selector_str;
 pub use rollout::list::ConversationItem;
@@ -96,7 +103,18 @@ pub use rollout::list::ConversationsPage;
 pub use rollout::list::Cursor;
 pub use rollout::list::parse_cursor;
 pub use rollout::list::read_head_for_summary;
+pub use rollout::read_rollout_lines;
 pub use rollout::replace_last_tool_result;
+pub use rollout::replace_last_tool_result_and_archive;
+pub use rollout::replace_last_tool_result_matching;
+pub use rollout::replace_tool_results;
+pub use rollout::replay_stop_hook;
+pub use rollout::rewrite_tool_outputs;
+pub use rollout::stop_hook_event_from_rollout;
+pub use rollout::write_rollout_lines;
+pub use tools::hooks::StopHook;
+pub use tools::hooks::StopHookDirective;
+pub use tools::hooks::StopHookEvent;
 mod function_tool;
 mod state;
 mod tasks;
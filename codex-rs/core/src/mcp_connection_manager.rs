@@ -551,12 +551,14 @@ impl McpConnectionManager {
         aggregated
     }
 
-    /// Invoke the tool indicated by the (server, tool) pair.
+    /// Invoke the tool indicated by the (server, tool) pair, sending `meta`
+    /// as the request's `_meta`.
     pub async fn call_tool(
         &self,
         server: &str,
         tool: &str,
         arguments: Option<serde_json::Value>,
+        meta: Option<serde_json::Map<String, serde_json::Value>>,
     ) -> Result<mcp_types::CallToolResult> {
         let client = self.client_by_name(server).await?;
         if !client.tool_filter.allows(tool) {
@@ -567,7 +569,7 @@ impl McpConnectionManager {
 
         client
             .client
-            .call_tool(tool.to_string(), arguments, client.tool_timeout)
+            .call_tool(tool.to_string(), arguments, meta, client.tool_timeout)
             .await
             .with_context(|| format!("tool call failed for `{server}/{tool}`"))
     }
@@ -4,12 +4,14 @@ use tracing::error;
 
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::function_tool::FunctionCallError;
 use crate::protocol::EventMsg;
 use crate::protocol::McpInvocation;
 use crate::protocol::McpToolCallBeginEvent;
 use crate::protocol::McpToolCallEndEvent;
 use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseInputItem;
+use codex_rmcp_client::McpServerError;
 
 /// Handles the specified tool call dispatches the appropriate
 /// `McpToolCallBegin` and `McpToolCallEnd` events to the `Session`.
@@ -20,7 +22,7 @@ pub(crate) async fn handle_mcp_tool_call(
     server: String,
     tool_name: String,
     arguments: String,
-) -> ResponseInputItem {
+) -> Result<ResponseInputItem, FunctionCallError> {
     // Parse the `arguments` as JSON. An empty string is OK, but invalid JSON
     // is not.
     let arguments_value = if arguments.trim().is_empty() {
@@ -30,14 +32,14 @@ pub(crate) async fn handle_mcp_tool_call(
             Ok(value) => Some(value),
             Err(e) => {
                 error!("failed to parse tool call arguments: {e}");
-                return ResponseInputItem::FunctionCallOutput {
+                return Ok(ResponseInputItem::FunctionCallOutput {
                     call_id: call_id.clone(),
                     output: FunctionCallOutputPayload {
                         content: format!("err: {e}"),
                         success: Some(false),
                         ..Default::default()
                     },
-                };
+                });
             }
         }
     };
@@ -56,10 +58,16 @@ pub(crate) async fn handle_mcp_tool_call(
 
     let start = Instant::now();
     // Perform the tool call.
+    let meta = correlation_meta(&call_id, sess, turn_context);
     let result = sess
-        .call_tool(&server, &tool_name, arguments_value.clone())
-        .await
-        .map_err(|e| format!("tool call error: {e:?}"));
+        .call_tool(&server, &tool_name, arguments_value.clone(), Some(meta))
+        .await;
+    let server_error = result
+        .as_ref()
+        .err()
+        .and_then(|e| e.downcast_ref::<McpServerError>())
+        .cloned();
+    let result = result.map_err(|e| format!("tool call error: {e:?}"));
     if let Err(e) = &result {
         tracing::warn!("MCP tool call error: {e:?}");
     }
@@ -72,7 +80,29 @@ pub(crate) async fn handle_mcp_tool_call(
 
     notify_mcp_tool_call_event(sess, turn_context, tool_call_end_event.clone()).await;
 
-    ResponseInputItem::McpToolCallOutput { call_id, result }
+    // Structured server errors are handed to the router so the model sees the
+    // JSON-RPC code/message/data rather than a debug string.
+    if let Some(error) = server_error {
+        return Err(FunctionCallError::Mcp(error));
+    }
+    Ok(ResponseInputItem::McpToolCallOutput { call_id, result })
+}
+
+/// Identifiers sent as the `tools/call` request's `_meta` so MCP servers can
+/// correlate the call with Codex's own logs and rollouts.
+fn correlation_meta(
+    call_id: &str,
+    sess: &Session,
+    turn_context: &TurnContext,
+) -> serde_json::Map<String, serde_json::Value> {
+    serde_json::Map::from_iter([
+        ("call_id".to_string(), call_id.into()),
+        (
+            "conversation_id".to_string(),
+            sess.conversation_id().to_string().into(),
+        ),
+        ("turn_id".to_string(), turn_context.sub_id.clone().into()),
+    ])
 }
 
 async fn notify_mcp_tool_call_event(sess: &Session, turn_context: &TurnContext, event: EventMsg) {
@@ -1,8 +1,12 @@
 use std::collections::HashMap;
 
+use crate::tools::hooks::ToolCallSnapshot;
+use crate::tools::hooks::ToolHook;
 use codex_protocol::models::FunctionCallOutputPayload;
+use codex_protocol::protocol::SandboxPolicy;
 use tokio::sync::Mutex;
 use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Clone, Debug)]
 pub(crate) struct PendingToolMetadata {
@@ -10,12 +14,26 @@ pub(crate) struct PendingToolMetadata {
     pub(crate) tool_name: String,
     pub(crate) turn_id: String,
     pub(crate) note: Option<String>,
+    /// Hook to notify once the result is delivered.
+    pub(crate) hook: Option<PendingToolHook>,
+}
+
+/// The turn's tool hook, remembered so it still hears about a result that is
+/// delivered after the turn has moved on.
+#[derive(Clone, Debug)]
+pub(crate) struct PendingToolHook {
+    pub(crate) hook: ToolHook,
+    /// The call as the hook saw it.
+    pub(crate) call: ToolCallSnapshot,
+    pub(crate) sandbox_policy: SandboxPolicy,
 }
 
 struct PendingToolEntry {
     metadata: PendingToolMetadata,
     receiver: Option<oneshot::Receiver<FunctionCallOutputPayload>>,
     sender: Option<oneshot::Sender<FunctionCallOutputPayload>>,
+    /// Chunks appended before the final delivery, in arrival order.
+    partial_output: String,
 }
 
 impl PendingToolEntry {
@@ -25,8 +43,24 @@ impl PendingToolEntry {
             metadata,
             receiver: Some(rx),
             sender: Some(tx),
+            partial_output: String::new(),
         }
     }
+
+    /// Hand `payload`, prefixed with any buffered chunks, to the waiter.
+    fn deliver(
+        mut self,
+        mut payload: FunctionCallOutputPayload,
+    ) -> (PendingToolMetadata, FunctionCallOutputPayload) {
+        if !self.partial_output.is_empty() {
+            self.partial_output.push_str(&payload.content);
+            payload.content = self.partial_output;
+        }
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.send(payload.clone());
+        }
+        (self.metadata, payload)
+    }
 }
 
 pub(crate) struct PendingToolManager {
@@ -46,12 +80,14 @@ impl PendingToolManager {
         tool_name: String,
         turn_id: String,
         note: Option<String>,
+        hook: Option<PendingToolHook>,
     ) -> PendingToolMetadata {
         let metadata = PendingToolMetadata {
             call_id: call_id.clone(),
             tool_name,
             turn_id,
             note,
+            hook,
         };
         let mut guard = self.entries.lock().await;
         guard.insert(call_id, PendingToolEntry::new(metadata.clone()));
@@ -71,17 +107,145 @@ impl PendingToolManager {
             .and_then(|entry| entry.receiver.take().map(|rx| (entry.metadata.clone(), rx)))
     }
 
+    /// Buffer `chunk` for `call_id` without resolving it. Returns `None` when
+    /// no such call is pending.
+    pub(crate) async fn append(&self, call_id: &str, chunk: &str) -> Option<PendingToolMetadata> {
+        let mut guard = self.entries.lock().await;
+        let entry = guard.get_mut(call_id)?;
+        entry.partial_output.push_str(chunk);
+        Some(entry.metadata.clone())
+    }
+
+    /// The metadata of `call_id`, or `None` when no such call is pending.
+    pub(crate) async fn metadata(&self, call_id: &str) -> Option<PendingToolMetadata> {
+        let guard = self.entries.lock().await;
+        guard.get(call_id).map(|entry| entry.metadata.clone())
+    }
+
+    /// Resolve `call_id` with `payload`, whose content is appended to any
+    /// chunks buffered by [`PendingToolManager::append`]. Returns the call's
+    /// metadata and the payload that was handed to the waiter.
     pub(crate) async fn resolve(
         &self,
         call_id: &str,
         payload: FunctionCallOutputPayload,
-    ) -> Option<PendingToolMetadata> {
+    ) -> Option<(PendingToolMetadata, FunctionCallOutputPayload)> {
         let mut guard = self.entries.lock().await;
-        guard.remove(call_id).map(|mut entry| {
-            if let Some(sender) = entry.sender.take() {
-                let _ = sender.send(payload);
+        guard.remove(call_id).map(|entry| entry.deliver(payload))
+    }
+
+    /// Drop every pending entry that belongs to `turn_id`. Dropping the sender
+    /// closes the paired receiver, so anything still awaiting it unblocks.
+    pub(crate) async fn cancel_turn(&self, turn_id: &str) -> Vec<PendingToolMetadata> {
+        let mut guard = self.entries.lock().await;
+        guard
+            .extract_if(|_, entry| entry.metadata.turn_id == turn_id)
+            .map(|(_, entry)| entry.metadata)
+            .collect()
+    }
+}
+
+/// Wait for a pending tool result, giving up when the turn is cancelled or the
+/// entry is cancelled/dropped. Returns `None` in both cases.
+pub(crate) async fn wait_for_result(
+    receiver: oneshot::Receiver<FunctionCallOutputPayload>,
+    cancellation_token: &CancellationToken,
+) -> Option<FunctionCallOutputPayload> {
+    tokio::select! {
+        result = receiver => result.ok(),
+        _ = cancellation_token.cancelled() => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    async fn register_call(manager: &PendingToolManager, call_id: &str, turn_id: &str) {
+        manager
+            .register(
+                call_id.to_string(),
+                "wait_for_reply".to_string(),
+                turn_id.to_string(),
+                None,
+                None,
+            )
+            .await;
+    }
+
+    #[tokio::test]
+    async fn cancelling_turn_token_unblocks_waiting_receiver() {
+        let manager = PendingToolManager::new();
+        register_call(&manager, "call-1", "turn-1").await;
+        let (_, receiver) = manager.take_receiver("call-1").await.expect("receiver");
+
+        let token = CancellationToken::new();
+        let waiter = tokio::spawn({
+            let token = token.clone();
+            async move { wait_for_result(receiver, &token).await }
+        });
+        token.cancel();
+
+        assert_eq!(waiter.await.expect("join"), None);
+    }
+
+    #[tokio::test]
+    async fn appended_chunks_are_delivered_with_the_final_output() {
+        let manager = PendingToolManager::new();
+        register_call(&manager, "call-1", "turn-1").await;
+        let (_, receiver) = manager.take_receiver("call-1").await.expect("receiver");
+
+        assert!(
+            manager
+                .append("call-1", "step 1 approved\n")
+                .await
+                .is_some()
+        );
+        assert!(
+            manager
+                .append("call-1", "step 2 approved\n")
+                .await
+                .is_some()
+        );
+        assert!(manager.append("call-unknown", "ignored").await.is_none());
+        manager
+            .resolve(
+                "call-1",
+                FunctionCallOutputPayload {
+                    content: "all steps approved".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("pending call");
+
+        assert_eq!(
+            receiver.await.expect("result"),
+            FunctionCallOutputPayload {
+                content: "step 1 approved\nstep 2 approved\nall steps approved".to_string(),
+                ..Default::default()
             }
-            entry.metadata
-        })
+        );
+    }
+
+    #[tokio::test]
+    async fn cancel_turn_closes_receivers_for_that_turn_only() {
+        let manager = PendingToolManager::new();
+        register_call(&manager, "call-1", "turn-1").await;
+        register_call(&manager, "call-2", "turn-2").await;
+        let (_, receiver) = manager.take_receiver("call-1").await.expect("receiver");
+
+        let cancelled = manager.cancel_turn("turn-1").await;
+
+        assert_eq!(
+            cancelled
+                .into_iter()
+                .map(|metadata| metadata.call_id)
+                .collect::<Vec<_>>(),
+            vec!["call-1".to_string()]
+        );
+        assert!(receiver.await.is_err());
+        assert!(manager.take_receiver("call-2").await.is_some());
     }
 }
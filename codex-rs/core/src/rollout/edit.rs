@@ -1,10 +1,25 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::io;
+use std::io::Read;
+use std::io::Write;
 use std::path::Path;
+use std::path::PathBuf;
 
 use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::EventMsg;
 use codex_protocol::protocol::RolloutItem;
 use codex_protocol::protocol::RolloutLine;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use tempfile::NamedTempFile;
+
+use crate::compact::content_items_to_text;
+use crate::config::types::StopHookResponseItems;
+use crate::tools::hooks::StopHook;
+use crate::tools::hooks::StopHookEvent;
 
 /// Describes which type of tool output was patched.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -18,6 +33,46 @@ pub enum ToolResultKind {
 pub struct PatchedToolCall {
     pub call_id: String,
     pub kind: ToolResultKind,
+    /// The output's content before it was overwritten, e.g. the placeholder that was replaced.
+    pub previous_content: String,
+}
+
+/// Result of [`replace_tool_results`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PatchedToolCalls {
+    /// Outputs that were overwritten, in rollout order.
+    pub patched: Vec<PatchedToolCall>,
+    /// Requested call ids with no tool output in the rollout, sorted.
+    pub missing: Vec<String>,
+}
+
+/// Placeholder content recorded for tool outputs that are still awaiting a real result.
+pub const PENDING_TOOL_OUTPUT_SENTINEL: &str = "pending";
+
+/// Selects which tool output [`replace_last_tool_result_matching`] patches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToolResultTarget {
+    /// The most recent tool output, whatever its state.
+    #[default]
+    Latest,
+    /// The most recent output that is still a placeholder: either its content equals
+    /// [`PENDING_TOOL_OUTPUT_SENTINEL`] or it is a function output with `success: Some(false)`.
+    /// Completed outputs recorded after the placeholder are skipped.
+    PendingOrFailed,
+}
+
+/// Extension of gzip-compressed rollouts. Every read and write in this module handles paths
+/// ending in it transparently.
+pub const ROLLOUT_ARCHIVE_EXTENSION: &str = "gz";
+
+/// Whether an edit should also leave a gzip copy of the rollout next to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RolloutArchive {
+    #[default]
+    Never,
+    Always,
+    /// Only archive rollouts of at least this many bytes.
+    AboveBytes(u64),
 }
 
 /// Replace the payload of the most recent tool call output within the rollout at `path`.
@@ -28,41 +83,42 @@ pub async fn replace_last_tool_result(
     path: &Path,
     new_output: &str,
 ) -> io::Result<PatchedToolCall> {
-    let contents = tokio::fs::read_to_string(path).await?;
-    if contents.trim().is_empty() {
-        return Err(io::Error::other("rollout file is empty"));
-    }
+    replace_last_tool_result_matching(path, new_output, ToolResultTarget::Latest).await
+}
 
-    let mut lines: Vec<RolloutLine> = Vec::new();
-    for raw in contents.lines() {
-        if raw.trim().is_empty() {
-            continue;
-        }
-        let parsed: RolloutLine = serde_json::from_str(raw).map_err(|err| {
-            io::Error::other(format!(
-                "failed to parse rollout line as JSON: {err}; offending line: {raw}"
-            ))
-        })?;
-        lines.push(parsed);
-    }
+/// Like [`replace_last_tool_result`], but only patches the most recent output selected by
+/// `target`.
+pub async fn replace_last_tool_result_matching(
+    path: &Path,
+    new_output: &str,
+    target: ToolResultTarget,
+) -> io::Result<PatchedToolCall> {
+    let mut lines = read_rollout_lines(path).await?;
 
     let mut patched: Option<PatchedToolCall> = None;
     for entry in lines.iter_mut().rev() {
         if let RolloutItem::ResponseItem(response) = &mut entry.item {
             match response {
-                ResponseItem::FunctionCallOutput { call_id, output } => {
+                ResponseItem::FunctionCallOutput { call_id, output }
+                    if target.matches_function_output(output) =>
+                {
+                    let previous_content = output.content.clone();
                     overwrite_function_output(output, new_output);
                     patched = Some(PatchedToolCall {
                         call_id: call_id.clone(),
                         kind: ToolResultKind::Function,
+                        previous_content,
                     });
                     break;
                 }
-                ResponseItem::CustomToolCallOutput { call_id, output } => {
-                    *output = new_output.to_string();
+                ResponseItem::CustomToolCallOutput { call_id, output }
+                    if target.matches_custom_output(output) =>
+                {
+                    let previous_content = std::mem::replace(output, new_output.to_string());
                     patched = Some(PatchedToolCall {
                         call_id: call_id.clone(),
                         kind: ToolResultKind::Custom,
+                        previous_content,
                     });
                     break;
                 }
@@ -71,23 +127,313 @@ pub async fn replace_last_tool_result(
         }
     }
 
-    let patched = patched.ok_or_else(|| {
-        io::Error::other("no tool call output found in rollout; nothing to replace")
+    let patched = patched.ok_or_else(|| match target {
+        ToolResultTarget::Latest => {
+            io::Error::other("no tool call output found in rollout; nothing to replace")
+        }
+        ToolResultTarget::PendingOrFailed => {
+            io::Error::other("no pending or failed tool call output found in rollout")
+        }
     })?;
 
+    write_rollout_lines(path, &lines).await?;
+    Ok(patched)
+}
+
+/// Like [`replace_last_tool_result_matching`], then archive the edited rollout according to
+/// `archive` (see [`archive_rollout`]). Returns the archive path when one was written.
+pub async fn replace_last_tool_result_and_archive(
+    path: &Path,
+    new_output: &str,
+    target: ToolResultTarget,
+    archive: RolloutArchive,
+) -> io::Result<(PatchedToolCall, Option<PathBuf>)> {
+    let patched = replace_last_tool_result_matching(path, new_output, target).await?;
+    let archive_path = archive_rollout(path, archive).await?;
+    Ok((patched, archive_path))
+}
+
+/// Write a gzip copy of the rollout at `path` to `<path>.gz`, replacing any earlier archive, and
+/// leave the active file in place. Returns `None` without writing when `archive` does not apply
+/// to the file's size.
+pub async fn archive_rollout(path: &Path, archive: RolloutArchive) -> io::Result<Option<PathBuf>> {
+    let size = tokio::fs::metadata(path).await?.len();
+    let should_archive = match archive {
+        RolloutArchive::Never => false,
+        RolloutArchive::Always => true,
+        RolloutArchive::AboveBytes(min_bytes) => size >= min_bytes,
+    };
+    if !should_archive {
+        return Ok(None);
+    }
+
+    let contents = tokio::fs::read(path).await?;
+    let mut archive_path = path.as_os_str().to_os_string();
+    archive_path.push(".");
+    archive_path.push(ROLLOUT_ARCHIVE_EXTENSION);
+    let archive_path = PathBuf::from(archive_path);
+    write_rollout_bytes(&archive_path, contents).await?;
+    Ok(Some(archive_path))
+}
+
+/// Replace the outputs of several tool calls in one read/rewrite pass over the rollout at `path`.
+/// `outputs` maps call ids to their new content; for each id the most recent matching output is
+/// patched. Ids without an output are reported in [`PatchedToolCalls::missing`] rather than
+/// failing the edit, and the file is left untouched when nothing matched.
+pub async fn replace_tool_results(
+    path: &Path,
+    outputs: &HashMap<String, String>,
+) -> io::Result<PatchedToolCalls> {
+    let mut lines = read_rollout_lines(path).await?;
+
+    let mut patched = Vec::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+    for entry in lines.iter_mut().rev() {
+        let RolloutItem::ResponseItem(response) = &mut entry.item else {
+            continue;
+        };
+        match response {
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                if let Some((id, new_output)) = outputs.get_key_value(call_id.as_str())
+                    && seen.insert(id)
+                {
+                    let previous_content = output.content.clone();
+                    overwrite_function_output(output, new_output);
+                    patched.push(PatchedToolCall {
+                        call_id: call_id.clone(),
+                        kind: ToolResultKind::Function,
+                        previous_content,
+                    });
+                }
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                if let Some((id, new_output)) = outputs.get_key_value(call_id.as_str())
+                    && seen.insert(id)
+                {
+                    let previous_content = std::mem::replace(output, new_output.clone());
+                    patched.push(PatchedToolCall {
+                        call_id: call_id.clone(),
+                        kind: ToolResultKind::Custom,
+                        previous_content,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    patched.reverse();
+
+    let mut missing: Vec<String> = outputs
+        .keys()
+        .filter(|id| !seen.contains(id.as_str()))
+        .cloned()
+        .collect();
+    missing.sort();
+
+    if !patched.is_empty() {
+        write_rollout_lines(path, &lines).await?;
+    }
+    Ok(PatchedToolCalls { patched, missing })
+}
+
+/// Apply `rewrite` to the content of every function and custom tool output in the rollout at
+/// `path`, replacing the outputs for which it returns `Some`. Returns how many outputs were
+/// rewritten; the file is left untouched when none were.
+///
+/// Intended for bulk edits such as redacting secrets that leaked into command output.
+pub async fn rewrite_tool_outputs(
+    path: &Path,
+    rewrite: impl Fn(&str) -> Option<String>,
+) -> io::Result<usize> {
+    let mut lines = read_rollout_lines(path).await?;
+
+    let mut rewritten = 0;
+    for entry in &mut lines {
+        let RolloutItem::ResponseItem(response) = &mut entry.item else {
+            continue;
+        };
+        match response {
+            ResponseItem::FunctionCallOutput { output, .. } => {
+                if let Some(new_output) = rewrite(&output.content) {
+                    overwrite_function_output(output, &new_output);
+                    rewritten += 1;
+                }
+            }
+            ResponseItem::CustomToolCallOutput { output, .. } => {
+                if let Some(new_output) = rewrite(output) {
+                    *output = new_output;
+                    rewritten += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if rewritten > 0 {
+        write_rollout_lines(path, &lines).await?;
+    }
+    Ok(rewritten)
+}
+
+/// Turn id reported to stop hooks replayed from a rollout. Rollouts do not record turn ids.
+pub const REPLAY_TURN_ID: &str = "replay";
+
+/// Re-run `hook` against the session recorded at `path`, as if its last turn had just finished.
+/// The model is not contacted; see [`stop_hook_event_from_rollout`] for how the event is built.
+/// Unlike a live stop hook, a failing command is reported as an error, and
+/// directives the hook prints (such as `export_to`) are ignored.
+pub async fn replay_stop_hook(path: &Path, hook: &StopHook) -> io::Result<()> {
+    let event = stop_hook_event_from_rollout(path, hook.response_items()).await?;
+    hook.spawn_and_send(event).await.map(|_| ())
+}
+
+/// Rebuild the [`StopHookEvent`] for the session recorded at `path`: every recorded response
+/// item, the last assistant message as `final_message`, the latest token usage, and the cwd of
+/// the last turn. The turn id is always [`REPLAY_TURN_ID`].
+pub async fn stop_hook_event_from_rollout(
+    path: &Path,
+    response_items_filter: StopHookResponseItems,
+) -> io::Result<StopHookEvent> {
+    let lines = read_rollout_lines(path).await?;
+
+    let mut conversation_id = None;
+    let mut cwd = None;
+    let mut response_items = Vec::new();
+    let mut token_usage = None;
+    for line in lines {
+        match line.item {
+            RolloutItem::SessionMeta(meta_line) => {
+                conversation_id = Some(meta_line.meta.id);
+                cwd = Some(meta_line.meta.cwd);
+            }
+            RolloutItem::TurnContext(turn_context) => cwd = Some(turn_context.cwd),
+            RolloutItem::ResponseItem(item) => response_items.push(item),
+            RolloutItem::EventMsg(EventMsg::TokenCount(event)) => {
+                if let Some(info) = event.info {
+                    token_usage = Some(info.last_token_usage);
+                }
+            }
+            RolloutItem::Compacted(_) | RolloutItem::EventMsg(_) => {}
+        }
+    }
+    let conversation_id = conversation_id
+        .ok_or_else(|| io::Error::other("rollout has no session metadata; cannot replay"))?;
+
+    let final_message = response_items.iter().rev().find_map(|item| match item {
+        ResponseItem::Message { role, content, .. } if role == "assistant" => {
+            content_items_to_text(content)
+        }
+        _ => None,
+    });
+
+    Ok(StopHookEvent::new(
+        conversation_id.to_string(),
+        REPLAY_TURN_ID.to_string(),
+        cwd.unwrap_or_default().display().to_string(),
+        final_message,
+        response_items,
+        response_items_filter,
+        token_usage,
+    ))
+}
+
+/// Read the rollout at `path` as text, decompressing it first when it is a gzip archive.
+pub(crate) async fn read_rollout_text(path: &Path) -> io::Result<String> {
+    if !is_archive_path(path) {
+        return tokio::fs::read_to_string(path).await;
+    }
+    let compressed = tokio::fs::read(path).await?;
+    tokio::task::spawn_blocking(move || {
+        let mut text = String::new();
+        GzDecoder::new(compressed.as_slice()).read_to_string(&mut text)?;
+        Ok(text)
+    })
+    .await
+    .map_err(|err| io::Error::other(format!("rollout decompress task failed: {err}")))?
+}
+
+/// Parse the rollout at `path` (plain or gzip-archived) into its lines, skipping blank ones.
+/// An empty rollout or a line that is not a valid [`RolloutLine`] is an error.
+pub async fn read_rollout_lines(path: &Path) -> io::Result<Vec<RolloutLine>> {
+    let contents = read_rollout_text(path).await?;
+    if contents.trim().is_empty() {
+        return Err(io::Error::other("rollout file is empty"));
+    }
+
+    let mut lines: Vec<RolloutLine> = Vec::new();
+    for raw in contents.lines() {
+        if raw.trim().is_empty() {
+            continue;
+        }
+        let parsed: RolloutLine = serde_json::from_str(raw).map_err(|err| {
+            io::Error::other(format!(
+                "failed to parse rollout line as JSON: {err}; offending line: {raw}"
+            ))
+        })?;
+        lines.push(parsed);
+    }
+    Ok(lines)
+}
+
+/// Re-encode `lines` and atomically replace the rollout at `path` with them, so a crash
+/// mid-write never leaves a truncated rollout behind.
+pub async fn write_rollout_lines(path: &Path, lines: &[RolloutLine]) -> io::Result<()> {
     let mut buffer = String::new();
-    for line in &lines {
+    for line in lines {
         let encoded = serde_json::to_string(line)
             .map_err(|err| io::Error::other(format!("failed to encode rollout line: {err}")))?;
         buffer.push_str(&encoded);
         buffer.push('\n');
     }
+    write_rollout_bytes(path, buffer.into_bytes()).await
+}
 
-    tokio::fs::write(path, buffer).await?;
-    Ok(patched)
+/// Atomically replace `path` with `contents`, gzip-compressing them first when `path` is an
+/// archive.
+async fn write_rollout_bytes(path: &Path, contents: Vec<u8>) -> io::Result<()> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let contents = if is_archive_path(&path) {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&contents)?;
+            encoder.finish()?
+        } else {
+            contents
+        };
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let tmp = NamedTempFile::new_in(dir)?;
+        std::fs::write(tmp.path(), contents)?;
+        tmp.persist(&path).map_err(|err| err.error)?;
+        Ok(())
+    })
+    .await
+    .map_err(|err| io::Error::other(format!("rollout write task failed: {err}")))?
+}
+
+fn is_archive_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == ROLLOUT_ARCHIVE_EXTENSION)
 }
 
 fn overwrite_function_output(output: &mut FunctionCallOutputPayload, new_output: &str) {
     output.content = new_output.to_string();
     output.content_items = None;
 }
+
+impl ToolResultTarget {
+    fn matches_function_output(self, output: &FunctionCallOutputPayload) -> bool {
+        match self {
+            Self::Latest => true,
+            Self::PendingOrFailed => {
+                output.success == Some(false) || output.content == PENDING_TOOL_OUTPUT_SENTINEL
+            }
+        }
+    }
+
+    fn matches_custom_output(self, output: &str) -> bool {
+        match self {
+            Self::Latest => true,
+            Self::PendingOrFailed => output == PENDING_TOOL_OUTPUT_SENTINEL,
+        }
+    }
+}
@@ -14,9 +14,24 @@ pub(crate) mod policy;
 pub mod recorder;
 
 pub use codex_protocol::protocol::SessionMeta;
+pub use edit::PENDING_TOOL_OUTPUT_SENTINEL;
 pub use edit::PatchedToolCall;
+pub use edit::PatchedToolCalls;
+pub use edit::REPLAY_TURN_ID;
+pub use edit::ROLLOUT_ARCHIVE_EXTENSION;
+pub use edit::RolloutArchive;
 pub use edit::ToolResultKind;
+pub use edit::ToolResultTarget;
+pub use edit::archive_rollout;
+pub use edit::read_rollout_lines;
 pub use edit::replace_last_tool_result;
+pub use edit::replace_last_tool_result_and_archive;
+pub use edit::replace_last_tool_result_matching;
+pub use edit::replace_tool_results;
+pub use edit::replay_stop_hook;
+pub use edit::rewrite_tool_outputs;
+pub use edit::stop_hook_event_from_rollout;
+pub use edit::write_rollout_lines;
 pub(crate) use error::map_session_init_error;
 pub use list::find_conversation_path_by_id_str;
 pub use list::find_conversation_path_by_selector_str;
@@ -19,6 +19,7 @@ use tracing::info;
 use tracing::warn;
 
 use super::SESSIONS_SUBDIR;
+use super::edit::read_rollout_text;
 use super::list::ConversationsPage;
 use super::list::Cursor;
 use super::list::get_conversations;
@@ -209,7 +210,7 @@ impl RolloutRecorder {
 
     pub async fn get_rollout_history(path: &Path) -> std::io::Result<InitialHistory> {
         info!("Resuming rollout from {path:?}");
-        let text = tokio::fs::read_to_string(path).await?;
+        let text = read_rollout_text(path).await?;
         if text.trim().is_empty() {
             return Err(IoError::other("empty session file"));
         }
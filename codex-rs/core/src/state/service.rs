@@ -6,6 +6,9 @@ use crate::exec_policy::ExecPolicyManager;
 use crate::mcp_connection_manager::McpConnectionManager;
 use crate::models_manager::manager::ModelsManager;
 use crate::skills::SkillsManager;
+use crate::tools::hooks::StopHook;
+use crate::tools::hooks::ToolHook;
+use crate::tools::rate_limit::ShellRateLimiter;
 use crate::tools::sandboxing::ApprovalStore;
 use crate::unified_exec::UnifiedExecSessionManager;
 use crate::user_notification::UserNotifier;
@@ -28,4 +31,8 @@ pub(crate) struct SessionServices {
     pub(crate) otel_manager: OtelManager,
     pub(crate) tool_approvals: Mutex<ApprovalStore>,
     pub(crate) skills_manager: Arc<SkillsManager>,
+    pub(crate) shell_rate_limiter: ShellRateLimiter,
+    /// Built once per session so a missing hook program is reported once.
+    pub(crate) tool_hook: Option<ToolHook>,
+    pub(crate) stop_hook: Option<StopHook>,
 }
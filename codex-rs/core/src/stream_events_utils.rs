@@ -119,12 +119,15 @@ pub(crate) async fn handle_output_item_done(
             output.needs_follow_up = true;
         }
         // The tool request should be answered directly (or was denied); push that response into the transcript.
-        Err(FunctionCallError::RespondToModel(message))
-        | Err(FunctionCallError::Denied(message)) => {
+        Err(
+            err @ (FunctionCallError::RespondToModel(_)
+            | FunctionCallError::Denied(_)
+            | FunctionCallError::Mcp(_)),
+        ) => {
             let response = ResponseInputItem::FunctionCallOutput {
                 call_id: String::new(),
                 output: FunctionCallOutputPayload {
-                    content: message,
+                    content: err.model_output(),
                     ..Default::default()
                 },
             };
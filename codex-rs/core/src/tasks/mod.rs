@@ -218,6 +218,7 @@ impl Session {
 
         trace!(task_kind = ?task.kind, sub_id, "aborting running task");
         task.cancellation_token.cancel();
+        self.cancel_pending_tools(task.turn_context.as_ref()).await;
         let session_task = task.task;
 
         select! {
@@ -1,11 +1,26 @@
 use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::FunctionCallOutputContentItem;
+use codex_protocol::models::ResponseInputItem;
+use codex_protocol::user_input::UserInput;
+use codex_utils_string::take_bytes_at_char_boundary;
+use serde::Deserialize;
 use serde_json::Value;
+use tracing::warn;
 
 use crate::exec::ExecParams;
 use crate::exec_env::create_env;
+use crate::exec_env::filter_inherited_env;
+use crate::exec_env::passthrough_env;
 use crate::function_tool::FunctionCallError;
+use crate::protocol::SandboxPolicy;
 use crate::sandboxing::SandboxPermissions;
 use crate::tools::context::ToolInvocation;
 use crate::tools::context::ToolOutput;
@@ -15,14 +30,20 @@ use crate::tools::registry::ToolHandler;
 use crate::tools::registry::ToolKind;
 use crate::tools::spec::ConfigCustomTool;
 
+/// Runs config-defined tools through the shell runtime, so their stdout and
+/// stderr stream to clients as `ExecCommandOutputDelta` events while the
+/// command runs and the aggregated output becomes the tool result.
 pub struct CustomToolHandler {
     tools: HashMap<String, ConfigCustomTool>,
 }
 
 impl CustomToolHandler {
+    /// Disabled tools are dropped here, so a call that still reaches the
+    /// handler is reported as unsupported.
     pub fn new(tools: Vec<ConfigCustomTool>) -> Self {
         let map = tools
             .into_iter()
+            .filter(|tool| tool.enabled)
             .map(|tool| (tool.name.clone(), tool))
             .collect();
         Self { tools: map }
@@ -65,6 +86,7 @@ impl ToolHandler for CustomToolHandler {
                 "failed to parse arguments for {tool_name}: {err}"
             ))
         })?;
+        check_required_args(&args_json, &tool.required, tool_name.as_str())?;
         let serialized_args = serde_json::to_string(&args_json).map_err(|err| {
             FunctionCallError::RespondToModel(format!(
                 "failed to serialize arguments for {tool_name}: {err}"
@@ -72,6 +94,12 @@ impl ToolHandler for CustomToolHandler {
         })?;
 
         let mut env = create_env(&turn.shell_environment_policy);
+        filter_inherited_env(&mut env, &tool.env_include, &tool.env_exclude);
+        env.extend(passthrough_env(std::env::vars(), &tool.env_passthrough));
+        if let Some(env_file) = &tool.env_file {
+            let path = turn.resolve_path(Some(env_file.clone()));
+            env.extend(read_env_file(&path, tool_name.as_str())?);
+        }
         env.extend(tool.env.clone());
         env.insert("CODEX_TOOL_ARGS_JSON".to_string(), serialized_args.clone());
         env.insert("CODEX_TOOL_NAME".to_string(), tool.name.clone());
@@ -91,9 +119,20 @@ impl ToolHandler for CustomToolHandler {
             _ => SandboxPermissions::UseDefault,
         };
 
+        let home = dirs::home_dir();
+        let mut command = tool.command.clone();
+        if let Some(program) = command.first_mut() {
+            *program = resolve_tool_program(program, &turn.cwd, home.as_deref());
+        }
+        let cwd = turn.resolve_path(tool.cwd.as_deref().map(|cwd| {
+            expand_home(cwd, home.as_deref())
+                .to_string_lossy()
+                .into_owned()
+        }));
+
         let exec_params = ExecParams {
-            command: tool.command.clone(),
-            cwd: turn.resolve_path(tool.cwd.clone()),
+            command,
+            cwd,
             expiration: tool.timeout_ms.into(),
             env,
             sandbox_permissions,
@@ -101,32 +140,721 @@ impl ToolHandler for CustomToolHandler {
             arg0: None,
         };
 
+        let output_file = tool.output_as_file.then(|| {
+            tool_output_path(
+                &turn.sandbox_policy,
+                &turn.cwd,
+                tool_name.as_str(),
+                &call_id,
+            )
+        });
         let output = ShellHandler::run_exec_like(
             tool_name.as_str(),
             exec_params,
             session,
-            turn,
+            Arc::clone(&turn),
             tracker,
             call_id,
             false,
         )
-        .await?;
+        .await
+        .map_err(|err| match err {
+            FunctionCallError::RespondToModel(content) => FunctionCallError::RespondToModel(
+                with_exit_code(&truncate_output(&content, tool.max_output_bytes)),
+            ),
+            err => err,
+        })?;
+        let output = match (output, output_file) {
+            (
+                ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success,
+                },
+                Some(path),
+            ) => ToolOutput::Function {
+                content: write_output_file(&content, &path, tool_name.as_str()).await?,
+                content_items: None,
+                success,
+            },
+            (output, _) => output,
+        };
+        let output = match output {
+            ToolOutput::Function {
+                content,
+                content_items: None,
+                success,
+            } => ToolOutput::Function {
+                content_items: structured_content_items(&content, &turn.cwd),
+                content,
+                success,
+            },
+            output => output,
+        };
 
-        if tool.hibernate_after_call
-            && let ToolOutput::Function {
+        let output = match output {
+            ToolOutput::Function {
                 content,
                 content_items,
                 success,
-            } = output
-        {
-            return Ok(ToolOutput::Pending {
+            } if should_shutdown(&content, tool.hibernate_after_call) => ToolOutput::Pending {
                 content,
                 content_items,
                 success,
                 shutdown: true,
-            });
+            },
+            output => output,
+        };
+        Ok(limit_output(output, tool.max_output_bytes))
+    }
+}
+
+/// Apply a tool's `max_output_bytes` to the text the model will see. Output
+/// already mapped to structured content items is left alone.
+fn limit_output(output: ToolOutput, max_bytes: Option<usize>) -> ToolOutput {
+    match output {
+        ToolOutput::Function {
+            content,
+            content_items: None,
+            success,
+        } => ToolOutput::Function {
+            content: truncate_output(&content, max_bytes),
+            content_items: None,
+            success,
+        },
+        ToolOutput::Pending {
+            content,
+            content_items: None,
+            success,
+            shutdown,
+        } => ToolOutput::Pending {
+            content: truncate_output(&content, max_bytes),
+            content_items: None,
+            success,
+            shutdown,
+        },
+        output => output,
+    }
+}
+
+/// Cut a tool's output down to its first `max_bytes` bytes (at a character
+/// boundary), followed by a `[truncated N bytes]` marker. For the structured
+/// exec format only the `output` field is cut, so the metadata still reaches
+/// the model.
+fn truncate_output(content: &str, max_bytes: Option<usize>) -> String {
+    let Some(max_bytes) = max_bytes else {
+        return content.to_string();
+    };
+    let truncate = |text: &str| {
+        let kept = take_bytes_at_char_boundary(text, max_bytes);
+        if kept.len() == text.len() {
+            return text.to_string();
+        }
+        format!("{kept}\n[truncated {} bytes]", text.len() - kept.len())
+    };
+    let Some(ExecReport { output, mut rest }) = ExecReport::parse(content) else {
+        return truncate(content);
+    };
+    rest.insert("output".to_string(), Value::String(truncate(&output)));
+    Value::Object(rest).to_string()
+}
+
+/// Copy a non-zero `metadata.exit_code` of a failed call to a top-level
+/// `exit_code` field, so the model can branch on it without digging through
+/// the exec metadata. Any other content is returned unchanged.
+fn with_exit_code(content: &str) -> String {
+    let Some(ExecReport { output, mut rest }) = ExecReport::parse(content) else {
+        return content.to_string();
+    };
+    let exit_code = rest
+        .get("metadata")
+        .and_then(|metadata| metadata.get("exit_code"))
+        .and_then(Value::as_i64)
+        .filter(|code| *code != 0);
+    let Some(exit_code) = exit_code else {
+        return content.to_string();
+    };
+    rest.insert("exit_code".to_string(), Value::from(exit_code));
+    rest.insert("output".to_string(), Value::String(output));
+    Value::Object(rest).to_string()
+}
+
+/// Reject a call that omits (or passes `null` for) any of the tool's
+/// `required` arguments, naming every missing one so the model can retry.
+fn check_required_args(
+    args: &Value,
+    required: &[String],
+    tool_name: &str,
+) -> Result<(), FunctionCallError> {
+    let missing: Vec<&str> = required
+        .iter()
+        .filter(|name| args.get(name.as_str()).is_none_or(Value::is_null))
+        .map(String::as_str)
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(FunctionCallError::RespondToModel(format!(
+        "missing required argument(s) for {tool_name}: {}",
+        missing.join(", ")
+    )))
+}
+
+/// Replace a leading `~` (alone or followed by `/`) with `home`. Other paths,
+/// and all paths when the home directory is unknown, are returned unchanged.
+fn expand_home(path: &str, home: Option<&Path>) -> PathBuf {
+    match (home, path.strip_prefix('~')) {
+        (Some(home), Some("")) => home.to_path_buf(),
+        (Some(home), Some(rest)) if rest.starts_with('/') => home.join(&rest[1..]),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Resolve a tool's program the way its `cwd` is resolved: `~` expands to the
+/// home directory and relative paths resolve against `workspace_root`. A bare
+/// name without a `/` is left for the usual `PATH` lookup.
+fn resolve_tool_program(program: &str, workspace_root: &Path, home: Option<&Path>) -> String {
+    let expanded = expand_home(program, home);
+    if expanded.is_absolute() || !program.contains('/') {
+        return expanded.to_string_lossy().into_owned();
+    }
+    workspace_root.join(expanded).to_string_lossy().into_owned()
+}
+
+/// Where an `output_as_file` tool's output is saved: one file per call under
+/// `.codex/tool-output/` in the workspace, named after the tool and call id.
+/// When the sandbox policy does not allow writing there, the file goes under
+/// the system temp dir instead, so the workspace is never written to behind
+/// the sandbox's back.
+fn tool_output_path(
+    sandbox_policy: &SandboxPolicy,
+    workspace_root: &Path,
+    tool_name: &str,
+    call_id: &str,
+) -> PathBuf {
+    let sanitize = |raw: &str| -> String {
+        raw.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    };
+    let file_name = format!("{}-{}.txt", sanitize(tool_name), sanitize(call_id));
+    let in_workspace = workspace_root
+        .join(".codex")
+        .join("tool-output")
+        .join(&file_name);
+    let writable = sandbox_policy.has_full_disk_write_access()
+        || sandbox_policy
+            .get_writable_roots_with_cwd(workspace_root)
+            .iter()
+            .any(|root| root.is_path_writable(&in_workspace));
+    if writable {
+        in_workspace
+    } else {
+        std::env::temp_dir()
+            .join("codex-tool-output")
+            .join(file_name)
+    }
+}
+
+/// Save the output of a successful `output_as_file` call to `path` and leave
+/// a pointer to the file in its place, keeping the exec metadata.
+async fn write_output_file(
+    content: &str,
+    path: &Path,
+    tool_name: &str,
+) -> Result<String, FunctionCallError> {
+    let Some(ExecReport { output, mut rest }) = ExecReport::parse(content) else {
+        return Ok(content.to_string());
+    };
+    let write = async {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, &output).await
+    };
+    write.await.map_err(|err| {
+        FunctionCallError::RespondToModel(format!(
+            "failed to write output of {tool_name} to {}: {err}",
+            path.display()
+        ))
+    })?;
+    let message = format!(
+        "Output of `{tool_name}` ({} bytes) was written to {}. Read that file if you need its contents.",
+        output.len(),
+        path.display()
+    );
+    rest.insert("output".to_string(), Value::String(message));
+    Ok(Value::Object(rest).to_string())
+}
+
+/// Load the variables from a tool's `env_file`. Failures are reported to the
+/// model rather than silently running the tool without its configuration.
+fn read_env_file(path: &Path, tool_name: &str) -> Result<Vec<(String, String)>, FunctionCallError> {
+    dotenvy::from_path_iter(path)
+        .and_then(Iterator::collect)
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to load env_file {} for {tool_name}: {err}",
+                path.display()
+            ))
+        })
+}
+
+/// Stdout a tool can print, as its whole output, to return images alongside
+/// text, e.g. `{"content_items": [{"type": "image", "path": "shot.png"}]}`.
+#[derive(Debug, Deserialize)]
+struct StructuredToolOutput {
+    content_items: Vec<ToolContentItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ToolContentItem {
+    Text {
+        text: String,
+    },
+    /// Either a `path` (relative to the turn cwd) or base64 `data`, whose
+    /// `mime_type` defaults to `image/png`.
+    Image {
+        #[serde(default)]
+        path: Option<PathBuf>,
+        #[serde(default)]
+        data: Option<String>,
+        #[serde(default)]
+        mime_type: Option<String>,
+    },
+}
+
+/// A custom tool's result in the structured exec format the shell runtime
+/// produces for it: `{"output": "...", "metadata": {"exit_code": .., ...}}`.
+struct ExecReport {
+    /// The tool's captured stdout and stderr.
+    output: String,
+    /// Every other field of the report, such as `metadata`.
+    rest: serde_json::Map<String, Value>,
+}
+
+impl ExecReport {
+    /// Returns `None` for content in any other shape.
+    fn parse(content: &str) -> Option<Self> {
+        let Ok(Value::Object(mut rest)) = serde_json::from_str::<Value>(content) else {
+            return None;
+        };
+        let Some(Value::String(output)) = rest.remove("output") else {
+            return None;
+        };
+        Some(Self { output, rest })
+    }
+}
+
+/// Map structured tool stdout to content items the model can see. Returns
+/// `None` for ordinary output, which is sent as text. Image references that
+/// cannot be loaded become text items explaining why.
+fn structured_content_items(
+    content: &str,
+    cwd: &Path,
+) -> Option<Vec<FunctionCallOutputContentItem>> {
+    let report = ExecReport::parse(content)?;
+    let structured: StructuredToolOutput = serde_json::from_str(report.output.trim()).ok()?;
+    let items = structured
+        .content_items
+        .into_iter()
+        .map(|item| match item {
+            ToolContentItem::Text { text } => FunctionCallOutputContentItem::InputText { text },
+            ToolContentItem::Image {
+                path,
+                data,
+                mime_type,
+            } => image_content_item(path, data, mime_type, cwd),
+        })
+        .collect();
+    Some(items)
+}
+
+fn image_content_item(
+    path: Option<PathBuf>,
+    data: Option<String>,
+    mime_type: Option<String>,
+    cwd: &Path,
+) -> FunctionCallOutputContentItem {
+    let loaded = match (path, data) {
+        (Some(path), None) => load_local_image(&cwd.join(path)),
+        (None, Some(data)) => {
+            let mime_type = mime_type.unwrap_or_else(|| "image/png".to_string());
+            if !mime_type.starts_with("image/") {
+                Err(format!("unsupported MIME type `{mime_type}`"))
+            } else if let Err(err) = BASE64_STANDARD.decode(data.trim()) {
+                Err(format!("invalid base64 image data: {err}"))
+            } else {
+                Ok(format!("data:{mime_type};base64,{}", data.trim()))
+            }
+        }
+        _ => Err("image items need exactly one of `path` or `data`".to_string()),
+    };
+    match loaded {
+        Ok(image_url) => FunctionCallOutputContentItem::InputImage { image_url },
+        Err(text) => {
+            warn!("custom_tool_image_error" = %text, "custom tool returned an unusable image");
+            FunctionCallOutputContentItem::InputText { text }
+        }
+    }
+}
+
+/// Load an image the way attached local images are, so it is resized to fit;
+/// the error is the placeholder text the model would otherwise see.
+fn load_local_image(path: &Path) -> Result<String, String> {
+    let ResponseInputItem::Message { content, .. } =
+        ResponseInputItem::from(vec![UserInput::LocalImage {
+            path: path.to_path_buf(),
+        }])
+    else {
+        return Err(format!(
+            "Codex could not read the image at `{}`",
+            path.display()
+        ));
+    };
+    match content.into_iter().next() {
+        Some(ContentItem::InputImage { image_url }) => Ok(image_url),
+        Some(ContentItem::InputText { text }) => Err(text),
+        _ => Err(format!(
+            "Codex could not read the image at `{}`",
+            path.display()
+        )),
+    }
+}
+
+/// Decide whether a call should end in the pending/shutdown flow. A tool can
+/// override `hibernate_after_call` at runtime by printing a JSON object with a
+/// boolean `shutdown` field (either as its whole output or as its last line);
+/// otherwise the configured default applies.
+fn should_shutdown(content: &str, config_default: bool) -> bool {
+    runtime_shutdown_flag(content).unwrap_or(config_default)
+}
+
+fn runtime_shutdown_flag(content: &str) -> Option<bool> {
+    let report = ExecReport::parse(content)?;
+    let output = report.output.trim();
+    let last_line = output.lines().rev().find(|line| !line.trim().is_empty());
+    std::iter::once(output)
+        .chain(last_line)
+        .find_map(|candidate| {
+            serde_json::from_str::<Value>(candidate)
+                .ok()?
+                .get("shutdown")?
+                .as_bool()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codex::make_session_and_context;
+    use crate::protocol::AskForApproval;
+    use crate::tools::spec::JsonSchema;
+    use crate::turn_diff_tracker::TurnDiffTracker;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn exec_content(output: &str) -> String {
+        json!({
+            "output": output,
+            "metadata": { "exit_code": 0, "duration_seconds": 0.1 },
+        })
+        .to_string()
+    }
+
+    fn config_tool(name: &str, enabled: bool) -> ConfigCustomTool {
+        ConfigCustomTool {
+            name: name.to_string(),
+            description: format!("Run {name}"),
+            parameters: JsonSchema::Object {
+                properties: Default::default(),
+                required: None,
+                additional_properties: None,
+            },
+            required: Vec::new(),
+            command: vec!["true".to_string()],
+            enabled,
+            cwd: None,
+            env: HashMap::new(),
+            env_include: Vec::new(),
+            env_exclude: Vec::new(),
+            env_passthrough: Vec::new(),
+            env_file: None,
+            timeout_ms: None,
+            with_escalated_permissions: None,
+            parallel: false,
+            hibernate_after_call: false,
+            output_as_file: false,
+            max_output_bytes: None,
         }
+    }
+
+    #[test]
+    fn disabled_tools_are_left_out_of_the_handler() {
+        let handler = CustomToolHandler::new(vec![
+            config_tool("custom.enabled", true),
+            config_tool("custom.disabled", false),
+        ]);
+
+        let mut names: Vec<&str> = handler.tools.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["custom.enabled"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn non_zero_exit_code_is_reported_in_a_top_level_field() {
+        let (session, mut turn) = make_session_and_context().await;
+        turn.approval_policy = AskForApproval::Never;
+        turn.sandbox_policy = SandboxPolicy::DangerFullAccess;
+        let mut tool = config_tool("custom.lint", true);
+        tool.command = vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "echo lint failed; exit 2".to_string(),
+        ];
+        let handler = CustomToolHandler::new(vec![tool]);
+
+        let result = handler
+            .handle(ToolInvocation {
+                session: Arc::new(session),
+                turn: Arc::new(turn),
+                tracker: Arc::new(tokio::sync::Mutex::new(TurnDiffTracker::new())),
+                call_id: "lint-call".to_string(),
+                tool_name: "custom.lint".to_string(),
+                payload: ToolPayload::Function {
+                    arguments: "{}".to_string(),
+                },
+            })
+            .await;
+
+        let Err(FunctionCallError::RespondToModel(content)) = result else {
+            panic!("expected the failed call to be reported to the model");
+        };
+        let report: Value = serde_json::from_str(&content).expect("structured exec output");
+        assert_eq!(
+            (
+                &report["exit_code"],
+                &report["metadata"]["exit_code"],
+                &report["output"]
+            ),
+            (&json!(2), &json!(2), &json!("lint failed\n"))
+        );
+    }
+
+    #[test]
+    fn complete_call_passes_required_check() {
+        let required = vec!["ticket".to_string(), "priority".to_string()];
+        assert_eq!(
+            check_required_args(
+                &json!({ "ticket": "ABC-1", "priority": 2, "extra": true }),
+                &required,
+                "custom.triage",
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn missing_required_args_are_named() {
+        let required = vec!["ticket".to_string(), "priority".to_string()];
+        assert_eq!(
+            check_required_args(&json!({ "priority": null }), &required, "custom.triage"),
+            Err(FunctionCallError::RespondToModel(
+                "missing required argument(s) for custom.triage: ticket, priority".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn tilde_program_expands_to_home() {
+        let home = Path::new("/home/dev");
+        assert_eq!(
+            resolve_tool_program("~/bin/foo", Path::new("/workspace"), Some(home)),
+            "/home/dev/bin/foo"
+        );
+    }
+
+    #[test]
+    fn relative_program_resolves_against_workspace_root() {
+        let home = Path::new("/home/dev");
+        let workspace = Path::new("/workspace");
+        assert_eq!(
+            [
+                resolve_tool_program("scripts/lint.sh", workspace, Some(home)),
+                resolve_tool_program("./lint.sh", workspace, Some(home)),
+                resolve_tool_program("python3", workspace, Some(home)),
+                resolve_tool_program("/usr/bin/env", workspace, Some(home)),
+            ],
+            [
+                "/workspace/scripts/lint.sh".to_string(),
+                "/workspace/./lint.sh".to_string(),
+                "python3".to_string(),
+                "/usr/bin/env".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn output_file_stays_out_of_a_workspace_the_sandbox_cannot_write() {
+        let workspace = tempfile::tempdir().expect("tempdir");
+        let path_for = |policy: &SandboxPolicy| {
+            tool_output_path(policy, workspace.path(), "custom/report", "call 1")
+        };
+
+        assert_eq!(
+            (
+                path_for(&SandboxPolicy::new_workspace_write_policy()),
+                path_for(&SandboxPolicy::ReadOnly),
+            ),
+            (
+                workspace
+                    .path()
+                    .join(".codex/tool-output/custom_report-call_1.txt"),
+                std::env::temp_dir().join("codex-tool-output/custom_report-call_1.txt"),
+            )
+        );
+    }
+
+    #[test]
+    fn read_env_file_parses_variables() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join(".env");
+        std::fs::write(&path, "# secrets\nAPI_TOKEN=abc123\nQUOTED=\"two words\"\n")
+            .expect("write env file");
+
+        assert_eq!(
+            read_env_file(&path, "custom.deploy").expect("env file"),
+            vec![
+                ("API_TOKEN".to_string(), "abc123".to_string()),
+                ("QUOTED".to_string(), "two words".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_env_file_is_reported_to_model() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("missing.env");
+
+        let Err(FunctionCallError::RespondToModel(message)) = read_env_file(&path, "custom.deploy")
+        else {
+            panic!("expected an error for the model");
+        };
+        assert!(
+            message.starts_with(&format!(
+                "failed to load env_file {} for custom.deploy:",
+                path.display()
+            )),
+            "unexpected message: {message}"
+        );
+    }
+
+    #[test]
+    fn structured_output_maps_images_and_degrades_bad_references() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let content = exec_content(
+            &json!({
+                "content_items": [
+                    { "type": "text", "text": "two screenshots" },
+                    { "type": "image", "data": "iVBORw0KGgo=" },
+                    { "type": "image", "data": "not base64!" },
+                    { "type": "image", "path": "missing.png" },
+                ]
+            })
+            .to_string(),
+        );
+
+        let items = structured_content_items(&content, dir.path()).expect("structured output");
+
+        let missing = dir.path().join("missing.png");
+        let read_error = std::fs::read(&missing).expect_err("missing image");
+        assert_eq!(
+            items,
+            vec![
+                FunctionCallOutputContentItem::InputText {
+                    text: "two screenshots".to_string(),
+                },
+                FunctionCallOutputContentItem::InputImage {
+                    image_url: "data:image/png;base64,iVBORw0KGgo=".to_string(),
+                },
+                FunctionCallOutputContentItem::InputText {
+                    text: format!(
+                        "invalid base64 image data: {}",
+                        BASE64_STANDARD
+                            .decode("not base64!")
+                            .expect_err("invalid base64")
+                    ),
+                },
+                FunctionCallOutputContentItem::InputText {
+                    text: format!(
+                        "Codex could not read the local image at `{}`: failed to read image at {}: {read_error}",
+                        missing.display(),
+                        missing.display()
+                    ),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_output_has_no_structured_items() {
+        assert_eq!(
+            structured_content_items(&exec_content("hello\n"), Path::new("/tmp")),
+            None
+        );
+    }
+
+    #[test]
+    fn output_under_the_limit_passes_through() {
+        let content = exec_content("short");
+        assert_eq!(truncate_output(&content, Some(5)), content);
+        assert_eq!(truncate_output("plain text", Some(64)), "plain text");
+        assert_eq!(truncate_output("plain text", None), "plain text");
+    }
+
+    #[test]
+    fn output_over_the_limit_is_truncated_with_marker() {
+        let structured: Value =
+            serde_json::from_str(&truncate_output(&exec_content("0123456789"), Some(4)))
+                .expect("structured output");
+
+        assert_eq!(
+            (structured, truncate_output("héllo world", Some(2))),
+            (
+                json!({
+                    "output": "0123\n[truncated 6 bytes]",
+                    "metadata": { "exit_code": 0, "duration_seconds": 0.1 },
+                }),
+                "h\n[truncated 11 bytes]".to_string(),
+            )
+        );
+    }
+
+    #[test]
+    fn runtime_shutdown_true_overrides_config() {
+        let content = exec_content("working...\n{\"shutdown\": true, \"ticket\": \"sync-42\"}\n");
+        assert!(should_shutdown(&content, false));
+    }
+
+    #[test]
+    fn runtime_shutdown_false_overrides_config() {
+        let content = exec_content("{\n  \"shutdown\": false\n}\n");
+        assert!(!should_shutdown(&content, true));
+    }
 
-        Ok(output)
+    #[test]
+    fn missing_shutdown_flag_falls_back_to_config() {
+        let content = exec_content("{\"status\": \"pending\"}\n");
+        assert!(should_shutdown(&content, true));
+        assert!(!should_shutdown(&content, false));
+        assert!(!should_shutdown("not json", false));
     }
 }
@@ -49,7 +49,7 @@ impl ToolHandler for McpHandler {
             tool,
             arguments_str,
         )
-        .await;
+        .await?;
 
         match response {
             codex_protocol::models::ResponseInputItem::McpToolCallOutput { result, .. } => {
@@ -1,33 +1,184 @@
+use crate::config::types::HookStderr;
+use crate::config::types::ShellEnvironmentPolicy;
+use crate::config::types::StopHookResponseItems;
+use crate::config::types::ToolHookFormat;
+use crate::config::types::ToolHookMode;
+use crate::config::types::ToolHookStage;
+use crate::exec_env::create_env;
+use crate::git_info::GitWorktreeStatus;
 use crate::tools::context::ToolPayload;
 use crate::tools::router::ToolCall;
+use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseInputItem;
 use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::SandboxPolicy;
 use codex_protocol::protocol::TokenUsage;
+use regex::Regex;
 use serde::Deserialize;
 use serde::Serialize;
+use serde::de::DeserializeOwned;
 use serde_json::Value;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tracing::info;
 use tracing::warn;
+use wildmatch::WildMatchPattern;
+
+/// Tool name matcher used by `tool_hook_include`/`tool_hook_exclude`. Exact
+/// names match themselves; `*` matches any run of characters.
+pub type ToolNamePattern = WildMatchPattern<'*', '?'>;
+
+/// How many tool hook processes may run at once for one hook. Parallel tool
+/// calls emit their events concurrently; extra events wait for a free slot.
+pub const DEFAULT_MAX_CONCURRENT_TOOL_HOOKS: usize = 8;
 
 #[derive(Clone, Debug)]
 pub struct ToolHook {
     command: Arc<Vec<String>>,
+    mode: ToolHookMode,
+    format: ToolHookFormat,
+    stderr: HookStderr,
+    env_policy: Option<Arc<ShellEnvironmentPolicy>>,
+    include: Arc<Vec<ToolNamePattern>>,
+    exclude: Arc<Vec<ToolNamePattern>>,
+    fires_before: bool,
+    fires_after: bool,
+    /// Log events instead of running `command`; see [`ToolHook::debug_echo`].
+    debug_echo: bool,
+    /// Shared by clones so the limit holds across concurrent dispatches.
+    slots: Arc<Semaphore>,
 }
 
 impl ToolHook {
+    /// Returns `None` when `command` is empty or its program cannot be found,
+    /// logging a single warning in the latter case so a misconfigured hook
+    /// does not fail (and warn) again on every tool call.
     pub fn new(command: Vec<String>) -> Option<Self> {
-        if command.is_empty() {
-            return None;
+        Self::build(command, None)
+    }
+
+    /// Like [`ToolHook::new`], but a relative program that cannot be found
+    /// from the current directory or on `PATH` falls back to `base_dir`.
+    pub fn new_in(command: Vec<String>, base_dir: &Path) -> Option<Self> {
+        Self::build(command, Some(base_dir))
+    }
+
+    /// A hook that runs no command: every event is logged as JSON through
+    /// `tracing` and no directive is returned. Handy for seeing payload
+    /// shapes before writing a real hook.
+    pub fn debug_echo() -> Self {
+        Self {
+            debug_echo: true,
+            ..Self::with_command(Arc::new(Vec::new()))
         }
-        Some(Self {
-            command: Arc::new(command),
-        })
+    }
+
+    fn build(command: Vec<String>, base_dir: Option<&Path>) -> Option<Self> {
+        runnable_hook_command(command, base_dir, "tool hook").map(Self::with_command)
+    }
+
+    fn with_command(command: Arc<Vec<String>>) -> Self {
+        Self {
+            command,
+            mode: ToolHookMode::default(),
+            format: ToolHookFormat::default(),
+            stderr: HookStderr::default(),
+            env_policy: None,
+            include: Arc::new(Vec::new()),
+            exclude: Arc::new(Vec::new()),
+            fires_before: true,
+            fires_after: true,
+            debug_echo: false,
+            slots: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_TOOL_HOOKS)),
+        }
+    }
+
+    pub fn with_mode(mut self, mode: ToolHookMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn mode(&self) -> ToolHookMode {
+        self.mode
+    }
+
+    pub fn with_format(mut self, format: ToolHookFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Build the hook's environment from `policy` instead of passing the
+    /// agent's environment through.
+    pub fn with_env_policy(mut self, policy: Option<ShellEnvironmentPolicy>) -> Self {
+        self.env_policy = policy.map(Arc::new);
+        self
+    }
+
+    pub fn with_stderr(mut self, stderr: HookStderr) -> Self {
+        self.stderr = stderr;
+        self
+    }
+
+    /// Cap how many hook processes run at once (at least one).
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.slots = Arc::new(Semaphore::new(max_concurrency.max(1)));
+        self
+    }
+
+    /// Restrict the hook to tools matching `include` (all tools when empty)
+    /// and not matching `exclude`.
+    pub fn with_tool_filter(mut self, include: &[String], exclude: &[String]) -> Self {
+        let patterns = |raw: &[String]| -> Vec<ToolNamePattern> {
+            raw.iter()
+                .map(|pattern| ToolNamePattern::new(pattern))
+                .collect()
+        };
+        self.include = Arc::new(patterns(include));
+        self.exclude = Arc::new(patterns(exclude));
+        self
+    }
+
+    /// Only spawn the hook for events of the given phases; events of other
+    /// phases are dropped without running the command.
+    pub fn with_phases(mut self, phases: &[ToolHookStage]) -> Self {
+        self.fires_before = phases.contains(&ToolHookStage::Before);
+        self.fires_after = phases.contains(&ToolHookStage::After);
+        self
+    }
+
+    fn fires_for(&self, phase: &ToolHookPhase) -> bool {
+        match phase {
+            ToolHookPhase::BeforeExecution => self.fires_before,
+            ToolHookPhase::AfterExecution | ToolHookPhase::Replaced => self.fires_after,
+        }
+    }
+
+    pub fn applies_to(&self, tool_name: &str) -> bool {
+        let matches_any = |patterns: &[ToolNamePattern]| {
+            patterns.iter().any(|pattern| pattern.matches(tool_name))
+        };
+        !matches_any(&self.exclude) && (self.include.is_empty() || matches_any(&self.include))
     }
 
     pub async fn emit(&self, event: ToolHookEvent) -> Option<ToolHookDirective> {
+        if !self.fires_for(&event.phase) {
+            return None;
+        }
+        if self.debug_echo {
+            echo_hook_event(&event);
+            return None;
+        }
+        // The semaphore is never closed, so acquiring only fails if that changes.
+        let Ok(_slot) = self.slots.acquire().await else {
+            return None;
+        };
         match self.spawn_and_send(event).await {
             Ok(result) => result,
             Err(err) => {
@@ -52,51 +203,201 @@ impl ToolHook {
         } else {
             cmd.stdout(Stdio::inherit());
         }
-        cmd.stderr(Stdio::inherit());
+        cmd.stderr(self.stderr.stdio());
+        apply_env_policy(&mut cmd, self.env_policy.as_deref());
+        let serialized = match self.format {
+            ToolHookFormat::Json => serde_json::to_vec(&event),
+            ToolHookFormat::Jsonl => serde_json::to_vec(&event).map(|mut line| {
+                line.push(b'\n');
+                line
+            }),
+            ToolHookFormat::Env => {
+                cmd.envs(event.env_vars());
+                serde_json::to_vec(&event.env_stdin())
+            }
+        };
+        let payload = serialized.map_err(|err| {
+            std::io::Error::other(format!("failed to serialize hook event: {err}"))
+        })?;
 
         let mut child = cmd.spawn()?;
         if let Some(mut stdin) = child.stdin.take() {
-            let payload = serde_json::to_vec(&event).map_err(|err| {
-                std::io::Error::other(format!("failed to serialize hook event: {err}"))
-            })?;
             stdin.write_all(&payload).await?;
         }
+        // Piped streams are drained concurrently; inherited ones come back empty.
+        let output = child.wait_with_output().await?;
+        forward_hook_stderr(&self.command[0], &output.stderr);
+        if !output.status.success() {
+            return Err(std::io::Error::other(format!(
+                "hook exited with status {}",
+                output.status
+            )));
+        }
         if capture_response {
-            let output = child.wait_with_output().await?;
-            if !output.status.success() {
-                return Err(std::io::Error::other(format!(
-                    "hook exited with status {}",
-                    output.status
-                )));
+            Ok(parse_directive_output(&output.stdout))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+fn echo_hook_event(event: &ToolHookEvent) {
+    match serde_json::to_string(event) {
+        Ok(json) => info!("hook_event" = %json, "debug-echo tool hook received an event"),
+        Err(err) => warn!("hook_error" = %err, "failed to serialize hook event"),
+    }
+}
+
+/// Replace the inherited environment with the one `policy` derives, as
+/// `create_env` does for shell commands. Without a policy the hook inherits
+/// everything.
+fn apply_env_policy(cmd: &mut Command, policy: Option<&ShellEnvironmentPolicy>) {
+    if let Some(policy) = policy {
+        cmd.env_clear();
+        cmd.envs(create_env(policy));
+    }
+}
+
+/// Resolve the hook's program (see [`resolve_hook_program`]) and check that
+/// it exists. A missing program disables the hook with one warning rather
+/// than failing to spawn on every event.
+fn runnable_hook_command(
+    mut command: Vec<String>,
+    base_dir: Option<&Path>,
+    kind: &str,
+) -> Option<Arc<Vec<String>>> {
+    let program = command.first()?;
+    if let Some(resolved) = base_dir.and_then(|base_dir| resolve_hook_program(program, base_dir)) {
+        command[0] = resolved.to_string_lossy().into_owned();
+    }
+    if !hook_program_exists(&command[0]) {
+        warn!(
+            "hook" = %command[0],
+            "{kind} program not found on PATH or on disk; the hook is disabled"
+        );
+        return None;
+    }
+    Some(Arc::new(command))
+}
+
+fn hook_program_exists(program: &str) -> bool {
+    let path = Path::new(program);
+    if path.components().count() == 1 {
+        which::which(program).is_ok()
+    } else {
+        path.is_file()
+    }
+}
+
+/// Hook commands are often written relative to a project, but Codex may run
+/// from another directory. When the program cannot be found as given (a bare
+/// name missing from `PATH`, or a relative path missing from the current
+/// directory), use the copy under `base_dir` if there is one. Absolute paths
+/// are never rewritten.
+fn resolve_hook_program(program: &str, base_dir: &Path) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.is_absolute() {
+        return None;
+    }
+    if hook_program_exists(program) {
+        return None;
+    }
+    let candidate = base_dir.join(path);
+    candidate.is_file().then_some(candidate)
+}
+
+/// Resolve a hook-supplied `path` against `root`, returning `None` when it
+/// points outside of it. Both paths are canonicalized as far as they exist,
+/// so a symlink cannot lead out of `root`; the rest is resolved lexically, so
+/// the path does not have to exist yet.
+pub(crate) fn resolve_hook_path(path: &Path, root: &Path) -> Option<PathBuf> {
+    fn normalize(path: &Path) -> PathBuf {
+        let mut out = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::ParentDir => {
+                    out.pop();
+                }
+                Component::CurDir => {}
+                other => out.push(other.as_os_str()),
             }
-            let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if stdout.is_empty() {
-                return Ok(None);
+        }
+        out
+    }
+
+    fn canonicalize_existing(path: &Path) -> PathBuf {
+        let mut missing = Vec::new();
+        let mut existing = path;
+        loop {
+            if let Ok(mut canonical) = existing.canonicalize() {
+                canonical.extend(missing.iter().rev());
+                return canonical;
             }
-            match serde_json::from_str::<ToolHookDirective>(&stdout) {
-                Ok(directive) => Ok(Some(directive)),
-                Err(err) => {
-                    warn!(
-                        "tool_hook_parse_error" = %err,
-                        "stdout" = %stdout,
-                        "failed to parse tool hook output"
-                    );
-                    Ok(None)
+            match (existing.parent(), existing.file_name()) {
+                (Some(parent), Some(name)) => {
+                    missing.push(name);
+                    existing = parent;
                 }
+                _ => return path.to_path_buf(),
             }
-        } else {
-            let status = child.wait().await?;
-            if !status.success() {
-                return Err(std::io::Error::other(format!(
-                    "hook exited with status {status}"
-                )));
-            }
-            Ok(None)
+        }
+    }
+
+    let root = canonicalize_existing(&normalize(root));
+    let resolved = canonicalize_existing(&normalize(&root.join(path)));
+    resolved.starts_with(&root).then_some(resolved)
+}
+
+impl HookStderr {
+    fn stdio(self) -> Stdio {
+        match self {
+            HookStderr::Inherit => Stdio::inherit(),
+            HookStderr::Capture => Stdio::piped(),
+        }
+    }
+}
+
+/// Log each line of captured hook stderr so it can be routed with the rest of
+/// Codex's diagnostics instead of interleaving with agent output.
+fn forward_hook_stderr(hook: &str, stderr: &[u8]) {
+    for line in String::from_utf8_lossy(stderr).lines() {
+        if !line.trim().is_empty() {
+            info!("hook" = %hook, "hook_stderr" = %line, "hook wrote to stderr");
         }
     }
 }
 
-#[derive(Serialize, Clone)]
+/// Decode a before-hook's or stop hook's stdout into a directive. Stdout must
+/// be valid UTF-8; lossy decoding could turn corrupted bytes into a directive
+/// that still parses, so invalid output is rejected with a warning instead.
+fn parse_directive_output<T: DeserializeOwned>(stdout: &[u8]) -> Option<T> {
+    let stdout = match std::str::from_utf8(stdout) {
+        Ok(stdout) => stdout.trim(),
+        Err(err) => {
+            warn!(
+                "hook_parse_error" = %err,
+                "hook stdout is not valid UTF-8; ignoring directive"
+            );
+            return None;
+        }
+    };
+    if stdout.is_empty() {
+        return None;
+    }
+    match serde_json::from_str::<T>(stdout) {
+        Ok(directive) => Some(directive),
+        Err(err) => {
+            warn!(
+                "hook_parse_error" = %err,
+                "stdout" = %stdout,
+                "failed to parse hook output"
+            );
+            None
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
 pub struct ToolCallSnapshot {
     tool_name: String,
     call_id: String,
@@ -108,18 +409,38 @@ impl ToolCallSnapshot {
         Self {
             tool_name: call.tool_name.clone(),
             call_id: call.call_id.clone(),
-            payload: ToolCallPayloadSnapshot::from_payload(&call.payload),
+            payload: ToolCallPayloadSnapshot::from_payload(&call.tool_name, &call.payload),
+        }
+    }
+
+    /// Mask secrets in the payload before the snapshot reaches hooks or
+    /// dispatch observers. See [`SnapshotRedactor`].
+    pub fn redacted(mut self, redactor: &SnapshotRedactor) -> Self {
+        if !redactor.is_empty() {
+            self.payload.redact(redactor);
         }
+        self
+    }
+
+    pub fn tool_name(&self) -> &str {
+        &self.tool_name
+    }
+
+    pub fn call_id(&self) -> &str {
+        &self.call_id
     }
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, Debug)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum ToolCallPayloadSnapshot {
     Function {
         arguments: String,
         #[serde(skip_serializing_if = "Option::is_none")]
         parsed_arguments: Option<Value>,
+        /// Only set for shell-style function tools (`shell`, `shell_command`).
+        #[serde(skip_serializing_if = "Option::is_none")]
+        category: Option<CommandCategory>,
     },
     Custom {
         input: String,
@@ -130,6 +451,7 @@ pub enum ToolCallPayloadSnapshot {
         workdir: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         timeout_ms: Option<u64>,
+        category: CommandCategory,
     },
     Mcp {
         server: String,
@@ -139,13 +461,17 @@ pub enum ToolCallPayloadSnapshot {
 }
 
 impl ToolCallPayloadSnapshot {
-    fn from_payload(payload: &ToolPayload) -> Self {
+    fn from_payload(tool_name: &str, payload: &ToolPayload) -> Self {
         match payload {
             ToolPayload::Function { arguments } => {
-                let parsed_arguments = serde_json::from_str(arguments).ok();
+                let parsed_arguments: Option<Value> = serde_json::from_str(arguments).ok();
+                let category = parsed_arguments
+                    .as_ref()
+                    .and_then(|args| shell_function_category(tool_name, args));
                 Self::Function {
                     arguments: arguments.clone(),
                     parsed_arguments,
+                    category,
                 }
             }
             ToolPayload::Custom { input } => Self::Custom {
@@ -155,6 +481,7 @@ impl ToolCallPayloadSnapshot {
                 command: params.command.clone(),
                 workdir: params.workdir.clone(),
                 timeout_ms: params.timeout_ms,
+                category: CommandCategory::from_argv(&params.command),
             },
             ToolPayload::Mcp {
                 server,
@@ -169,12 +496,195 @@ impl ToolCallPayloadSnapshot {
     }
 }
 
+impl ToolCallPayloadSnapshot {
+    fn redact(&mut self, redactor: &SnapshotRedactor) {
+        match self {
+            Self::Function {
+                arguments,
+                parsed_arguments,
+                ..
+            } => {
+                if let Some(parsed) = parsed_arguments {
+                    redactor.redact_value(parsed);
+                    // Re-serialize so masked keys are masked in the raw text too.
+                    if let Ok(serialized) = serde_json::to_string(parsed) {
+                        *arguments = serialized;
+                    }
+                } else {
+                    *arguments = redactor.redact_str(arguments);
+                }
+            }
+            Self::Custom { input } => *input = redactor.redact_str(input),
+            Self::LocalShell { command, .. } => {
+                for arg in command {
+                    *arg = redactor.redact_str(arg);
+                }
+            }
+            Self::Mcp { raw_arguments, .. } => match serde_json::from_str::<Value>(raw_arguments) {
+                Ok(mut parsed) => {
+                    redactor.redact_value(&mut parsed);
+                    if let Ok(serialized) = serde_json::to_string(&parsed) {
+                        *raw_arguments = serialized;
+                    }
+                }
+                Err(_) => *raw_arguments = redactor.redact_str(raw_arguments),
+            },
+        }
+    }
+}
+
+/// Replacement text for redacted secrets.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Masks secrets in tool call snapshots, configured with
+/// `tool_hook_redact_patterns` and `tool_hook_redact_keys`. Substrings
+/// matching any pattern are replaced with [`REDACTED`], as is the whole value
+/// of any JSON object key in the key list (compared case-insensitively).
+#[derive(Clone, Debug, Default)]
+pub struct SnapshotRedactor {
+    patterns: Vec<Regex>,
+    keys: Vec<String>,
+}
+
+impl SnapshotRedactor {
+    pub fn new(patterns: &[String], keys: &[String]) -> Result<Self, regex::Error> {
+        Ok(Self {
+            patterns: patterns
+                .iter()
+                .map(|pattern| Regex::new(pattern))
+                .collect::<Result<_, _>>()?,
+            keys: keys.iter().map(|key| key.to_ascii_lowercase()).collect(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty() && self.keys.is_empty()
+    }
+
+    fn redact_str(&self, text: &str) -> String {
+        let mut redacted = text.to_string();
+        for pattern in &self.patterns {
+            redacted = pattern.replace_all(&redacted, REDACTED).into_owned();
+        }
+        redacted
+    }
+
+    fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => *text = self.redact_str(text),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_value(item)),
+            Value::Object(map) => {
+                for (key, item) in map.iter_mut() {
+                    if self.keys.contains(&key.to_ascii_lowercase()) {
+                        *item = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_value(item);
+                    }
+                }
+            }
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+    }
+}
+
+/// Compared by source text, since compiled patterns cannot be compared.
+impl PartialEq for SnapshotRedactor {
+    fn eq(&self, other: &Self) -> bool {
+        self.keys == other.keys
+            && self.patterns.len() == other.patterns.len()
+            && self
+                .patterns
+                .iter()
+                .zip(&other.patterns)
+                .all(|(left, right)| left.as_str() == right.as_str())
+    }
+}
+
+/// Coarse, heuristic classification of a shell command so policy hooks can
+/// act on whole groups of commands without parsing argv themselves.
+///
+/// Only the program name is inspected (after unwrapping a `bash -lc <script>`
+/// style wrapper), so anything not explicitly listed maps to `Other`. Treat it
+/// as a hint, not a security boundary.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CommandCategory {
+    Network,
+    FilesystemWrite,
+    PackageManager,
+    Other,
+}
+
+impl CommandCategory {
+    pub fn from_argv(command: &[String]) -> Self {
+        match command {
+            [shell, flag, script, ..]
+                if is_shell_program(shell) && matches!(flag.as_str(), "-c" | "-lc") =>
+            {
+                Self::from_program(script.split_whitespace().next().unwrap_or_default())
+            }
+            [program, ..] => Self::from_program(program),
+            [] => Self::Other,
+        }
+    }
+
+    pub fn from_program(program: &str) -> Self {
+        let name = program.rsplit('/').next().unwrap_or(program);
+        match name {
+            "curl" | "wget" | "ssh" | "scp" | "sftp" | "ftp" | "rsync" | "nc" | "ncat"
+            | "telnet" | "ping" | "dig" | "nslookup" | "http" | "https" => Self::Network,
+            "rm" | "rmdir" | "mv" | "cp" | "mkdir" | "touch" | "chmod" | "chown" | "ln" | "dd"
+            | "truncate" | "tee" | "shred" | "install" => Self::FilesystemWrite,
+            "npm" | "pnpm" | "yarn" | "npx" | "pip" | "pip3" | "pipx" | "uv" | "poetry"
+            | "conda" | "cargo" | "gem" | "bundle" | "brew" | "apt" | "apt-get" | "dnf" | "yum"
+            | "pacman" | "apk" => Self::PackageManager,
+            _ => Self::Other,
+        }
+    }
+}
+
+fn is_shell_program(program: &str) -> bool {
+    let name = program.rsplit('/').next().unwrap_or(program);
+    matches!(name, "bash" | "sh" | "zsh")
+}
+
+fn shell_function_category(tool_name: &str, arguments: &Value) -> Option<CommandCategory> {
+    let command = arguments.get("command")?;
+    match tool_name {
+        "shell" | "container.exec" => {
+            let argv: Vec<String> = serde_json::from_value(command.clone()).ok()?;
+            Some(CommandCategory::from_argv(&argv))
+        }
+        "shell_command" => {
+            let script = command.as_str()?;
+            Some(CommandCategory::from_program(
+                script.split_whitespace().next().unwrap_or_default(),
+            ))
+        }
+        _ => None,
+    }
+}
+
 #[derive(Serialize)]
 pub struct ToolHookEvent {
     phase: ToolHookPhase,
     call: ToolCallSnapshot,
+    /// True when the tool supports parallel calls and other calls were in
+    /// flight when this one was dispatched.
+    parallel: bool,
+    /// True when the router is previewing calls and this one will not (or
+    /// did not) actually run.
+    dry_run: bool,
+    /// Sandbox mode of the turn that made the call (`read-only`,
+    /// `workspace-write`, or `danger-full-access`), so a hook can apply a
+    /// stricter policy to less restricted sessions.
+    sandbox: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     outcome: Option<ToolHookOutcome>,
+    /// How long the tool itself ran, in milliseconds. Only set on
+    /// `after_execution` events for calls that were actually dispatched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_duration_ms: Option<u64>,
 }
 
 impl ToolHookEvent {
@@ -182,6 +692,10 @@ impl ToolHookEvent {
         Self {
             phase: ToolHookPhase::BeforeExecution,
             call,
+            parallel: false,
+            dry_run: false,
+            sandbox: String::new(),
+            tool_duration_ms: None,
             outcome: None,
         }
     }
@@ -190,6 +704,10 @@ impl ToolHookEvent {
         Self {
             phase: ToolHookPhase::AfterExecution,
             call,
+            parallel: false,
+            dry_run: false,
+            sandbox: String::new(),
+            tool_duration_ms: None,
             outcome: Some(ToolHookOutcome::Success { response }),
         }
     }
@@ -198,9 +716,106 @@ impl ToolHookEvent {
         Self {
             phase: ToolHookPhase::AfterExecution,
             call,
+            parallel: false,
+            dry_run: false,
+            sandbox: String::new(),
+            tool_duration_ms: None,
             outcome: Some(ToolHookOutcome::Error { message }),
         }
     }
+
+    /// Terminal event for a call the before-hook refused, so every
+    /// `before_execution` event is still paired with an `after_execution`.
+    pub fn after_blocked(call: ToolCallSnapshot, reason: String) -> Self {
+        Self {
+            phase: ToolHookPhase::AfterExecution,
+            call,
+            parallel: false,
+            dry_run: false,
+            sandbox: String::new(),
+            tool_duration_ms: None,
+            outcome: Some(ToolHookOutcome::Blocked { reason }),
+        }
+    }
+
+    /// Terminal event for a call whose turn was cancelled while the tool was
+    /// still running.
+    pub fn after_cancelled(call: ToolCallSnapshot, message: String) -> Self {
+        Self {
+            phase: ToolHookPhase::AfterExecution,
+            call,
+            parallel: false,
+            dry_run: false,
+            sandbox: String::new(),
+            tool_duration_ms: None,
+            outcome: Some(ToolHookOutcome::Cancelled { message }),
+        }
+    }
+
+    /// Sent when an out-of-band delivery resolves a pending call, carrying
+    /// the result the model will see in place of the pending placeholder.
+    pub fn replaced(call: ToolCallSnapshot, output: FunctionCallOutputPayload) -> Self {
+        let response = ResponseInputItem::FunctionCallOutput {
+            call_id: call.call_id.clone(),
+            output,
+        };
+        Self {
+            phase: ToolHookPhase::Replaced,
+            call,
+            parallel: false,
+            dry_run: false,
+            sandbox: String::new(),
+            tool_duration_ms: None,
+            outcome: Some(ToolHookOutcome::Success { response }),
+        }
+    }
+
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_sandbox(mut self, sandbox_policy: &SandboxPolicy) -> Self {
+        self.sandbox = sandbox_policy.to_string();
+        self
+    }
+
+    pub fn with_tool_duration(mut self, duration: Duration) -> Self {
+        self.tool_duration_ms = Some(u64::try_from(duration.as_millis()).unwrap_or(u64::MAX));
+        self
+    }
+
+    /// Scalar fields exported to the hook under `ToolHookFormat::Env`.
+    fn env_vars(&self) -> [(&'static str, String); 6] {
+        [
+            ("CODEX_HOOK_PHASE", self.phase.as_str().to_string()),
+            ("CODEX_HOOK_TOOL_NAME", self.call.tool_name.clone()),
+            ("CODEX_HOOK_CALL_ID", self.call.call_id.clone()),
+            ("CODEX_HOOK_PARALLEL", self.parallel.to_string()),
+            ("CODEX_HOOK_DRY_RUN", self.dry_run.to_string()),
+            ("CODEX_HOOK_SANDBOX", self.sandbox.clone()),
+        ]
+    }
+
+    /// What remains on stdin once the scalars moved to the environment.
+    fn env_stdin(&self) -> ToolHookEnvStdin<'_> {
+        ToolHookEnvStdin {
+            payload: &self.call.payload,
+            outcome: self.outcome.as_ref(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ToolHookEnvStdin<'a> {
+    payload: &'a ToolCallPayloadSnapshot,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome: Option<&'a ToolHookOutcome>,
 }
 
 #[derive(Serialize)]
@@ -208,6 +823,18 @@ impl ToolHookEvent {
 enum ToolHookPhase {
     BeforeExecution,
     AfterExecution,
+    /// A pending call's result was delivered out of band.
+    Replaced,
+}
+
+impl ToolHookPhase {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ToolHookPhase::BeforeExecution => "before_execution",
+            ToolHookPhase::AfterExecution => "after_execution",
+            ToolHookPhase::Replaced => "replaced",
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -215,24 +842,54 @@ enum ToolHookPhase {
 enum ToolHookOutcome {
     Success { response: ResponseInputItem },
     Error { message: String },
+    Blocked { reason: String },
+    Cancelled { message: String },
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct ToolHookDirective {
     #[serde(default)]
     pub local_shell: Option<HookLocalShellDirective>,
+    /// Ask Codex to wait this many milliseconds (at most 30 seconds) and then
+    /// re-run the before-hook for the same call instead of executing it now.
+    /// Only one deferral is honored per call; a second request is ignored and
+    /// the call runs.
+    #[serde(default)]
+    pub defer_ms: Option<u64>,
+    /// Refuse the call without running it; the reason is returned to the
+    /// model as the tool output.
+    #[serde(default)]
+    pub block: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct HookLocalShellDirective {
     #[serde(default)]
     timeout_ms: Option<ToolHookTimeoutOverride>,
+    /// Run the command in this directory instead. Relative paths resolve
+    /// against the turn's working directory, and the result must stay inside
+    /// it.
+    #[serde(default)]
+    cwd: Option<PathBuf>,
+    /// Run the command outside the sandbox, asking for approval with this
+    /// justification as if the model had requested escalation itself.
+    #[serde(default)]
+    escalate: Option<HookEscalation>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HookEscalation {
+    pub justification: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(untagged)]
 enum ToolHookTimeoutOverride {
     Millis(u64),
+    /// `{"seconds": 2.5}`, for hooks that think in (fractional) seconds.
+    Seconds {
+        seconds: f64,
+    },
     Keyword(String),
 }
 
@@ -240,6 +897,19 @@ enum ToolHookTimeoutOverride {
 pub enum TimeoutBehavior {
     Millis(u64),
     Infinite,
+    /// Drop any timeout on the call so the tool's configured default applies.
+    Default,
+}
+
+impl ToolHookDirective {
+    /// Whether the directive sets or lifts the call's timeout. A `Default`
+    /// reset does not count: it asks for the tool's configured default.
+    pub fn overrides_timeout(&self) -> bool {
+        self.local_shell
+            .as_ref()
+            .and_then(HookLocalShellDirective::timeout_behavior)
+            .is_some_and(|behavior| behavior != TimeoutBehavior::Default)
+    }
 }
 
 impl HookLocalShellDirective {
@@ -248,56 +918,170 @@ impl HookLocalShellDirective {
             .as_ref()
             .and_then(ToolHookTimeoutOverride::behavior)
     }
+
+    pub fn cwd(&self) -> Option<&Path> {
+        self.cwd.as_deref()
+    }
+
+    pub fn escalate(&self) -> Option<&HookEscalation> {
+        self.escalate.as_ref()
+    }
 }
 
 impl ToolHookTimeoutOverride {
     fn behavior(&self) -> Option<TimeoutBehavior> {
         match self {
             Self::Millis(ms) => Some(TimeoutBehavior::Millis(*ms)),
+            Self::Seconds { seconds } => {
+                if !seconds.is_finite() || *seconds < 0.0 {
+                    warn!(seconds, "ignoring invalid tool hook timeout in seconds");
+                    return None;
+                }
+                Some(TimeoutBehavior::Millis((seconds * 1000.0).round() as u64))
+            }
             Self::Keyword(keyword) => {
                 let normalized = keyword.trim().to_ascii_lowercase();
-                if matches!(
-                    normalized.as_str(),
-                    "infinite" | "no_timeout" | "none" | "unlimited"
-                ) {
-                    Some(TimeoutBehavior::Infinite)
-                } else {
-                    None
+                match normalized.as_str() {
+                    "infinite" | "no_timeout" | "none" | "unlimited" => {
+                        Some(TimeoutBehavior::Infinite)
+                    }
+                    "default" => Some(TimeoutBehavior::Default),
+                    _ => None,
                 }
             }
         }
     }
 }
 
+/// Actions a stop hook can request by printing a JSON object on stdout.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct StopHookDirective {
+    /// Save the session's rollout to this path. Relative paths resolve
+    /// against the turn's working directory, and the path must stay inside it.
+    #[serde(default)]
+    pub export_to: Option<PathBuf>,
+}
+
 #[derive(Clone, Debug)]
 pub struct StopHook {
-    command: Arc<Vec<String>>,
+    target: StopHookTarget,
+    response_items: StopHookResponseItems,
+    stderr: HookStderr,
+    git_status: bool,
+    final_message_max_chars: Option<usize>,
+    env_policy: Option<Arc<ShellEnvironmentPolicy>>,
+}
+
+/// Where a [`StopHook`] delivers its events.
+#[derive(Clone, Debug)]
+enum StopHookTarget {
+    /// Spawn this command once per event.
+    Command(Arc<Vec<String>>),
+    /// Connect to a long-lived hook process listening on this Unix socket.
+    Daemon(PathBuf),
 }
 
 impl StopHook {
+    /// See [`ToolHook::new`].
     pub fn new(command: Vec<String>) -> Option<Self> {
-        if command.is_empty() {
-            return None;
+        Self::build(command, None)
+    }
+
+    /// See [`ToolHook::new_in`].
+    pub fn new_in(command: Vec<String>, base_dir: &Path) -> Option<Self> {
+        Self::build(command, Some(base_dir))
+    }
+
+    /// Stream events to an already-running hook process instead of spawning
+    /// one per turn. Each event is written as one JSON line on a fresh
+    /// connection to `socket`; the daemon may reply with a
+    /// [`StopHookDirective`] before closing it.
+    pub fn daemon(socket: PathBuf) -> Self {
+        Self::with_target(StopHookTarget::Daemon(socket))
+    }
+
+    fn build(command: Vec<String>, base_dir: Option<&Path>) -> Option<Self> {
+        let command = runnable_hook_command(command, base_dir, "stop hook")?;
+        Some(Self::with_target(StopHookTarget::Command(command)))
+    }
+
+    fn with_target(target: StopHookTarget) -> Self {
+        Self {
+            target,
+            response_items: StopHookResponseItems::default(),
+            stderr: HookStderr::default(),
+            git_status: false,
+            final_message_max_chars: None,
+            env_policy: None,
         }
-        Some(Self {
-            command: Arc::new(command),
-        })
     }
 
-    pub async fn emit(&self, event: StopHookEvent) {
-        if let Err(err) = self.spawn_and_send(event).await {
-            warn!("stop_hook_error" = %err, "failed to run stop hook command");
+    pub fn with_response_items(mut self, response_items: StopHookResponseItems) -> Self {
+        self.response_items = response_items;
+        self
+    }
+
+    pub fn response_items(&self) -> StopHookResponseItems {
+        self.response_items
+    }
+
+    pub fn with_stderr(mut self, stderr: HookStderr) -> Self {
+        self.stderr = stderr;
+        self
+    }
+
+    /// Ask for the worktree's git branch and dirty files to be attached to
+    /// each event (see [`StopHookEvent::with_git`]).
+    pub fn with_git_status(mut self, git_status: bool) -> Self {
+        self.git_status = git_status;
+        self
+    }
+
+    pub fn includes_git_status(&self) -> bool {
+        self.git_status
+    }
+
+    pub fn with_final_message_max_chars(mut self, max_chars: Option<usize>) -> Self {
+        self.final_message_max_chars = max_chars;
+        self
+    }
+
+    pub fn final_message_max_chars(&self) -> Option<usize> {
+        self.final_message_max_chars
+    }
+
+    /// See [`ToolHook::with_env_policy`].
+    pub fn with_env_policy(mut self, policy: Option<ShellEnvironmentPolicy>) -> Self {
+        self.env_policy = policy.map(Arc::new);
+        self
+    }
+
+    pub async fn emit(&self, event: StopHookEvent) -> Option<StopHookDirective> {
+        match self.spawn_and_send(event).await {
+            Ok(directive) => directive,
+            Err(err) => {
+                warn!("stop_hook_error" = %err, "failed to run stop hook command");
+                None
+            }
         }
     }
 
-    async fn spawn_and_send(&self, event: StopHookEvent) -> std::io::Result<()> {
-        let mut cmd = Command::new(&self.command[0]);
-        if self.command.len() > 1 {
-            cmd.args(&self.command[1..]);
+    pub(crate) async fn spawn_and_send(
+        &self,
+        event: StopHookEvent,
+    ) -> std::io::Result<Option<StopHookDirective>> {
+        let command = match &self.target {
+            StopHookTarget::Command(command) => command,
+            StopHookTarget::Daemon(socket) => return send_to_daemon(socket, &event).await,
+        };
+        let mut cmd = Command::new(&command[0]);
+        if command.len() > 1 {
+            cmd.args(&command[1..]);
         }
         cmd.stdin(Stdio::piped());
-        cmd.stdout(Stdio::inherit());
-        cmd.stderr(Stdio::inherit());
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(self.stderr.stdio());
+        apply_env_policy(&mut cmd, self.env_policy.as_deref());
 
         let mut child = cmd.spawn()?;
         if let Some(mut stdin) = child.stdin.take() {
@@ -306,19 +1090,63 @@ impl StopHook {
             })?;
             stdin.write_all(&payload).await?;
         }
-        let status = child.wait().await?;
-        if !status.success() {
+        let output = child.wait_with_output().await?;
+        forward_hook_stderr(&command[0], &output.stderr);
+        if !output.status.success() {
             return Err(std::io::Error::other(format!(
-                "hook exited with status {status}"
+                "hook exited with status {}",
+                output.status
             )));
         }
-        Ok(())
+        // Only a JSON object is a directive. Anything else is the hook's own
+        // output and goes to Codex's stdout, as it did before directives.
+        if output.stdout.trim_ascii_start().starts_with(b"{") {
+            Ok(parse_directive_output(&output.stdout))
+        } else {
+            tokio::io::stdout().write_all(&output.stdout).await?;
+            Ok(None)
+        }
     }
 }
 
+#[cfg(unix)]
+async fn send_to_daemon(
+    socket: &Path,
+    event: &StopHookEvent,
+) -> std::io::Result<Option<StopHookDirective>> {
+    use tokio::io::AsyncReadExt;
+
+    let mut payload = serde_json::to_vec(event).map_err(|err| {
+        std::io::Error::other(format!("failed to serialize stop hook event: {err}"))
+    })?;
+    payload.push(b'\n');
+    let mut stream = tokio::net::UnixStream::connect(socket).await?;
+    stream.write_all(&payload).await?;
+    stream.shutdown().await?;
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).await?;
+    Ok(parse_directive_output(&reply))
+}
+
+#[cfg(not(unix))]
+async fn send_to_daemon(
+    socket: &Path,
+    _event: &StopHookEvent,
+) -> std::io::Result<Option<StopHookDirective>> {
+    Err(std::io::Error::other(format!(
+        "stop hook socket {} is only supported on Unix",
+        socket.display()
+    )))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::types::EnvironmentVariablePattern;
+    use codex_protocol::models::ContentItem;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use tracing_test::traced_test;
 
     #[test]
     fn parses_infinite_timeout_directive() {
@@ -343,6 +1171,543 @@ mod tests {
             .unwrap();
         assert_eq!(behavior, TimeoutBehavior::Millis(60_000));
     }
+
+    fn filtered_hook(include: &[&str], exclude: &[&str]) -> ToolHook {
+        let owned = |raw: &[&str]| raw.iter().map(ToString::to_string).collect::<Vec<_>>();
+        ToolHook::new(vec!["true".to_string()])
+            .unwrap()
+            .with_tool_filter(&owned(include), &owned(exclude))
+    }
+
+    #[test]
+    fn tool_filter_matches_wildcard_families_and_exact_names() {
+        let hook = filtered_hook(&["mcp__github__*", "shell"], &["mcp__github__delete_*"]);
+        let applies = [
+            "mcp__github__create_issue",
+            "shell",
+            "mcp__github__delete_repo",
+            "mcp__gitlab__create_issue",
+            "shell_command",
+        ]
+        .map(|name| (name, hook.applies_to(name)));
+        assert_eq!(
+            applies,
+            [
+                ("mcp__github__create_issue", true),
+                ("shell", true),
+                ("mcp__github__delete_repo", false),
+                ("mcp__gitlab__create_issue", false),
+                ("shell_command", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn tool_filter_defaults_to_every_tool() {
+        let hook = filtered_hook(&[], &[]);
+        assert!(hook.applies_to("anything"));
+    }
+
+    #[test]
+    fn parses_seconds_timeout_directive() {
+        let directive: ToolHookDirective =
+            serde_json::from_str(r#"{"local_shell":{"timeout_ms":{"seconds":2.5}}}"#).unwrap();
+        let behavior = directive
+            .local_shell
+            .as_ref()
+            .and_then(HookLocalShellDirective::timeout_behavior);
+        assert_eq!(behavior, Some(TimeoutBehavior::Millis(2_500)));
+    }
+
+    #[test]
+    fn negative_seconds_timeout_directive_is_ignored() {
+        let directive: ToolHookDirective =
+            serde_json::from_str(r#"{"local_shell":{"timeout_ms":{"seconds":-1.0}}}"#).unwrap();
+        let behavior = directive
+            .local_shell
+            .as_ref()
+            .and_then(HookLocalShellDirective::timeout_behavior);
+        assert_eq!(behavior, None);
+        assert!(!directive.overrides_timeout());
+    }
+
+    #[test]
+    fn parses_default_timeout_directive() {
+        let directive: ToolHookDirective =
+            serde_json::from_str(r#"{"local_shell":{"timeout_ms":"Default"}}"#).unwrap();
+        let behavior = directive
+            .local_shell
+            .as_ref()
+            .and_then(HookLocalShellDirective::timeout_behavior)
+            .unwrap();
+        assert_eq!(behavior, TimeoutBehavior::Default);
+        assert!(!directive.overrides_timeout());
+    }
+
+    #[test]
+    fn invalid_utf8_hook_stdout_is_ignored() {
+        let valid = br#"{"local_shell":{"timeout_ms":"infinite"}}"#;
+        assert!(parse_directive_output::<ToolHookDirective>(valid).is_some());
+
+        // A lossy decode would turn the stray byte into U+FFFD and still parse.
+        let corrupted = b"{\"local_shell\":{\"timeout_ms\":\"infin\xffite\"}}";
+        assert!(
+            serde_json::from_str::<ToolHookDirective>(&String::from_utf8_lossy(corrupted)).is_ok()
+        );
+        assert!(parse_directive_output::<ToolHookDirective>(corrupted).is_none());
+    }
+
+    fn argv(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn categorizes_commands_by_program() {
+        assert_eq!(
+            CommandCategory::from_argv(&argv(&["curl", "https://example.com"])),
+            CommandCategory::Network
+        );
+        assert_eq!(
+            CommandCategory::from_argv(&argv(&["/bin/rm", "-rf", "target"])),
+            CommandCategory::FilesystemWrite
+        );
+        assert_eq!(
+            CommandCategory::from_argv(&argv(&["bash", "-lc", "npm install"])),
+            CommandCategory::PackageManager
+        );
+        assert_eq!(
+            CommandCategory::from_argv(&argv(&["frobnicate", "--all"])),
+            CommandCategory::Other
+        );
+    }
+
+    #[test]
+    fn shell_command_function_snapshot_includes_category() {
+        let call = ToolCall {
+            tool_name: "shell_command".to_string(),
+            call_id: "call-1".to_string(),
+            payload: ToolPayload::Function {
+                arguments: r#"{"command":"wget https://example.com"}"#.to_string(),
+            },
+        };
+        let snapshot = serde_json::to_value(ToolCallSnapshot::from_call(&call)).unwrap();
+        assert_eq!(snapshot["payload"]["category"], "network");
+    }
+
+    #[test]
+    fn redactor_masks_secret_patterns_and_keys_in_snapshots() {
+        let redactor =
+            SnapshotRedactor::new(&[r"ghp_[A-Za-z0-9]+".to_string()], &["api_key".to_string()])
+                .unwrap();
+        let function_call = ToolCall {
+            tool_name: "shell_command".to_string(),
+            call_id: "call-secret".to_string(),
+            payload: ToolPayload::Function {
+                arguments:
+                    r#"{"API_KEY":"sk-1","command":"gh auth login --with-token ghp_abc123"}"#
+                        .to_string(),
+            },
+        };
+        let mcp_call = ToolCall {
+            tool_name: "mcp__github__search".to_string(),
+            call_id: "call-mcp".to_string(),
+            payload: ToolPayload::Mcp {
+                server: "github".to_string(),
+                tool: "search".to_string(),
+                raw_arguments: "token=ghp_xyz789".to_string(),
+            },
+        };
+
+        let snapshots = [function_call, mcp_call].map(|call| {
+            serde_json::to_value(ToolCallSnapshot::from_call(&call).redacted(&redactor)).unwrap()
+        });
+
+        assert_eq!(
+            snapshots,
+            [
+                serde_json::json!({
+                    "tool_name": "shell_command",
+                    "call_id": "call-secret",
+                    "payload": {
+                        "kind": "function",
+                        "arguments": r#"{"API_KEY":"[REDACTED]","command":"gh auth login --with-token [REDACTED]"}"#,
+                        "parsed_arguments": {
+                            "command": "gh auth login --with-token [REDACTED]",
+                            "API_KEY": "[REDACTED]",
+                        },
+                        "category": "other",
+                    },
+                }),
+                serde_json::json!({
+                    "tool_name": "mcp__github__search",
+                    "call_id": "call-mcp",
+                    "payload": {
+                        "kind": "mcp",
+                        "server": "github",
+                        "tool": "search",
+                        "raw_arguments": "token=[REDACTED]",
+                    },
+                }),
+            ]
+        );
+    }
+
+    fn message(role: &str, text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![ContentItem::OutputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    fn sample_history() -> Vec<ResponseItem> {
+        vec![
+            message("user", "list files"),
+            message("assistant", "running ls"),
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell_command".to_string(),
+                arguments: r#"{"command":"ls"}"#.to_string(),
+                call_id: "call-1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "call-1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "README.md".to_string(),
+                    ..Default::default()
+                },
+            },
+            message("assistant", "found README.md"),
+        ]
+    }
+
+    #[test]
+    fn final_message_limit_truncates_only_long_messages() {
+        let event = |final_message: &str| {
+            let event = StopHookEvent::new(
+                "conversation".to_string(),
+                "turn".to_string(),
+                "/tmp".to_string(),
+                Some(final_message.to_string()),
+                Vec::new(),
+                StopHookResponseItems::Full,
+                None,
+            )
+            .with_final_message_limit(Some(10));
+            let value = serde_json::to_value(event).unwrap();
+            (
+                value["final_message"].clone(),
+                value.get("final_message_truncated").cloned(),
+            )
+        };
+
+        assert_eq!(
+            [
+                event("All tests passed after fixing the parser."),
+                event("Done.")
+            ],
+            [
+                (
+                    serde_json::json!("All tests …"),
+                    Some(serde_json::json!(true))
+                ),
+                (serde_json::json!("Done."), None),
+            ]
+        );
+    }
+
+    fn serialized_response_items(filter: StopHookResponseItems) -> Value {
+        let event = StopHookEvent::new(
+            "conversation".to_string(),
+            "turn".to_string(),
+            "/tmp".to_string(),
+            Some("found README.md".to_string()),
+            sample_history(),
+            filter,
+            None,
+        );
+        serde_json::to_value(event).unwrap()["response_items"].clone()
+    }
+
+    #[test]
+    fn stop_hook_full_response_items() {
+        assert_eq!(
+            serialized_response_items(StopHookResponseItems::Full),
+            serde_json::to_value(sample_history()).unwrap()
+        );
+    }
+
+    #[test]
+    fn stop_hook_final_message_only_response_items() {
+        assert_eq!(
+            serialized_response_items(StopHookResponseItems::FinalMessageOnly),
+            serde_json::to_value(vec![message("assistant", "found README.md")]).unwrap()
+        );
+    }
+
+    #[test]
+    fn stop_hook_tool_calls_only_response_items() {
+        let history = sample_history();
+        assert_eq!(
+            serialized_response_items(StopHookResponseItems::ToolCallsOnly),
+            serde_json::to_value(&history[2..4]).unwrap()
+        );
+    }
+
+    /// `sh -c script` with `args` bound to `$0`, `$1`, and so on.
+    #[cfg(unix)]
+    fn sh_argv(script: &str, args: &[&str]) -> Vec<String> {
+        let mut command = argv(&["sh", "-c", script]);
+        command.extend(args.iter().map(ToString::to_string));
+        command
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    #[traced_test]
+    async fn captured_hook_stderr_is_forwarded_to_tracing() {
+        let hook = StopHook::new(sh_argv("cat > /dev/null; echo hook-diagnostic >&2", &[]))
+            .unwrap()
+            .with_stderr(HookStderr::Capture);
+        let event = StopHookEvent::new(
+            "conversation".to_string(),
+            "turn".to_string(),
+            "/tmp".to_string(),
+            None,
+            Vec::new(),
+            StopHookResponseItems::Full,
+            None,
+        );
+
+        hook.spawn_and_send(event).await.unwrap();
+
+        assert!(logs_contain("hook_stderr=hook-diagnostic"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    #[traced_test]
+    async fn plain_text_stop_hook_stdout_is_not_parsed_as_a_directive() {
+        let hook = StopHook::new(sh_argv("cat > /dev/null; echo turn finished", &[])).unwrap();
+        let event = StopHookEvent::new(
+            "conversation".to_string(),
+            "turn".to_string(),
+            "/tmp".to_string(),
+            None,
+            Vec::new(),
+            StopHookResponseItems::Full,
+            None,
+        );
+
+        let directive = hook.spawn_and_send(event).await.unwrap();
+
+        assert_eq!(directive, None);
+        assert!(!logs_contain("failed to parse hook output"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn daemon_stop_hook_streams_event_to_socket() {
+        use tokio::io::AsyncReadExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("stop-hook.sock");
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        let daemon = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut received = String::new();
+            stream.read_to_string(&mut received).await.unwrap();
+            stream
+                .write_all(br#"{"export_to": "exported.jsonl"}"#)
+                .await
+                .unwrap();
+            received
+        });
+        let hook = StopHook::daemon(socket);
+        let event = StopHookEvent::new(
+            "conversation".to_string(),
+            "turn".to_string(),
+            "/tmp".to_string(),
+            Some("done".to_string()),
+            Vec::new(),
+            StopHookResponseItems::Full,
+            None,
+        );
+
+        let directive = hook.spawn_and_send(event).await.unwrap();
+
+        let received = daemon.await.unwrap();
+        assert!(received.ends_with('\n'));
+        let recorded: Value = serde_json::from_str(&received).unwrap();
+        assert_eq!(
+            (
+                recorded["turn_id"].clone(),
+                recorded["final_message"].clone()
+            ),
+            (Value::from("turn"), Value::from("done"))
+        );
+        assert_eq!(
+            directive,
+            Some(StopHookDirective {
+                export_to: Some(PathBuf::from("exported.jsonl")),
+            })
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn relative_hook_program_resolves_against_base_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let base_dir = tempfile::tempdir().unwrap();
+        let script = base_dir.path().join("record-stop-hook.sh");
+        std::fs::write(&script, "#!/bin/sh\ncat > \"$1\"\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let output = base_dir.path().join("event.json");
+        let hook = StopHook::new_in(
+            argv(&["record-stop-hook.sh", output.to_str().unwrap()]),
+            base_dir.path(),
+        )
+        .unwrap();
+        let event = StopHookEvent::new(
+            "conversation".to_string(),
+            "turn".to_string(),
+            "/tmp".to_string(),
+            Some("done".to_string()),
+            Vec::new(),
+            StopHookResponseItems::Full,
+            None,
+        );
+
+        hook.spawn_and_send(event).await.unwrap();
+
+        let recorded: Value =
+            serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(recorded["final_message"], "done");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn env_format_exports_scalars_and_sends_payload_on_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let stdin_path = dir.path().join("stdin.json");
+        let hook = ToolHook::new(sh_argv(
+            r#"cat > "$0"; if [ "$CODEX_HOOK_PHASE" = before_execution ] && [ "$CODEX_HOOK_CALL_ID" = call-env ]; then echo '{"defer_ms": 7}'; fi"#,
+            &[stdin_path.to_str().unwrap()],
+        ))
+        .unwrap()
+        .with_format(ToolHookFormat::Env);
+        let call = ToolCall {
+            tool_name: "custom_tool".to_string(),
+            call_id: "call-env".to_string(),
+            payload: ToolPayload::Custom {
+                input: "hello".to_string(),
+            },
+        };
+
+        let directive = hook
+            .spawn_and_send(ToolHookEvent::before(ToolCallSnapshot::from_call(&call)))
+            .await
+            .unwrap();
+
+        assert_eq!(directive.and_then(|directive| directive.defer_ms), Some(7));
+        let stdin: Value =
+            serde_json::from_str(&std::fs::read_to_string(&stdin_path).unwrap()).unwrap();
+        assert_eq!(
+            stdin,
+            serde_json::json!({ "payload": { "kind": "custom", "input": "hello" } })
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn allowlisted_hook_environment_only_exposes_permitted_variables() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("env.txt");
+        let policy = ShellEnvironmentPolicy {
+            r#set: HashMap::from([
+                ("HOOK_ALLOWED".to_string(), "yes".to_string()),
+                ("HOOK_BLOCKED".to_string(), "no".to_string()),
+            ]),
+            include_only: vec![EnvironmentVariablePattern::new_case_insensitive(
+                "HOOK_ALLOWED",
+            )],
+            ..Default::default()
+        };
+        let hook = ToolHook::new(sh_argv(
+            r#"cat > /dev/null; printf '%s %s %s' "${HOOK_ALLOWED-unset}" "${HOOK_BLOCKED-unset}" "${HOME-unset}" > "$0""#,
+            &[output.to_str().unwrap()],
+        ))
+        .unwrap()
+        .with_env_policy(Some(policy));
+        let call = ToolCall {
+            tool_name: "custom_tool".to_string(),
+            call_id: "call-env".to_string(),
+            payload: ToolPayload::Custom {
+                input: "hello".to_string(),
+            },
+        };
+
+        hook.spawn_and_send(ToolHookEvent::before(ToolCallSnapshot::from_call(&call)))
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "yes unset unset");
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn debug_echo_hook_logs_the_event_and_returns_no_directive() {
+        let call = ToolCall {
+            tool_name: "custom_tool".to_string(),
+            call_id: "call-echo".to_string(),
+            payload: ToolPayload::Custom {
+                input: "hello".to_string(),
+            },
+        };
+
+        let directive = ToolHook::debug_echo()
+            .emit(ToolHookEvent::before(ToolCallSnapshot::from_call(&call)))
+            .await;
+
+        assert!(directive.is_none());
+        assert!(logs_contain(r#""phase":"before_execution""#));
+        assert!(logs_contain(r#""call_id":"call-echo""#));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn missing_hook_program_disables_the_hook_with_one_warning() {
+        let missing = tempfile::tempdir().unwrap().path().join("no-such-hook");
+
+        let tool_hook = ToolHook::new(argv(&[missing.to_str().unwrap(), "--flag"]));
+        let stop_hook = StopHook::new(argv(&["codex-no-such-hook-program"]));
+
+        assert!(tool_hook.is_none());
+        assert!(stop_hook.is_none());
+        logs_assert(|lines: &[&str]| {
+            let warnings = lines
+                .iter()
+                .filter(|line| line.contains("program not found"))
+                .count();
+            if warnings == 2 {
+                Ok(())
+            } else {
+                Err(format!("expected one warning per hook, saw {warnings}"))
+            }
+        });
+    }
+
+    #[test]
+    fn absolute_and_path_programs_are_not_rewritten() {
+        let base_dir = tempfile::tempdir().unwrap();
+        std::fs::write(base_dir.path().join("sh"), "").unwrap();
+
+        assert_eq!(resolve_hook_program("/bin/sh", base_dir.path()), None);
+        assert_eq!(resolve_hook_program("sh", base_dir.path()), None);
+    }
 }
 
 #[derive(Serialize)]
@@ -352,9 +1717,19 @@ pub struct StopHookEvent {
     cwd: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     final_message: Option<String>,
+    /// Set when `final_message` was cut to `stop_hook_final_message_max_chars`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    final_message_truncated: bool,
     response_items: Vec<ResponseItem>,
     #[serde(skip_serializing_if = "Option::is_none")]
     token_usage: Option<TokenUsage>,
+    /// Set when the turn ended in an error instead of completing normally.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Branch and dirty files of `cwd`, when `stop_hook_git_status` is on and
+    /// `cwd` is inside a git repository.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    git: Option<GitWorktreeStatus>,
 }
 
 impl StopHookEvent {
@@ -364,6 +1739,7 @@ impl StopHookEvent {
         cwd: String,
         final_message: Option<String>,
         response_items: Vec<ResponseItem>,
+        response_items_filter: StopHookResponseItems,
         token_usage: Option<TokenUsage>,
     ) -> Self {
         Self {
@@ -371,8 +1747,62 @@ impl StopHookEvent {
             turn_id,
             cwd,
             final_message,
-            response_items,
+            final_message_truncated: false,
+            response_items: filter_response_items(response_items, response_items_filter),
             token_usage,
+            error: None,
+            git: None,
+        }
+    }
+
+    pub fn with_error(mut self, error: Option<String>) -> Self {
+        self.error = error;
+        self
+    }
+
+    pub fn with_git(mut self, git: Option<GitWorktreeStatus>) -> Self {
+        self.git = git;
+        self
+    }
+
+    /// Cut `final_message` to at most `max_chars` characters plus an ellipsis,
+    /// flagging the event as truncated. `None` leaves the message whole.
+    pub fn with_final_message_limit(mut self, max_chars: Option<usize>) -> Self {
+        if let (Some(max_chars), Some(message)) = (max_chars, self.final_message.as_mut())
+            && let Some((cut, _)) = message.char_indices().nth(max_chars)
+        {
+            message.truncate(cut);
+            message.push('…');
+            self.final_message_truncated = true;
         }
+        self
+    }
+}
+
+fn filter_response_items(
+    items: Vec<ResponseItem>,
+    filter: StopHookResponseItems,
+) -> Vec<ResponseItem> {
+    match filter {
+        StopHookResponseItems::Full => items,
+        StopHookResponseItems::FinalMessageOnly => items
+            .into_iter()
+            .rfind(|item| matches!(item, ResponseItem::Message { role, .. } if role == "assistant"))
+            .into_iter()
+            .collect(),
+        StopHookResponseItems::ToolCallsOnly => items
+            .into_iter()
+            .filter(|item| {
+                matches!(
+                    item,
+                    ResponseItem::LocalShellCall { .. }
+                        | ResponseItem::FunctionCall { .. }
+                        | ResponseItem::FunctionCallOutput { .. }
+                        | ResponseItem::CustomToolCall { .. }
+                        | ResponseItem::CustomToolCallOutput { .. }
+                        | ResponseItem::WebSearchCall { .. }
+                )
+            })
+            .collect(),
     }
 }
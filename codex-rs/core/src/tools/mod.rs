@@ -4,6 +4,7 @@ pub(crate) mod handlers;
 pub mod hooks;
 pub mod orchestrator;
 pub mod parallel;
+pub(crate) mod rate_limit;
 pub mod registry;
 pub mod router;
 pub mod runtimes;
@@ -1,4 +1,6 @@
 use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 use std::time::Instant;
 
 use tokio::sync::RwLock;
@@ -14,10 +16,8 @@ use crate::codex::TurnContext;
 use crate::error::CodexErr;
 use crate::function_tool::FunctionCallError;
 use crate::tools::context::SharedTurnDiffTracker;
-use crate::tools::context::ToolPayload;
 use crate::tools::router::ToolCall;
 use crate::tools::router::ToolRouter;
-use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseInputItem;
 
 #[derive(Clone)]
@@ -27,6 +27,7 @@ pub(crate) struct ToolCallRuntime {
     turn_context: Arc<TurnContext>,
     tracker: SharedTurnDiffTracker,
     parallel_execution: Arc<RwLock<()>>,
+    in_flight: Arc<AtomicUsize>,
 }
 
 impl ToolCallRuntime {
@@ -42,6 +43,7 @@ impl ToolCallRuntime {
             turn_context,
             tracker,
             parallel_execution: Arc::new(RwLock::new(())),
+            in_flight: Arc::new(AtomicUsize::new(0)),
         }
     }
 
@@ -58,6 +60,7 @@ impl ToolCallRuntime {
         let turn = Arc::clone(&self.turn_context);
         let tracker = Arc::clone(&self.tracker);
         let lock = Arc::clone(&self.parallel_execution);
+        let in_flight = InFlightCall::enter(&self.in_flight);
         let started = Instant::now();
 
         let dispatch_span = trace_span!(
@@ -70,25 +73,40 @@ impl ToolCallRuntime {
 
         let handle: AbortOnDropHandle<Result<ResponseInputItem, FunctionCallError>> =
             AbortOnDropHandle::new(tokio::spawn(async move {
-                tokio::select! {
+                let _guard = tokio::select! {
                     _ = cancellation_token.cancelled() => {
                         let secs = started.elapsed().as_secs_f32().max(0.1);
                         dispatch_span.record("aborted", true);
-                        Ok(Self::aborted_response(&call, secs))
+                        return Ok(ToolRouter::aborted_response(&call, secs));
                     },
-                    res = async {
-                        let _guard = if supports_parallel {
+                    guard = async {
+                        if supports_parallel {
                             Either::Left(lock.read().await)
                         } else {
                             Either::Right(lock.write().await)
-                        };
+                        }
+                    } => guard,
+                };
 
-                        router
-                            .dispatch_tool_call(session, turn, tracker, call.clone())
-                            .instrument(dispatch_span.clone())
-                            .await
-                    } => res,
+                let parallel = supports_parallel && in_flight.others_running();
+
+                // The router watches the token from here on so the tool hook
+                // sees the cancellation.
+                let result = router
+                    .dispatch_tool_call_cancellable(
+                        session,
+                        turn,
+                        tracker,
+                        call,
+                        parallel,
+                        &cancellation_token,
+                    )
+                    .instrument(dispatch_span.clone())
+                    .await;
+                if cancellation_token.is_cancelled() {
+                    dispatch_span.record("aborted", true);
                 }
+                result
             }));
 
         async move {
@@ -105,33 +123,27 @@ impl ToolCallRuntime {
     }
 }
 
-impl ToolCallRuntime {
-    fn aborted_response(call: &ToolCall, secs: f32) -> ResponseInputItem {
-        match &call.payload {
-            ToolPayload::Custom { .. } => ResponseInputItem::CustomToolCallOutput {
-                call_id: call.call_id.clone(),
-                output: Self::abort_message(call, secs),
-            },
-            ToolPayload::Mcp { .. } => ResponseInputItem::McpToolCallOutput {
-                call_id: call.call_id.clone(),
-                result: Err(Self::abort_message(call, secs)),
-            },
-            _ => ResponseInputItem::FunctionCallOutput {
-                call_id: call.call_id.clone(),
-                output: FunctionCallOutputPayload {
-                    content: Self::abort_message(call, secs),
-                    ..Default::default()
-                },
-            },
+/// Counts a call as in flight from the moment it is handed to the runtime
+/// until its task finishes or is aborted.
+struct InFlightCall {
+    counter: Arc<AtomicUsize>,
+}
+
+impl InFlightCall {
+    fn enter(counter: &Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self {
+            counter: Arc::clone(counter),
         }
     }
 
-    fn abort_message(call: &ToolCall, secs: f32) -> String {
-        match call.tool_name.as_str() {
-            "shell" | "container.exec" | "local_shell" | "shell_command" | "unified_exec" => {
-                format!("Wall time: {secs:.1} seconds\naborted by user")
-            }
-            _ => format!("aborted by user after {secs:.1}s"),
-        }
+    fn others_running(&self) -> bool {
+        self.counter.load(Ordering::SeqCst) > 1
+    }
+}
+
+impl Drop for InFlightCall {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::SeqCst);
     }
 }
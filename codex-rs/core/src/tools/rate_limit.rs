@@ -0,0 +1,86 @@
+//! Sliding-window limit on how many shell commands a conversation may start
+//! per minute (`shell_calls_per_minute`).
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Length of the sliding window the limit is counted over.
+pub(crate) const SHELL_RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Tools that spawn a command and therefore count against the limit.
+const SHELL_TOOL_NAMES: &[&str] = &[
+    "shell",
+    "container.exec",
+    "local_shell",
+    "shell_command",
+    "exec_command",
+];
+
+pub(crate) fn is_shell_tool(tool_name: &str) -> bool {
+    SHELL_TOOL_NAMES.contains(&tool_name)
+}
+
+/// Message returned to the model in place of the output of a rejected call.
+pub(crate) fn rate_limit_message(limit: u32) -> String {
+    format!(
+        "Rate limit exceeded: at most {limit} shell commands may run per minute in this conversation. Slow down and wait before running another command."
+    )
+}
+
+/// Start times of recent shell calls. Routers are rebuilt every turn, so the
+/// window lives in the session's services instead.
+#[derive(Default)]
+pub(crate) struct ShellRateLimiter {
+    calls: Mutex<VecDeque<Instant>>,
+}
+
+impl ShellRateLimiter {
+    /// Record a call at `now` and return `true`, or return `false` without
+    /// recording it if `limit` calls already started within the window.
+    pub(crate) fn try_acquire(&self, limit: u32, now: Instant) -> bool {
+        let mut window = self.calls.lock().unwrap_or_else(PoisonError::into_inner);
+        while window.front().is_some_and(|started| {
+            now.saturating_duration_since(*started) >= SHELL_RATE_LIMIT_WINDOW
+        }) {
+            window.pop_front();
+        }
+        if window.len() >= limit as usize {
+            return false;
+        }
+        window.push_back(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn window_slides_and_is_kept_per_limiter() {
+        let first = ShellRateLimiter::default();
+        let second = ShellRateLimiter::default();
+        let start = Instant::now();
+
+        let results = [
+            first.try_acquire(2, start),
+            first.try_acquire(2, start + Duration::from_secs(10)),
+            first.try_acquire(2, start + Duration::from_secs(20)),
+            second.try_acquire(2, start + Duration::from_secs(20)),
+            first.try_acquire(2, start + Duration::from_secs(60)),
+            first.try_acquire(2, start + Duration::from_secs(65)),
+        ];
+
+        assert_eq!(results, [true, true, false, true, true, false]);
+    }
+
+    #[test]
+    fn container_exec_counts_as_a_shell_tool() {
+        assert!(is_shell_tool("container.exec"));
+        assert!(!is_shell_tool("read_file"));
+    }
+}
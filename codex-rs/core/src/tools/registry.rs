@@ -7,6 +7,8 @@ use crate::function_tool::FunctionCallError;
 use crate::tools::context::ToolInvocation;
 use crate::tools::context::ToolOutput;
 use crate::tools::context::ToolPayload;
+use crate::tools::hooks::ToolCallSnapshot;
+use crate::tools::router::ToolCall;
 use async_trait::async_trait;
 use codex_protocol::models::ResponseInputItem;
 use codex_utils_readiness::Readiness;
@@ -111,6 +113,7 @@ impl ToolRegistry {
                     let invocation = invocation;
                     let tool_label = tool_name.clone();
                     let call_id_for_note = call_id_owned.clone();
+                    let payload_for_pending = payload_for_response.clone();
                     async move {
                         let session = Arc::clone(&invocation.session);
                         let turn = Arc::clone(&invocation.turn);
@@ -146,13 +149,13 @@ impl ToolRegistry {
                                         },
                                     );
                                     session.notify_background_event(&turn, note).await;
+                                    let call = ToolCallSnapshot::from_call(&ToolCall {
+                                        tool_name: tool,
+                                        call_id,
+                                        payload: payload_for_pending,
+                                    });
                                     session
-                                        .mark_tool_pending(
-                                            &turn,
-                                            call_id,
-                                            tool,
-                                            note_for_event,
-                                        )
+                                        .mark_tool_pending(&turn, call, note_for_event)
                                         .await;
                                 }
                                 Ok((preview, success))
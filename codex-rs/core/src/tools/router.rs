@@ -1,15 +1,19 @@
 use crate::client_common::tools::ToolSpec;
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::config::types::ToolHookMode;
 use crate::function_tool::FunctionCallError;
-use crate::sandboxing::SandboxPermissions;
 use crate::tools::context::SharedTurnDiffTracker;
 use crate::tools::context::ToolInvocation;
 use crate::tools::context::ToolPayload;
+use crate::tools::hooks::SnapshotRedactor;
 use crate::tools::hooks::TimeoutBehavior;
 use crate::tools::hooks::ToolCallSnapshot;
+use crate::tools::hooks::ToolHook;
 use crate::tools::hooks::ToolHookDirective;
 use crate::tools::hooks::ToolHookEvent;
+use crate::tools::hooks::resolve_hook_path;
+use crate::tools::rate_limit;
 use crate::tools::registry::ConfiguredToolSpec;
 use crate::tools::registry::ToolRegistry;
 use crate::tools::spec::ToolsConfig;
@@ -17,14 +21,27 @@ use crate::tools::spec::build_specs;
 use codex_protocol::models::LocalShellAction;
 use codex_protocol::models::ResponseInputItem;
 use codex_protocol::models::ResponseItem;
+use codex_protocol::models::SandboxPermissions;
 use codex_protocol::models::ShellToolCallParams;
+use codex_protocol::protocol::SandboxPolicy;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 use tracing::instrument;
 use tracing::warn;
 
+/// Longest a before-hook may defer a call; longer `defer_ms` requests are
+/// clamped to it.
+const MAX_HOOK_DEFER: Duration = Duration::from_secs(30);
+
 #[derive(Clone, Debug)]
 pub struct ToolCall {
     pub tool_name: String,
@@ -32,9 +49,37 @@ pub struct ToolCall {
     pub payload: ToolPayload,
 }
 
+/// Summary of a finished `dispatch_tool_call`, handed to the router's
+/// dispatch observer.
+#[derive(Clone, Debug)]
+pub struct ToolDispatchReport {
+    pub call: ToolCallSnapshot,
+    pub success: bool,
+    pub duration: Duration,
+    pub error: Option<String>,
+}
+
+pub type ToolDispatchObserver = Arc<dyn Fn(ToolDispatchReport) + Send + Sync>;
+
+/// Which `local_shell` directive fields a before-hook asked for were applied
+/// to a call, and which were ignored (e.g. a timeout for a tool that has
+/// none, or a `cwd` outside the workspace).
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct DirectiveApplication {
+    pub(crate) applied: Vec<&'static str>,
+    pub(crate) ignored: Vec<&'static str>,
+}
+
 pub struct ToolRouter {
     registry: ToolRegistry,
     specs: Vec<ConfiguredToolSpec>,
+    dispatch_observer: Option<ToolDispatchObserver>,
+    /// `timeout_ms` applied per tool name when a call arrives without one.
+    default_timeouts_ms: HashMap<String, u64>,
+    /// Run hooks and return a placeholder result instead of executing calls.
+    dry_run: bool,
+    /// Masks secrets in the snapshots handed to hooks and the observer.
+    snapshot_redactor: SnapshotRedactor,
 }
 
 impl ToolRouter {
@@ -44,8 +89,50 @@ impl ToolRouter {
     ) -> Self {
         let builder = build_specs(config, mcp_tools);
         let (specs, registry) = builder.build();
+        Self::with_registry(registry, specs)
+    }
+
+    /// Build a router around an already-assembled registry instead of
+    /// deriving one from `ToolsConfig`, e.g. to dispatch to stub handlers in
+    /// tests.
+    pub fn with_registry(registry: ToolRegistry, specs: Vec<ConfiguredToolSpec>) -> Self {
+        Self {
+            registry,
+            specs,
+            dispatch_observer: None,
+            default_timeouts_ms: HashMap::new(),
+            dry_run: false,
+            snapshot_redactor: SnapshotRedactor::default(),
+        }
+    }
 
-        Self { registry, specs }
+    /// Give calls to the named tools this `timeout_ms` when neither the model
+    /// nor the tool hook chose one.
+    pub fn with_default_timeouts(mut self, default_timeouts_ms: HashMap<String, u64>) -> Self {
+        self.default_timeouts_ms = default_timeouts_ms;
+        self
+    }
+
+    /// Preview calls instead of running them: hooks still see both phases
+    /// (flagged `dry_run`), but no handler executes and the model receives a
+    /// placeholder output.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Mask secrets in call snapshots before hooks or the dispatch observer
+    /// see them.
+    pub fn with_snapshot_redactor(mut self, redactor: SnapshotRedactor) -> Self {
+        self.snapshot_redactor = redactor;
+        self
+    }
+
+    /// Invoke `observer` after every dispatched tool call, on both the success
+    /// and failure paths.
+    pub fn with_dispatch_observer(mut self, observer: ToolDispatchObserver) -> Self {
+        self.dispatch_observer = Some(observer);
+        self
     }
 
     pub fn specs(&self) -> Vec<ToolSpec> {
@@ -55,6 +142,15 @@ impl ToolRouter {
             .collect()
     }
 
+    /// The specs whose tool name is in `names`, in advertised order.
+    pub fn specs_for(&self, names: &HashSet<String>) -> Vec<ToolSpec> {
+        self.specs
+            .iter()
+            .filter(|config| names.contains(config.spec.name()))
+            .map(|config| config.spec.clone())
+            .collect()
+    }
+
     pub fn tool_supports_parallel(&self, tool_name: &str) -> bool {
         self.specs
             .iter()
@@ -114,13 +210,7 @@ impl ToolRouter {
 
                 match action {
                     LocalShellAction::Exec(exec) => {
-                        let params = ShellToolCallParams {
-                            command: exec.command,
-                            workdir: exec.working_directory,
-                            timeout_ms: exec.timeout_ms,
-                            sandbox_permissions: Some(SandboxPermissions::UseDefault),
-                            justification: None,
-                        };
+                        let params = ShellToolCallParams::from_local_shell_exec(exec);
                         Ok(Some(ToolCall {
                             tool_name: "local_shell".to_string(),
                             call_id,
@@ -133,7 +223,6 @@ impl ToolRouter {
         }
     }
 
-    #[instrument(level = "trace", skip_all, err)]
     pub async fn dispatch_tool_call(
         &self,
         session: Arc<Session>,
@@ -141,17 +230,180 @@ impl ToolRouter {
         tracker: SharedTurnDiffTracker,
         call: ToolCall,
     ) -> Result<ResponseInputItem, FunctionCallError> {
-        let hook = turn.tool_hook.clone();
+        self.dispatch_tool_call_with_concurrency(session, turn, tracker, call, false)
+            .await
+    }
+
+    /// Like [`ToolRouter::dispatch_tool_call`], but tells the tool hook
+    /// whether the call is running alongside other parallel calls.
+    pub async fn dispatch_tool_call_with_concurrency(
+        &self,
+        session: Arc<Session>,
+        turn: Arc<TurnContext>,
+        tracker: SharedTurnDiffTracker,
+        call: ToolCall,
+        parallel: bool,
+    ) -> Result<ResponseInputItem, FunctionCallError> {
+        self.dispatch_tool_call_cancellable(
+            session,
+            turn,
+            tracker,
+            call,
+            parallel,
+            &CancellationToken::new(),
+        )
+        .await
+    }
+
+    /// Like [`ToolRouter::dispatch_tool_call_with_concurrency`], but stops
+    /// waiting on the call once `cancellation_token` (normally the turn's) is
+    /// cancelled, including while the before-hook runs or defers it. The call
+    /// then resolves to an "aborted by user" output and the hook sees a
+    /// `cancelled` outcome.
+    #[instrument(level = "trace", skip_all, err)]
+    pub async fn dispatch_tool_call_cancellable(
+        &self,
+        session: Arc<Session>,
+        turn: Arc<TurnContext>,
+        tracker: SharedTurnDiffTracker,
+        call: ToolCall,
+        parallel: bool,
+        cancellation_token: &CancellationToken,
+    ) -> Result<ResponseInputItem, FunctionCallError> {
+        let hook = turn
+            .tool_hook
+            .clone()
+            .filter(|hook| hook.applies_to(&call.tool_name));
+        let sandbox_policy = turn.sandbox_policy.clone();
+        let snapshot = (hook.is_some() || self.dispatch_observer.is_some())
+            .then(|| ToolCallSnapshot::from_call(&call).redacted(&self.snapshot_redactor));
+        let cancelled_call = call.clone();
+        let started = Instant::now();
+
+        tokio::select! {
+            result = self.dispatch_with_hooks(
+                session,
+                turn,
+                tracker,
+                call,
+                parallel,
+                hook.clone(),
+                snapshot.clone(),
+            ) => result,
+            () = cancellation_token.cancelled() => {
+                let secs = started.elapsed().as_secs_f32().max(0.1);
+                let message = Self::abort_message(&cancelled_call, secs);
+                // Don't hold up the abort on the hook process.
+                if let (Some(hook), Some(snapshot)) = (hook, snapshot.clone()) {
+                    let event = ToolHookEvent::after_cancelled(snapshot, message.clone())
+                        .with_parallel(parallel)
+                        .with_dry_run(self.dry_run)
+                        .with_sandbox(&sandbox_policy)
+                        .with_tool_duration(started.elapsed());
+                    tokio::spawn(async move {
+                        let _ = hook.emit(event).await;
+                    });
+                }
+                self.report_dispatch(snapshot, started, Some(message));
+                Ok(Self::aborted_response(&cancelled_call, secs))
+            }
+        }
+    }
+
+    /// The before-hook, rate limiting, and the dispatch itself, with the
+    /// after-hook reporting how the call ended. Cancellation is handled by
+    /// the caller.
+    #[allow(clippy::too_many_arguments)]
+    async fn dispatch_with_hooks(
+        &self,
+        session: Arc<Session>,
+        turn: Arc<TurnContext>,
+        tracker: SharedTurnDiffTracker,
+        call: ToolCall,
+        parallel: bool,
+        hook: Option<ToolHook>,
+        snapshot: Option<ToolCallSnapshot>,
+    ) -> Result<ResponseInputItem, FunctionCallError> {
+        let sandbox_policy = turn.sandbox_policy.clone();
         let mut call = call;
-        let hook_snapshot = hook.as_ref().map(|_| ToolCallSnapshot::from_call(&call));
-        let directive =
-            if let (Some(hook), Some(snapshot)) = (hook.as_ref(), hook_snapshot.as_ref()) {
-                hook.emit(ToolHookEvent::before(snapshot.clone())).await
-            } else {
-                None
-            };
-        if let Some(directive) = directive {
-            Self::apply_tool_hook_directive(&mut call, directive);
+        let directive = if let (Some(hook), Some(snapshot)) = (hook.as_ref(), snapshot.as_ref())
+            && Self::should_run_before_hook(hook.mode(), &turn)
+        {
+            Self::run_before_hook(hook, snapshot, parallel, self.dry_run, &sandbox_policy).await
+        } else {
+            None
+        };
+        if let Some(reason) = directive
+            .as_ref()
+            .and_then(|directive| directive.block.clone())
+        {
+            let started = Instant::now();
+            if let (Some(hook), Some(snapshot)) = (hook.as_ref(), snapshot.as_ref()) {
+                let _ = hook
+                    .emit(
+                        ToolHookEvent::after_blocked(snapshot.clone(), reason.clone())
+                            .with_parallel(parallel)
+                            .with_dry_run(self.dry_run)
+                            .with_sandbox(&sandbox_policy),
+                    )
+                    .await;
+            }
+            let message = format!("Tool call blocked by hook: {reason}");
+            self.report_dispatch(snapshot, started, Some(message.clone()));
+            return Ok(Self::failure_response(
+                call.call_id,
+                matches!(call.payload, ToolPayload::Custom { .. }),
+                FunctionCallError::RespondToModel(message),
+            ));
+        }
+        self.apply_directive_and_default_timeout(&mut call, directive, &turn.cwd);
+        if self.dry_run {
+            let started = Instant::now();
+            let response = Self::dry_run_response(&call);
+            if let (Some(hook), Some(snapshot)) = (hook.as_ref(), snapshot.as_ref()) {
+                let _ = hook
+                    .emit(
+                        ToolHookEvent::after_success(snapshot.clone(), response.clone())
+                            .with_parallel(parallel)
+                            .with_dry_run(true)
+                            .with_sandbox(&sandbox_policy),
+                    )
+                    .await;
+            }
+            self.report_dispatch(snapshot, started, None);
+            return Ok(response);
+        }
+        // Only calls that would actually run count against the limit, so
+        // blocked and dry-run calls never use up the quota.
+        if let Some(limit) = turn.shell_calls_per_minute
+            && rate_limit::is_shell_tool(&call.tool_name)
+            && !session
+                .services
+                .shell_rate_limiter
+                .try_acquire(limit, Instant::now())
+        {
+            warn!(
+                call_id = %call.call_id,
+                limit, "shell call rejected by rate limit"
+            );
+            let started = Instant::now();
+            let message = rate_limit::rate_limit_message(limit);
+            if let (Some(hook), Some(snapshot)) = (hook.as_ref(), snapshot.as_ref()) {
+                let _ = hook
+                    .emit(
+                        ToolHookEvent::after_blocked(snapshot.clone(), message.clone())
+                            .with_parallel(parallel)
+                            .with_dry_run(self.dry_run)
+                            .with_sandbox(&sandbox_policy),
+                    )
+                    .await;
+            }
+            self.report_dispatch(snapshot, started, Some(message.clone()));
+            return Ok(Self::failure_response(
+                call.call_id,
+                matches!(call.payload, ToolPayload::Custom { .. }),
+                FunctionCallError::RespondToModel(message),
+            ));
         }
 
         let ToolCall {
@@ -171,38 +423,52 @@ impl ToolRouter {
             payload,
         };
 
-        match self.registry.dispatch(invocation).await {
+        let started = Instant::now();
+        let result = self.registry.dispatch(invocation).await;
+        match result {
             Ok(response) => {
-                if let (Some(hook), Some(snapshot)) = (hook.as_ref(), hook_snapshot.as_ref()) {
+                if let (Some(hook), Some(snapshot)) = (hook.as_ref(), snapshot.as_ref()) {
                     let _ = hook
-                        .emit(ToolHookEvent::after_success(
-                            snapshot.clone(),
-                            response.clone(),
-                        ))
+                        .emit(
+                            ToolHookEvent::after_success(snapshot.clone(), response.clone())
+                                .with_parallel(parallel)
+                                .with_dry_run(self.dry_run)
+                                .with_sandbox(&sandbox_policy)
+                                .with_tool_duration(started.elapsed()),
+                        )
                         .await;
                 }
+                self.report_dispatch(snapshot, started, None);
                 Ok(response)
             }
             Err(FunctionCallError::Fatal(message)) => {
-                if let (Some(hook), Some(snapshot)) = (hook.as_ref(), hook_snapshot.as_ref()) {
+                if let (Some(hook), Some(snapshot)) = (hook.as_ref(), snapshot.as_ref()) {
                     let _ = hook
-                        .emit(ToolHookEvent::after_error(
-                            snapshot.clone(),
-                            message.clone(),
-                        ))
+                        .emit(
+                            ToolHookEvent::after_error(snapshot.clone(), message.clone())
+                                .with_parallel(parallel)
+                                .with_dry_run(self.dry_run)
+                                .with_sandbox(&sandbox_policy)
+                                .with_tool_duration(started.elapsed()),
+                        )
                         .await;
                 }
+                self.report_dispatch(snapshot, started, Some(message.clone()));
                 Err(FunctionCallError::Fatal(message))
             }
             Err(err) => {
-                if let (Some(hook), Some(snapshot)) = (hook.as_ref(), hook_snapshot.as_ref()) {
+                if let (Some(hook), Some(snapshot)) = (hook.as_ref(), snapshot.as_ref()) {
                     let _ = hook
-                        .emit(ToolHookEvent::after_error(
-                            snapshot.clone(),
-                            err.to_string(),
-                        ))
+                        .emit(
+                            ToolHookEvent::after_error(snapshot.clone(), err.to_string())
+                                .with_parallel(parallel)
+                                .with_dry_run(self.dry_run)
+                                .with_sandbox(&sandbox_policy)
+                                .with_tool_duration(started.elapsed()),
+                        )
                         .await;
                 }
+                self.report_dispatch(snapshot, started, Some(err.to_string()));
                 Ok(Self::failure_response(
                     failure_call_id,
                     payload_outputs_custom,
@@ -212,12 +478,144 @@ impl ToolRouter {
         }
     }
 
+    /// Run the before-hook, honoring at most one `defer_ms` request: the call
+    /// waits, the hook runs again, and a second deferral is ignored so a hook
+    /// can never hold a call back indefinitely.
+    async fn run_before_hook(
+        hook: &ToolHook,
+        snapshot: &ToolCallSnapshot,
+        parallel: bool,
+        dry_run: bool,
+        sandbox_policy: &SandboxPolicy,
+    ) -> Option<ToolHookDirective> {
+        let before = || {
+            ToolHookEvent::before(snapshot.clone())
+                .with_parallel(parallel)
+                .with_dry_run(dry_run)
+                .with_sandbox(sandbox_policy)
+        };
+        let hook_started = Instant::now();
+        let directive = hook.emit(before()).await;
+        debug!(
+            call_id = snapshot.call_id(),
+            duration_ms = hook_started.elapsed().as_millis(),
+            "tool before-hook finished"
+        );
+        let Some(defer_ms) = directive.as_ref().and_then(|directive| directive.defer_ms) else {
+            return directive;
+        };
+
+        debug!(
+            call_id = snapshot.call_id(),
+            defer_ms, "tool hook deferred call"
+        );
+        tokio::time::sleep(Self::hook_defer_delay(defer_ms, snapshot.call_id())).await;
+        let directive = hook.emit(before()).await;
+        if directive
+            .as_ref()
+            .is_some_and(|directive| directive.defer_ms.is_some())
+        {
+            warn!(
+                call_id = snapshot.call_id(),
+                "tool hook deferred the same call twice; running it now"
+            );
+        }
+        directive
+    }
+
+    fn should_run_before_hook(mode: ToolHookMode, turn: &TurnContext) -> bool {
+        match mode {
+            ToolHookMode::EveryCall => true,
+            ToolHookMode::OncePerTurn => !turn.tool_hook_before_fired.swap(true, Ordering::Relaxed),
+        }
+    }
+
+    fn report_dispatch(
+        &self,
+        snapshot: Option<ToolCallSnapshot>,
+        started: Instant,
+        error: Option<String>,
+    ) {
+        let (Some(observer), Some(call)) = (self.dispatch_observer.as_ref(), snapshot) else {
+            return;
+        };
+        observer(ToolDispatchReport {
+            call,
+            success: error.is_none(),
+            duration: started.elapsed(),
+            error,
+        });
+    }
+
+    /// Output returned in place of a call that was cancelled mid-flight.
+    pub(crate) fn aborted_response(call: &ToolCall, secs: f32) -> ResponseInputItem {
+        match &call.payload {
+            ToolPayload::Custom { .. } => ResponseInputItem::CustomToolCallOutput {
+                call_id: call.call_id.clone(),
+                output: Self::abort_message(call, secs),
+            },
+            ToolPayload::Mcp { .. } => ResponseInputItem::McpToolCallOutput {
+                call_id: call.call_id.clone(),
+                result: Err(Self::abort_message(call, secs)),
+            },
+            _ => ResponseInputItem::FunctionCallOutput {
+                call_id: call.call_id.clone(),
+                output: codex_protocol::models::FunctionCallOutputPayload {
+                    content: Self::abort_message(call, secs),
+                    ..Default::default()
+                },
+            },
+        }
+    }
+
+    fn abort_message(call: &ToolCall, secs: f32) -> String {
+        match call.tool_name.as_str() {
+            "shell" | "container.exec" | "local_shell" | "shell_command" | "unified_exec" => {
+                format!("Wall time: {secs:.1} seconds\naborted by user")
+            }
+            _ => format!("aborted by user after {secs:.1}s"),
+        }
+    }
+
+    fn hook_defer_delay(defer_ms: u64, call_id: &str) -> Duration {
+        let requested = Duration::from_millis(defer_ms);
+        if requested > MAX_HOOK_DEFER {
+            warn!(
+                call_id,
+                defer_ms,
+                max_ms = MAX_HOOK_DEFER.as_millis(),
+                "tool hook defer_ms exceeds the maximum; clamping"
+            );
+            return MAX_HOOK_DEFER;
+        }
+        requested
+    }
+
+    fn dry_run_response(call: &ToolCall) -> ResponseInputItem {
+        let message = format!("Dry run: `{}` was not executed.", call.tool_name);
+        if matches!(call.payload, ToolPayload::Custom { .. }) {
+            ResponseInputItem::CustomToolCallOutput {
+                call_id: call.call_id.clone(),
+                output: message,
+            }
+        } else {
+            ResponseInputItem::FunctionCallOutput {
+                call_id: call.call_id.clone(),
+                output: codex_protocol::models::FunctionCallOutputPayload {
+                    content: message,
+                    success: Some(true),
+                    ..Default::default()
+                },
+            }
+        }
+    }
+
     fn failure_response(
         call_id: String,
         payload_outputs_custom: bool,
         err: FunctionCallError,
     ) -> ResponseInputItem {
-        let message = err.to_string();
+        let message = err.model_output();
         if payload_outputs_custom {
             ResponseInputItem::CustomToolCallOutput {
                 call_id,
@@ -235,49 +633,203 @@ impl ToolRouter {
         }
     }
 
-    fn apply_tool_hook_directive(call: &mut ToolCall, directive: ToolHookDirective) {
+    /// Apply the before-hook directive, then fill in the tool's configured
+    /// default timeout unless the hook set or lifted the timeout itself.
+    fn apply_directive_and_default_timeout(
+        &self,
+        call: &mut ToolCall,
+        directive: Option<ToolHookDirective>,
+        workspace_root: &Path,
+    ) {
+        let hook_set_timeout = directive
+            .as_ref()
+            .is_some_and(ToolHookDirective::overrides_timeout);
+        if let Some(directive) = directive {
+            Self::apply_tool_hook_directive(call, directive, workspace_root);
+        }
+        if !hook_set_timeout {
+            self.apply_default_timeout(call);
+        }
+    }
+
+    /// Apply a before-hook's `local_shell` overrides to the call. A `cwd`
+    /// that escapes `workspace_root` is ignored with a warning. Returns which
+    /// fields took effect; the summary is also logged at debug level.
+    fn apply_tool_hook_directive(
+        call: &mut ToolCall,
+        directive: ToolHookDirective,
+        workspace_root: &Path,
+    ) -> DirectiveApplication {
+        let mut summary = DirectiveApplication::default();
         let Some(local_shell) = directive.local_shell else {
-            return;
+            return summary;
         };
 
-        let Some(behavior) = local_shell.timeout_behavior() else {
-            return;
-        };
+        let behavior = local_shell.timeout_behavior();
+        let workdir = local_shell
+            .cwd()
+            .and_then(|cwd| Self::resolve_hook_workdir(cwd, workspace_root));
+        if local_shell.cwd().is_some() && workdir.is_none() {
+            summary.ignored.push("cwd");
+        }
+        let justification = local_shell
+            .escalate()
+            .map(|escalate| escalate.justification.as_str());
+        let requested: Vec<&'static str> = [
+            behavior.is_some().then_some("timeout_ms"),
+            workdir.is_some().then_some("cwd"),
+            justification.is_some().then_some("escalate"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
 
-        match (&mut call.payload, call.tool_name.as_str()) {
-            (ToolPayload::LocalShell { params }, _) => {
-                Self::apply_timeout_behavior(&mut params.timeout_ms, behavior);
-                debug!("tool_hook_timeout_override" = "local_shell", command = ?params.command, timeout = ?params.timeout_ms);
-            }
-            (ToolPayload::Function { arguments }, "shell_command") => {
-                match serde_json::from_str::<Value>(arguments) {
-                    Ok(mut params) => {
-                        if let Some(obj) = params.as_object_mut() {
-                            let value = match behavior {
-                                TimeoutBehavior::Millis(ms) => Value::from(ms),
-                                TimeoutBehavior::Infinite => Value::from(0u64),
-                            };
-                            obj.insert("timeout_ms".to_string(), value);
-                            match serde_json::to_string(&params) {
-                                Ok(updated) => {
-                                    *arguments = updated;
-                                    debug!("tool_hook_timeout_override" = "shell_command", timeout_behavior = ?behavior);
-                                }
-                                Err(err) => {
-                                    warn!("shell_command_hook_serialize_error" = %err,
-                                        "failed to serialize shell_command arguments after applying timeout override");
-                                }
-                            }
-                        } else {
-                            warn!(
-                                "shell_command_hook_parse_error: shell_command arguments were not an object"
-                            );
-                        }
+        if !requested.is_empty() {
+            let applied = match (&mut call.payload, call.tool_name.as_str()) {
+                (ToolPayload::LocalShell { params }, _) => {
+                    if let Some(behavior) = behavior {
+                        Self::apply_timeout_behavior(&mut params.timeout_ms, behavior);
                     }
-                    Err(err) => {
-                        warn!("shell_command_hook_parse_error" = %err,
-                            "failed to parse shell_command arguments for timeout override");
+                    if let Some(workdir) = &workdir {
+                        params.workdir = Some(workdir.to_string_lossy().into_owned());
                     }
+                    if let Some(justification) = justification {
+                        params.sandbox_permissions = Some(SandboxPermissions::RequireEscalated);
+                        params.justification = Some(justification.to_string());
+                    }
+                    true
+                }
+                (ToolPayload::Function { arguments }, "shell_command") => {
+                    Self::apply_shell_command_overrides(
+                        arguments,
+                        behavior,
+                        workdir.as_deref(),
+                        justification,
+                    )
+                }
+                _ => false,
+            };
+            if applied {
+                summary.applied.extend(requested);
+            } else {
+                summary.ignored.extend(requested);
+            }
+        }
+
+        if !summary.applied.is_empty() || !summary.ignored.is_empty() {
+            debug!(
+                call_id = %call.call_id,
+                tool = %call.tool_name,
+                applied = ?summary.applied,
+                ignored = ?summary.ignored,
+                "applied tool hook directive"
+            );
+        }
+        summary
+    }
+
+    /// Rewrite `shell_command` JSON arguments with the hook's timeout,
+    /// workdir, and escalation. Returns `false`, leaving `arguments`
+    /// untouched, when they are not a JSON object.
+    fn apply_shell_command_overrides(
+        arguments: &mut String,
+        behavior: Option<TimeoutBehavior>,
+        workdir: Option<&Path>,
+        justification: Option<&str>,
+    ) -> bool {
+        let mut params = match serde_json::from_str::<Value>(arguments) {
+            Ok(params) => params,
+            Err(err) => {
+                warn!("shell_command_hook_parse_error" = %err,
+                    "failed to parse shell_command arguments for hook overrides");
+                return false;
+            }
+        };
+        let Some(obj) = params.as_object_mut() else {
+            warn!("shell_command_hook_parse_error: shell_command arguments were not an object");
+            return false;
+        };
+        match behavior {
+            Some(TimeoutBehavior::Millis(ms)) => {
+                obj.insert("timeout_ms".to_string(), Value::from(ms));
+            }
+            Some(TimeoutBehavior::Infinite) => {
+                obj.insert("timeout_ms".to_string(), Value::from(0u64));
+            }
+            Some(TimeoutBehavior::Default) => {
+                obj.remove("timeout_ms");
+            }
+            None => {}
+        }
+        if let Some(workdir) = workdir {
+            obj.insert(
+                "workdir".to_string(),
+                Value::from(workdir.to_string_lossy().into_owned()),
+            );
+        }
+        if let Some(justification) = justification {
+            obj.insert(
+                "sandbox_permissions".to_string(),
+                Value::from("require_escalated"),
+            );
+            obj.insert("justification".to_string(), Value::from(justification));
+        }
+        match serde_json::to_string(&params) {
+            Ok(updated) => {
+                *arguments = updated;
+                true
+            }
+            Err(err) => {
+                warn!("shell_command_hook_serialize_error" = %err,
+                    "failed to serialize shell_command arguments after applying hook overrides");
+                false
+            }
+        }
+    }
+
+    /// Resolve a hook-supplied `cwd` against `workspace_root` (see
+    /// [`resolve_hook_path`]), warning when it points outside of it.
+    fn resolve_hook_workdir(cwd: &Path, workspace_root: &Path) -> Option<PathBuf> {
+        let resolved = resolve_hook_path(cwd, workspace_root);
+        if resolved.is_none() {
+            warn!(
+                cwd = %cwd.display(),
+                workspace_root = %workspace_root.display(),
+                "ignoring tool hook cwd outside of the workspace root"
+            );
+        }
+        resolved
+    }
+
+    /// Fill in the configured default `timeout_ms` for the call's tool when
+    /// the call has none. Only shell-style payloads and function arguments
+    /// that are JSON objects carry a timeout; other payloads are left alone.
+    fn apply_default_timeout(&self, call: &mut ToolCall) {
+        let Some(&default_ms) = self.default_timeouts_ms.get(&call.tool_name) else {
+            return;
+        };
+        match &mut call.payload {
+            ToolPayload::LocalShell { params } => {
+                params.timeout_ms.get_or_insert(default_ms);
+            }
+            ToolPayload::Function { arguments } => {
+                let Ok(Value::Object(mut params)) = serde_json::from_str::<Value>(arguments) else {
+                    return;
+                };
+                if params
+                    .get("timeout_ms")
+                    .is_some_and(|value| !value.is_null())
+                {
+                    return;
+                }
+                params.insert("timeout_ms".to_string(), Value::from(default_ms));
+                match serde_json::to_string(&params) {
+                    Ok(updated) => *arguments = updated,
+                    Err(err) => warn!(
+                        "default_timeout_serialize_error" = %err,
+                        tool = %call.tool_name,
+                        "failed to apply default timeout"
+                    ),
                 }
             }
             _ => {}
@@ -288,6 +840,1173 @@ impl ToolRouter {
         match behavior {
             TimeoutBehavior::Millis(ms) => *target = Some(ms),
             TimeoutBehavior::Infinite => *target = Some(0),
+            TimeoutBehavior::Default => *target = None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_common::tools::ResponsesApiTool;
+    use crate::codex::make_session_and_context_with_rx;
+    use crate::config::types::ToolHookStage;
+    use crate::protocol::AskForApproval;
+    use crate::tools::context::ToolOutput;
+    use crate::tools::handlers::ShellHandler;
+    use crate::tools::parallel::ToolCallRuntime;
+    use crate::tools::registry::ToolHandler;
+    use crate::tools::registry::ToolKind;
+    use crate::tools::spec::JsonSchema;
+    use crate::turn_diff_tracker::TurnDiffTracker;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use codex_protocol::models::ShellToolCallParams;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use tracing_test::traced_test;
+
+    #[test]
+    fn specs_for_keeps_only_requested_tools_in_order() {
+        let function = |name: &str| {
+            ToolSpec::Function(crate::client_common::tools::ResponsesApiTool {
+                name: name.to_string(),
+                description: String::new(),
+                strict: false,
+                parameters: crate::tools::spec::JsonSchema::Object {
+                    properties: Default::default(),
+                    required: None,
+                    additional_properties: None,
+                },
+            })
+        };
+        let specs = [
+            function("read_file"),
+            ToolSpec::LocalShell {},
+            function("list_dir"),
+            ToolSpec::WebSearch {},
+        ]
+        .into_iter()
+        .map(|spec| ConfiguredToolSpec::new(spec, false))
+        .collect();
+        let router = ToolRouter::with_registry(ToolRegistry::new(HashMap::new()), specs);
+        let names = HashSet::from([
+            "web_search".to_string(),
+            "read_file".to_string(),
+            "unknown_tool".to_string(),
+        ]);
+
+        assert_eq!(
+            router.specs_for(&names),
+            vec![function("read_file"), ToolSpec::WebSearch {}]
+        );
+    }
+
+    #[test]
+    fn hook_defer_delay_is_clamped_to_the_maximum() {
+        assert_eq!(
+            [
+                ToolRouter::hook_defer_delay(500, "call-1"),
+                ToolRouter::hook_defer_delay(u64::MAX, "call-1"),
+            ],
+            [Duration::from_millis(500), MAX_HOOK_DEFER]
+        );
+    }
+
+    #[test]
+    fn configured_default_timeout_applies_only_to_its_tool() {
+        let router = ToolRouter::with_registry(ToolRegistry::new(HashMap::new()), Vec::new())
+            .with_default_timeouts(HashMap::from([("shell_command".to_string(), 30_000)]));
+        let function_call = |tool_name: &str, arguments: &str| ToolCall {
+            tool_name: tool_name.to_string(),
+            call_id: "call-1".to_string(),
+            payload: ToolPayload::Function {
+                arguments: arguments.to_string(),
+            },
+        };
+        let mut defaulted = function_call("shell_command", r#"{"command":"ls"}"#);
+        let mut explicit = function_call("shell_command", r#"{"command":"ls","timeout_ms":5}"#);
+        let mut other = function_call("shell", r#"{"command":["ls"]}"#);
+
+        for call in [&mut defaulted, &mut explicit, &mut other] {
+            router.apply_default_timeout(call);
+        }
+
+        let arguments = |call: &ToolCall| match &call.payload {
+            ToolPayload::Function { arguments } => {
+                serde_json::from_str::<Value>(arguments).expect("valid json")
+            }
+            other => panic!("unexpected payload: {other:?}"),
+        };
+        assert_eq!(
+            [
+                arguments(&defaulted),
+                arguments(&explicit),
+                arguments(&other)
+            ],
+            [
+                serde_json::json!({ "command": "ls", "timeout_ms": 30_000 }),
+                serde_json::json!({ "command": "ls", "timeout_ms": 5 }),
+                serde_json::json!({ "command": ["ls"] }),
+            ]
+        );
+    }
+
+    fn default_timeout_directive() -> ToolHookDirective {
+        serde_json::from_str(r#"{"local_shell":{"timeout_ms":"default"}}"#)
+            .expect("valid directive")
+    }
+
+    #[test]
+    fn default_keyword_resets_local_shell_timeout() {
+        let params = ShellToolCallParams {
+            command: vec!["sleep".to_string(), "5".to_string()],
+            workdir: None,
+            timeout_ms: Some(5_000),
+            sandbox_permissions: None,
+            justification: None,
+        };
+        let mut call = ToolCall {
+            tool_name: "local_shell".to_string(),
+            call_id: "call-1".to_string(),
+            payload: ToolPayload::LocalShell {
+                params: params.clone(),
+            },
+        };
+
+        ToolRouter::apply_tool_hook_directive(
+            &mut call,
+            default_timeout_directive(),
+            Path::new("/workspace"),
+        );
+
+        let ToolPayload::LocalShell { params: updated } = call.payload else {
+            panic!("payload kind changed");
+        };
+        assert_eq!(
+            updated,
+            ShellToolCallParams {
+                timeout_ms: None,
+                ..params
+            }
+        );
+    }
+
+    #[test]
+    fn default_keyword_falls_back_to_configured_default_timeout() {
+        let router = ToolRouter::with_registry(ToolRegistry::new(HashMap::new()), Vec::new())
+            .with_default_timeouts(HashMap::from([("shell_command".to_string(), 30_000)]));
+        let mut call = ToolCall {
+            tool_name: "shell_command".to_string(),
+            call_id: "call-1".to_string(),
+            payload: ToolPayload::Function {
+                arguments: r#"{"command":"sleep 5","timeout_ms":5000}"#.to_string(),
+            },
+        };
+
+        router.apply_directive_and_default_timeout(
+            &mut call,
+            Some(default_timeout_directive()),
+            Path::new("/workspace"),
+        );
+
+        let ToolPayload::Function { arguments } = call.payload else {
+            panic!("payload kind changed");
+        };
+        let arguments: Value = serde_json::from_str(&arguments).expect("valid json");
+        assert_eq!(
+            arguments,
+            serde_json::json!({ "command": "sleep 5", "timeout_ms": 30_000 })
+        );
+    }
+
+    #[test]
+    fn default_keyword_removes_shell_command_timeout() {
+        let mut call = ToolCall {
+            tool_name: "shell_command".to_string(),
+            call_id: "call-1".to_string(),
+            payload: ToolPayload::Function {
+                arguments: r#"{"command":"sleep 5","timeout_ms":5000}"#.to_string(),
+            },
+        };
+
+        ToolRouter::apply_tool_hook_directive(
+            &mut call,
+            default_timeout_directive(),
+            Path::new("/workspace"),
+        );
+
+        let ToolPayload::Function { arguments } = call.payload else {
+            panic!("payload kind changed");
+        };
+        let arguments: Value = serde_json::from_str(&arguments).expect("valid json");
+        assert_eq!(arguments, serde_json::json!({ "command": "sleep 5" }));
+    }
+
+    #[test]
+    #[traced_test]
+    fn timeout_directive_for_non_shell_function_is_logged_as_ignored() {
+        let directive: ToolHookDirective =
+            serde_json::from_str(r#"{"local_shell":{"timeout_ms":"infinite"}}"#)
+                .expect("valid directive");
+        let arguments = r#"{"city":"Paris"}"#.to_string();
+        let mut call = ToolCall {
+            tool_name: "lookup_weather".to_string(),
+            call_id: "call-weather".to_string(),
+            payload: ToolPayload::Function {
+                arguments: arguments.clone(),
+            },
+        };
+
+        let summary =
+            ToolRouter::apply_tool_hook_directive(&mut call, directive, Path::new("/workspace"));
+
+        assert_eq!(
+            summary,
+            DirectiveApplication {
+                applied: Vec::new(),
+                ignored: vec!["timeout_ms"],
+            }
+        );
+        let ToolPayload::Function { arguments: updated } = call.payload else {
+            panic!("payload kind changed");
+        };
+        assert_eq!(updated, arguments);
+        assert!(logs_contain("call_id=call-weather"));
+        assert!(logs_contain(r#"ignored=["timeout_ms"]"#));
+    }
+
+    #[tokio::test]
+    async fn escalate_directive_sets_permissions_and_justification() {
+        let (_session, turn) = crate::codex::make_session_and_context().await;
+        let directive: ToolHookDirective = serde_json::from_str(
+            r#"{"local_shell":{"escalate":{"justification":"needs network to fetch deps"}}}"#,
+        )
+        .expect("valid directive");
+        let mut call = ToolCall {
+            tool_name: "local_shell".to_string(),
+            call_id: "call-1".to_string(),
+            payload: ToolPayload::LocalShell {
+                params: ShellToolCallParams {
+                    command: vec!["cargo".to_string(), "fetch".to_string()],
+                    workdir: None,
+                    timeout_ms: None,
+                    sandbox_permissions: None,
+                    justification: None,
+                },
+            },
+        };
+
+        let summary = ToolRouter::apply_tool_hook_directive(&mut call, directive, &turn.cwd);
+
+        let ToolPayload::LocalShell { params } = call.payload else {
+            panic!("payload kind changed");
+        };
+        let exec_params = ShellHandler::to_exec_params(params, &turn);
+        assert_eq!(
+            (
+                summary,
+                exec_params.sandbox_permissions,
+                exec_params.justification
+            ),
+            (
+                DirectiveApplication {
+                    applied: vec!["escalate"],
+                    ignored: Vec::new(),
+                },
+                SandboxPermissions::RequireEscalated,
+                Some("needs network to fetch deps".to_string()),
+            )
+        );
+    }
+
+    #[test]
+    fn escalate_directive_rewrites_shell_command_arguments() {
+        let directive: ToolHookDirective =
+            serde_json::from_str(r#"{"local_shell":{"escalate":{"justification":"push tags"}}}"#)
+                .expect("valid directive");
+        let mut call = ToolCall {
+            tool_name: "shell_command".to_string(),
+            call_id: "call-1".to_string(),
+            payload: ToolPayload::Function {
+                arguments: r#"{"command":"git push --tags"}"#.to_string(),
+            },
+        };
+
+        ToolRouter::apply_tool_hook_directive(&mut call, directive, Path::new("/workspace"));
+
+        let ToolPayload::Function { arguments } = call.payload else {
+            panic!("payload kind changed");
+        };
+        let arguments: Value = serde_json::from_str(&arguments).expect("valid json");
+        assert_eq!(
+            arguments,
+            serde_json::json!({
+                "command": "git push --tags",
+                "sandbox_permissions": "require_escalated",
+                "justification": "push tags",
+            })
+        );
+    }
+
+    #[test]
+    fn hook_cwd_must_stay_inside_workspace_root() {
+        let root = Path::new("/workspace/repo");
+        let resolved = [
+            "worktrees/feature",
+            "/workspace/repo/sub/../other",
+            "../elsewhere",
+            "/tmp",
+        ]
+        .map(|cwd| ToolRouter::resolve_hook_workdir(Path::new(cwd), root));
+
+        assert_eq!(
+            resolved,
+            [
+                Some(PathBuf::from("/workspace/repo/worktrees/feature")),
+                Some(PathBuf::from("/workspace/repo/other")),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hook_cwd_symlink_cannot_escape_workspace_root() {
+        let workspace = tempfile::tempdir().expect("tempdir");
+        let outside = tempfile::tempdir().expect("tempdir");
+        let root = workspace.path().canonicalize().expect("canonical root");
+        std::fs::create_dir(root.join("inside")).expect("create dir");
+        std::os::unix::fs::symlink(outside.path(), root.join("escape")).expect("symlink");
+
+        let resolved = ["inside/new", "escape", "escape/nested"]
+            .map(|cwd| ToolRouter::resolve_hook_workdir(Path::new(cwd), &root));
+
+        assert_eq!(resolved, [Some(root.join("inside/new")), None, None]);
+    }
+
+    #[test]
+    fn hook_cwd_sets_shell_command_workdir() {
+        let directive: ToolHookDirective =
+            serde_json::from_str(r#"{"local_shell":{"cwd":"worktree"}}"#).expect("valid directive");
+        let mut call = ToolCall {
+            tool_name: "shell_command".to_string(),
+            call_id: "call-1".to_string(),
+            payload: ToolPayload::Function {
+                arguments: r#"{"command":"pwd","timeout_ms":5000}"#.to_string(),
+            },
+        };
+
+        ToolRouter::apply_tool_hook_directive(&mut call, directive, Path::new("/workspace"));
+
+        let ToolPayload::Function { arguments } = call.payload else {
+            panic!("payload kind changed");
+        };
+        let arguments: Value = serde_json::from_str(&arguments).expect("valid json");
+        assert_eq!(
+            arguments,
+            serde_json::json!({
+                "command": "pwd",
+                "timeout_ms": 5000,
+                "workdir": "/workspace/worktree",
+            })
+        );
+    }
+
+    /// Answers every call with `stub handled <call_id>`.
+    struct StubToolHandler;
+
+    #[async_trait::async_trait]
+    impl ToolHandler for StubToolHandler {
+        fn kind(&self) -> ToolKind {
+            ToolKind::Function
+        }
+
+        async fn handle(
+            &self,
+            invocation: ToolInvocation,
+        ) -> Result<ToolOutput, FunctionCallError> {
+            Ok(ToolOutput::Function {
+                content: format!("stub handled {}", invocation.call_id),
+                content_items: None,
+                success: Some(true),
+            })
+        }
+    }
+
+    /// Signals `started` and then never finishes.
+    #[cfg(unix)]
+    struct HangingToolHandler {
+        started: Arc<tokio::sync::Notify>,
+    }
+
+    #[cfg(unix)]
+    #[async_trait::async_trait]
+    impl ToolHandler for HangingToolHandler {
+        fn kind(&self) -> ToolKind {
+            ToolKind::Function
+        }
+
+        async fn handle(
+            &self,
+            _invocation: ToolInvocation,
+        ) -> Result<ToolOutput, FunctionCallError> {
+            self.started.notify_one();
+            std::future::pending().await
+        }
+    }
+
+    fn stub_call(tool_name: &str, call_id: &str) -> ToolCall {
+        ToolCall {
+            tool_name: tool_name.to_string(),
+            call_id: call_id.to_string(),
+            payload: ToolPayload::Function {
+                arguments: "{}".to_string(),
+            },
+        }
+    }
+
+    #[cfg(unix)]
+    fn function_output(call_id: &str, content: &str, success: bool) -> ResponseInputItem {
+        ResponseInputItem::FunctionCallOutput {
+            call_id: call_id.to_string(),
+            output: FunctionCallOutputPayload {
+                content: content.to_string(),
+                content_items: None,
+                success: Some(success),
+            },
+        }
+    }
+
+    fn stub_router(tool_names: &[&str]) -> ToolRouter {
+        let handler: Arc<dyn ToolHandler> = Arc::new(StubToolHandler);
+        let registry = ToolRegistry::new(
+            tool_names
+                .iter()
+                .map(|name| (name.to_string(), Arc::clone(&handler)))
+                .collect(),
+        );
+        ToolRouter::with_registry(registry, Vec::new())
+    }
+
+    /// Script for [`dispatch_with_hook`] that appends each event it receives
+    /// to the log passed as `$0`, one JSON object per line.
+    #[cfg(unix)]
+    const LOG_HOOK_EVENTS: &str = r#"cat >> "$0"; echo >> "$0""#;
+
+    /// Tool hook that runs `script` under `sh -c` with `args` bound to `$0`,
+    /// `$1`, and so on.
+    #[cfg(unix)]
+    fn sh_tool_hook(script: &str, args: &[&Path]) -> Option<ToolHook> {
+        let mut command = vec!["sh".to_string(), "-c".to_string(), script.to_string()];
+        command.extend(args.iter().map(|arg| arg.to_string_lossy().into_owned()));
+        ToolHook::new(command)
+    }
+
+    #[cfg(unix)]
+    fn parse_hook_events(log: &str) -> Vec<Value> {
+        log.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).expect("hook event"))
+            .collect()
+    }
+
+    /// Each event's phase paired with the value at `pointer`.
+    #[cfg(unix)]
+    fn phases_with(events: &[Value], pointer: &str) -> Vec<(String, Value)> {
+        events
+            .iter()
+            .map(|event| {
+                (
+                    event["phase"].as_str().unwrap_or_default().to_string(),
+                    event.pointer(pointer).cloned().unwrap_or(Value::Null),
+                )
+            })
+            .collect()
+    }
+
+    /// Dispatch `calls` one after another to [`StubToolHandler`] while a tool
+    /// hook runs `hook_script` with its log file as `$0`. `configure` adjusts
+    /// the turn first. Like `run_turn`, every call gets a fresh router.
+    /// Returns the responses and the events logged by the hook.
+    #[cfg(unix)]
+    async fn dispatch_with_hook(
+        calls: Vec<ToolCall>,
+        hook_script: &str,
+        configure: impl FnOnce(&mut TurnContext),
+    ) -> (Vec<ResponseInputItem>, Vec<Value>) {
+        let (session, mut turn, _rx) = make_session_and_context_with_rx().await;
+        let hook_dir = tempfile::tempdir().expect("tempdir");
+        let log_path = hook_dir.path().join("hook.jsonl");
+        {
+            let turn = Arc::get_mut(&mut turn).expect("turn context is not shared");
+            turn.tool_hook = sh_tool_hook(hook_script, &[&log_path]);
+            configure(turn);
+        }
+
+        let mut responses = Vec::new();
+        for call in calls {
+            let router = stub_router(&[&call.tool_name]).with_dry_run(turn.tool_dry_run);
+            let tracker = Arc::new(tokio::sync::Mutex::new(TurnDiffTracker::new()));
+            let response = router
+                .dispatch_tool_call(Arc::clone(&session), Arc::clone(&turn), tracker, call)
+                .await
+                .expect("dispatch");
+            responses.push(response);
+        }
+        let log = std::fs::read_to_string(&log_path).unwrap_or_default();
+        (responses, parse_hook_events(&log))
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn injected_registry_dispatches_to_stub_tool_with_hooks() {
+        let (responses, events) = dispatch_with_hook(
+            vec![stub_call("stub_tool", "call-stub")],
+            LOG_HOOK_EVENTS,
+            |_| {},
+        )
+        .await;
+
+        let expected = function_output("call-stub", "stub handled call-stub", true);
+        assert_eq!(responses, vec![expected.clone()]);
+        assert_eq!(
+            phases_with(&events, "/outcome"),
+            vec![
+                ("before_execution".to_string(), Value::Null),
+                (
+                    "after_execution".to_string(),
+                    json!({ "success": { "response": expected } }),
+                ),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn once_per_turn_tool_hook_runs_before_hook_once() {
+        let calls = vec![
+            stub_call("stub_tool", "call-1"),
+            stub_call("stub_tool", "call-2"),
+        ];
+        let (_, events) = dispatch_with_hook(calls, LOG_HOOK_EVENTS, |turn| {
+            turn.tool_hook = turn
+                .tool_hook
+                .take()
+                .map(|hook| hook.with_mode(ToolHookMode::OncePerTurn));
+        })
+        .await;
+
+        assert_eq!(
+            phases_with(&events, "/call/call_id"),
+            vec![
+                ("before_execution".to_string(), json!("call-1")),
+                ("after_execution".to_string(), json!("call-1")),
+                ("after_execution".to_string(), json!("call-2")),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn tool_hook_can_defer_a_call_once() {
+        // Defers the first before-hook invocation, then lets the retry through.
+        let script = r#"cat >> "$0"; echo >> "$0"; if [ ! -e "$0.deferred" ]; then touch "$0.deferred"; echo '{"defer_ms": 10}'; fi"#;
+        let (responses, events) =
+            dispatch_with_hook(vec![stub_call("stub_tool", "call-1")], script, |_| {}).await;
+
+        assert_eq!(
+            responses,
+            vec![function_output("call-1", "stub handled call-1", true)]
+        );
+        assert_eq!(
+            phases_with(&events, "/call/call_id"),
+            vec![
+                ("before_execution".to_string(), json!("call-1")),
+                ("before_execution".to_string(), json!("call-1")),
+                ("after_execution".to_string(), json!("call-1")),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn blocked_tool_call_emits_paired_hook_events() {
+        let script = r#"cat >> "$0"; echo >> "$0"; echo '{"block":"writes are frozen"}'"#;
+        let (responses, events) =
+            dispatch_with_hook(vec![stub_call("stub_tool", "call-blocked")], script, |_| {}).await;
+
+        assert_eq!(
+            responses,
+            vec![function_output(
+                "call-blocked",
+                "Tool call blocked by hook: writes are frozen",
+                false
+            )]
+        );
+        assert_eq!(
+            phases_with(&events, "/outcome"),
+            vec![
+                ("before_execution".to_string(), Value::Null),
+                (
+                    "after_execution".to_string(),
+                    json!({ "blocked": { "reason": "writes are frozen" } }),
+                ),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn hook_events_report_the_turn_sandbox_mode() {
+        let (_, events) = dispatch_with_hook(
+            vec![stub_call("stub_tool", "call-sandbox")],
+            LOG_HOOK_EVENTS,
+            |turn| turn.sandbox_policy = SandboxPolicy::DangerFullAccess,
+        )
+        .await;
+
+        assert_eq!(
+            phases_with(&events, "/sandbox"),
+            vec![
+                ("before_execution".to_string(), json!("danger-full-access")),
+                ("after_execution".to_string(), json!("danger-full-access")),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn before_only_hook_emits_no_after_execution_event() {
+        let (_, events) = dispatch_with_hook(
+            vec![stub_call("stub_tool", "call-before-only")],
+            LOG_HOOK_EVENTS,
+            |turn| {
+                turn.tool_hook = turn
+                    .tool_hook
+                    .take()
+                    .map(|hook| hook.with_phases(&[ToolHookStage::Before]));
+            },
+        )
+        .await;
+
+        assert_eq!(
+            phases_with(&events, "/call/call_id"),
+            vec![("before_execution".to_string(), json!("call-before-only"))]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn dry_run_router_flags_hook_events_and_skips_execution() {
+        let (responses, events) = dispatch_with_hook(
+            vec![stub_call("stub_tool", "call-preview")],
+            LOG_HOOK_EVENTS,
+            |turn| turn.tool_dry_run = true,
+        )
+        .await;
+
+        assert_eq!(
+            responses,
+            vec![function_output(
+                "call-preview",
+                "Dry run: `stub_tool` was not executed.",
+                true
+            )]
+        );
+        assert_eq!(
+            phases_with(&events, "/dry_run"),
+            vec![
+                ("before_execution".to_string(), json!(true)),
+                ("after_execution".to_string(), json!(true)),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn after_hook_event_reports_tool_duration() {
+        let (_, events) = dispatch_with_hook(
+            vec![stub_call("stub_tool", "call-timed")],
+            LOG_HOOK_EVENTS,
+            |_| {},
+        )
+        .await;
+
+        let has_duration: Vec<(String, bool)> = phases_with(&events, "/tool_duration_ms")
+            .into_iter()
+            // `as_u64` only accepts non-negative integers.
+            .map(|(phase, duration)| (phase, duration.as_u64().is_some()))
+            .collect();
+        assert_eq!(
+            has_duration,
+            vec![
+                ("before_execution".to_string(), false),
+                ("after_execution".to_string(), true),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn tool_hook_filter_skips_non_matching_tools() {
+        let calls = vec![
+            stub_call("stub_tool", "call-skipped"),
+            stub_call("mcp__github__list_issues", "call-hooked"),
+        ];
+        let (_, events) = dispatch_with_hook(calls, LOG_HOOK_EVENTS, |turn| {
+            turn.tool_hook = turn
+                .tool_hook
+                .take()
+                .map(|hook| hook.with_tool_filter(&["mcp__github__*".to_string()], &[]));
+        })
+        .await;
+
+        assert_eq!(
+            phases_with(&events, "/call/call_id"),
+            vec![
+                ("before_execution".to_string(), json!("call-hooked")),
+                ("after_execution".to_string(), json!("call-hooked")),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn shell_calls_over_rate_limit_are_rejected() {
+        let calls = vec![
+            stub_call("shell_command", "call-1"),
+            stub_call("stub_tool", "call-2"),
+            stub_call("shell_command", "call-3"),
+            stub_call("shell_command", "call-4"),
+        ];
+        let (responses, events) = dispatch_with_hook(calls, LOG_HOOK_EVENTS, |turn| {
+            turn.shell_calls_per_minute = Some(2);
+        })
+        .await;
+
+        let message = rate_limit::rate_limit_message(2);
+        assert_eq!(
+            responses,
+            vec![
+                function_output("call-1", "stub handled call-1", true),
+                function_output("call-2", "stub handled call-2", true),
+                function_output("call-3", "stub handled call-3", true),
+                function_output("call-4", &message, false),
+            ]
+        );
+        let rejected: Vec<Value> = events
+            .iter()
+            .filter(|event| event["call"]["call_id"] == "call-4")
+            .map(|event| event["outcome"].clone())
+            .collect();
+        assert_eq!(
+            rejected,
+            vec![Value::Null, json!({ "blocked": { "reason": message } })]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn blocked_shell_calls_do_not_use_rate_limit_quota() {
+        // Blocks the first call it sees and lets every later one through.
+        let script = r#"cat > /dev/null; if [ ! -e "$0.blocked" ]; then touch "$0.blocked"; echo '{"block":"not yet"}'; fi"#;
+        let calls = vec![
+            stub_call("shell_command", "call-blocked"),
+            stub_call("shell_command", "call-allowed"),
+        ];
+        let (responses, _) = dispatch_with_hook(calls, script, |turn| {
+            turn.shell_calls_per_minute = Some(1);
+        })
+        .await;
+        assert_eq!(
+            responses,
+            vec![
+                function_output("call-blocked", "Tool call blocked by hook: not yet", false),
+                function_output("call-allowed", "stub handled call-allowed", true),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn dry_run_shell_calls_do_not_use_rate_limit_quota() {
+        let calls = vec![
+            stub_call("shell_command", "call-1"),
+            stub_call("shell_command", "call-2"),
+        ];
+        let (responses, _) = dispatch_with_hook(calls, LOG_HOOK_EVENTS, |turn| {
+            turn.shell_calls_per_minute = Some(1);
+            turn.tool_dry_run = true;
+        })
+        .await;
+        let dry_run = "Dry run: `shell_command` was not executed.";
+        assert_eq!(
+            responses,
+            vec![
+                function_output("call-1", dry_run, true),
+                function_output("call-2", dry_run, true),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn dispatch_observer_reports_success_and_failure() {
+        let (session, turn_context, _rx) = make_session_and_context_with_rx().await;
+        let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let observed = Arc::clone(&reports);
+        let router = stub_router(&["stub_tool"]).with_dispatch_observer(Arc::new(
+            move |report: ToolDispatchReport| {
+                observed
+                    .lock()
+                    .unwrap()
+                    .push((report.call.call_id().to_string(), report.success));
+            },
+        ));
+
+        for call in [
+            stub_call("stub_tool", "call-ok"),
+            stub_call("no_such_tool", "call-err"),
+        ] {
+            let tracker = Arc::new(tokio::sync::Mutex::new(TurnDiffTracker::new()));
+            router
+                .dispatch_tool_call(
+                    Arc::clone(&session),
+                    Arc::clone(&turn_context),
+                    tracker,
+                    call,
+                )
+                .await
+                .expect("dispatch");
+        }
+
+        assert_eq!(
+            *reports.lock().unwrap(),
+            vec![
+                ("call-ok".to_string(), true),
+                ("call-err".to_string(), false),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn tool_hook_can_redirect_local_shell_cwd() {
+        let (session, mut turn_context, _rx) = make_session_and_context_with_rx().await;
+        let workspace = tempfile::tempdir().expect("tempdir");
+        let worktree = workspace.path().join("worktree");
+        std::fs::create_dir(&worktree).expect("create worktree");
+        {
+            let turn = Arc::get_mut(&mut turn_context).expect("turn context is not shared");
+            turn.cwd = workspace.path().to_path_buf();
+            turn.approval_policy = AskForApproval::Never;
+            // Avoid platform sandbox dependencies in tests.
+            turn.sandbox_policy = SandboxPolicy::DangerFullAccess;
+            turn.tool_hook = sh_tool_hook(
+                r#"cat > /dev/null; echo '{"local_shell":{"cwd":"worktree"}}'"#,
+                &[],
+            );
+        }
+        let router = ToolRouter::from_config(&turn_context.tools_config, None);
+        let tracker = Arc::new(tokio::sync::Mutex::new(TurnDiffTracker::new()));
+        let call = ToolCall {
+            tool_name: "local_shell".to_string(),
+            call_id: "call-1".to_string(),
+            payload: ToolPayload::LocalShell {
+                params: ShellToolCallParams {
+                    command: vec!["pwd".to_string()],
+                    workdir: None,
+                    timeout_ms: None,
+                    sandbox_permissions: None,
+                    justification: None,
+                },
+            },
+        };
+
+        let response = router
+            .dispatch_tool_call(
+                Arc::clone(&session),
+                Arc::clone(&turn_context),
+                tracker,
+                call,
+            )
+            .await
+            .expect("dispatch");
+
+        let ResponseInputItem::FunctionCallOutput { output, .. } = response else {
+            panic!("expected function call output, got {response:?}");
+        };
+        let worktree = worktree.canonicalize().expect("canonical worktree");
+        assert!(
+            output.content.contains(&*worktree.to_string_lossy()),
+            "command did not run in {}: {}",
+            worktree.display(),
+            output.content
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn cancelling_token_mid_dispatch_returns_cancelled_output() {
+        let (session, mut turn_context, _rx) = make_session_and_context_with_rx().await;
+        let hook_dir = tempfile::tempdir().expect("tempdir");
+        let log_path = hook_dir.path().join("hook.jsonl");
+        Arc::get_mut(&mut turn_context)
+            .expect("turn context is not shared")
+            .tool_hook = sh_tool_hook(LOG_HOOK_EVENTS, &[&log_path]);
+        let started = Arc::new(tokio::sync::Notify::new());
+        let handler: Arc<dyn ToolHandler> = Arc::new(HangingToolHandler {
+            started: Arc::clone(&started),
+        });
+        let registry = ToolRegistry::new(HashMap::from([("hang_tool".to_string(), handler)]));
+        let router = ToolRouter::with_registry(registry, Vec::new());
+        let tracker = Arc::new(tokio::sync::Mutex::new(TurnDiffTracker::new()));
+        let token = CancellationToken::new();
+
+        let dispatch = router.dispatch_tool_call_cancellable(
+            Arc::clone(&session),
+            Arc::clone(&turn_context),
+            tracker,
+            stub_call("hang_tool", "call-hang"),
+            false,
+            &token,
+        );
+        let cancel = async {
+            started.notified().await;
+            token.cancel();
+        };
+        let (response, ()) = tokio::time::timeout(Duration::from_secs(10), async {
+            tokio::join!(dispatch, cancel)
+        })
+        .await
+        .expect("cancellation unblocks the dispatch");
+
+        // The elapsed time varies from run to run, so only the shape is checked.
+        let is_aborted_message =
+            |message: &str| message.starts_with("aborted by user after ") && message.ends_with('s');
+        let ResponseInputItem::FunctionCallOutput { call_id, output } = response.expect("dispatch")
+        else {
+            panic!("expected a function call output");
+        };
+        assert_eq!(call_id, "call-hang");
+        assert!(
+            is_aborted_message(&output.content),
+            "unexpected output: {}",
+            output.content
+        );
+        // The cancelled after-hook is emitted in the background.
+        let log = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let log = std::fs::read_to_string(&log_path).unwrap_or_default();
+                if log.matches('\n').count() >= 2 {
+                    break log;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("after-hook ran");
+        let cancelled: Vec<(String, bool)> =
+            phases_with(&parse_hook_events(&log), "/outcome/cancelled/message")
+                .into_iter()
+                .map(|(phase, message)| (phase, message.as_str().is_some_and(is_aborted_message)))
+                .collect();
+        assert_eq!(
+            cancelled,
+            vec![
+                ("before_execution".to_string(), false),
+                ("after_execution".to_string(), true),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn cancelling_token_during_hook_deferral_aborts_the_call() {
+        let (session, mut turn_context, _rx) = make_session_and_context_with_rx().await;
+        let hook_dir = tempfile::tempdir().expect("tempdir");
+        let log_path = hook_dir.path().join("hook.jsonl");
+        Arc::get_mut(&mut turn_context)
+            .expect("turn context is not shared")
+            .tool_hook = sh_tool_hook(
+            r#"cat >> "$0"; echo >> "$0"; echo '{"defer_ms":600000}'"#,
+            &[&log_path],
+        );
+        let router = stub_router(&["stub_tool"]);
+        let tracker = Arc::new(tokio::sync::Mutex::new(TurnDiffTracker::new()));
+        let token = CancellationToken::new();
+
+        let dispatch = router.dispatch_tool_call_cancellable(
+            Arc::clone(&session),
+            Arc::clone(&turn_context),
+            tracker,
+            stub_call("stub_tool", "call-deferred"),
+            false,
+            &token,
+        );
+        // Cancel once the before-hook has seen the call and asked to defer it.
+        let cancel = async {
+            while !std::fs::read_to_string(&log_path)
+                .unwrap_or_default()
+                .ends_with('\n')
+            {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            token.cancel();
+        };
+        let (response, ()) = tokio::time::timeout(Duration::from_secs(10), async {
+            tokio::join!(dispatch, cancel)
+        })
+        .await
+        .expect("cancellation interrupts the deferral");
+
+        let ResponseInputItem::FunctionCallOutput { call_id, output } = response.expect("dispatch")
+        else {
+            panic!("expected a function call output");
+        };
+        assert_eq!(call_id, "call-deferred");
+        assert!(
+            output.content.starts_with("aborted by user after "),
+            "unexpected output: {}",
+            output.content
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn parallel_dispatches_bound_concurrent_hook_processes() {
+        let (session, mut turn_context, _rx) = make_session_and_context_with_rx().await;
+        let hook_dir = tempfile::tempdir().expect("tempdir");
+        let running_dir = hook_dir.path().join("running");
+        std::fs::create_dir(&running_dir).expect("create running dir");
+        let counts_path = hook_dir.path().join("counts.txt");
+        // Each hook process marks itself as running, records how many are
+        // running right now, and lingers so overlapping calls would show up.
+        Arc::get_mut(&mut turn_context)
+            .expect("turn context is not shared")
+            .tool_hook = sh_tool_hook(
+            r#"cat > /dev/null; touch "$0/$$"; ls "$0" | wc -l >> "$1"; sleep 0.1; rm "$0/$$""#,
+            &[&running_dir, &counts_path],
+        )
+        .map(|hook| hook.with_max_concurrency(2));
+        let router = stub_router(&["stub_tool"]);
+        let tracker = Arc::new(tokio::sync::Mutex::new(TurnDiffTracker::new()));
+
+        let dispatches = (0..6).map(|index| {
+            router.dispatch_tool_call(
+                Arc::clone(&session),
+                Arc::clone(&turn_context),
+                Arc::clone(&tracker),
+                stub_call("stub_tool", &format!("call-{index}")),
+            )
+        });
+        for response in futures::future::join_all(dispatches).await {
+            response.expect("dispatch");
+        }
+
+        let counts: Vec<usize> = std::fs::read_to_string(&counts_path)
+            .expect("read counts")
+            .lines()
+            .map(|line| line.trim().parse().expect("count"))
+            .collect();
+        // One before and one after event per call.
+        assert_eq!(counts.len(), 12);
+        assert!(
+            counts.iter().all(|count| *count <= 2),
+            "more than two hooks ran at once: {counts:?}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn tool_hook_reports_parallel_batches() {
+        let (session, mut turn_context, _rx) = make_session_and_context_with_rx().await;
+        let hook_dir = tempfile::tempdir().expect("tempdir");
+        // One file per hook process so concurrent hooks never interleave writes.
+        Arc::get_mut(&mut turn_context)
+            .expect("turn context is not shared")
+            .tool_hook = sh_tool_hook(r#"cat > "$0/$$.json""#, &[hook_dir.path()]);
+        let handler: Arc<dyn ToolHandler> = Arc::new(StubToolHandler);
+        let registry = ToolRegistry::new(HashMap::from([
+            ("parallel_tool".to_string(), Arc::clone(&handler)),
+            ("serial_tool".to_string(), handler),
+        ]));
+        let function_spec = |name: &str| {
+            ToolSpec::Function(ResponsesApiTool {
+                name: name.to_string(),
+                description: format!("stub {name}"),
+                strict: false,
+                parameters: JsonSchema::Object {
+                    properties: BTreeMap::new(),
+                    required: None,
+                    additional_properties: None,
+                },
+            })
+        };
+        let router = ToolRouter::with_registry(
+            registry,
+            vec![
+                ConfiguredToolSpec::new(function_spec("parallel_tool"), true),
+                ConfiguredToolSpec::new(function_spec("serial_tool"), false),
+            ],
+        );
+        let runtime = ToolCallRuntime::new(
+            Arc::new(router),
+            Arc::clone(&session),
+            Arc::clone(&turn_context),
+            Arc::new(tokio::sync::Mutex::new(TurnDiffTracker::new())),
+        );
+        let handle = |tool_name: &str, call_id: &str| {
+            runtime
+                .clone()
+                .handle_tool_call(stub_call(tool_name, call_id), CancellationToken::new())
+        };
+
+        let (first, second) = tokio::join!(
+            handle("parallel_tool", "call-a"),
+            handle("parallel_tool", "call-b")
+        );
+        first.expect("first parallel call");
+        second.expect("second parallel call");
+        handle("parallel_tool", "call-alone")
+            .await
+            .expect("lone parallel call");
+        let (serial_first, serial_second) = tokio::join!(
+            handle("serial_tool", "call-serial-1"),
+            handle("serial_tool", "call-serial-2")
+        );
+        serial_first.expect("first serial call");
+        serial_second.expect("second serial call");
+
+        let mut before_events: Vec<(String, bool)> = std::fs::read_dir(hook_dir.path())
+            .expect("read hook dir")
+            .map(|entry| {
+                let contents =
+                    std::fs::read_to_string(entry.expect("dir entry").path()).expect("hook event");
+                serde_json::from_str::<Value>(&contents).expect("hook json")
+            })
+            .filter(|event| event["phase"] == "before_execution")
+            .map(|event| {
+                (
+                    event["call"]["call_id"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    event["parallel"].as_bool().unwrap_or_default(),
+                )
+            })
+            .collect();
+        before_events.sort();
+        assert_eq!(
+            before_events,
+            vec![
+                ("call-a".to_string(), true),
+                ("call-alone".to_string(), false),
+                ("call-b".to_string(), true),
+                ("call-serial-1".to_string(), false),
+                ("call-serial-2".to_string(), false),
+            ]
+        );
+    }
+}
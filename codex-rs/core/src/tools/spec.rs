@@ -1,6 +1,7 @@
 use crate::client_common::tools::ResponsesApiTool;
 use crate::client_common::tools::ToolSpec;
 use crate::config::CustomToolConfig;
+use crate::config::types::EnvironmentVariablePattern;
 use crate::features::Feature;
 use crate::features::Features;
 use crate::models_manager::model_family::ModelFamily;
@@ -39,13 +40,21 @@ pub(crate) struct ConfigCustomTool {
     pub name: String,
     pub description: String,
     pub parameters: JsonSchema,
+    pub required: Vec<String>,
     pub command: Vec<String>,
+    pub enabled: bool,
     pub cwd: Option<String>,
     pub env: HashMap<String, String>,
+    pub env_include: Vec<EnvironmentVariablePattern>,
+    pub env_exclude: Vec<EnvironmentVariablePattern>,
+    pub env_passthrough: Vec<EnvironmentVariablePattern>,
+    pub env_file: Option<String>,
     pub timeout_ms: Option<u64>,
     pub with_escalated_permissions: Option<bool>,
     pub parallel: bool,
     pub hibernate_after_call: bool,
+    pub output_as_file: bool,
+    pub max_output_bytes: Option<usize>,
 }
 
 impl ToolsConfig {
@@ -1001,7 +1010,18 @@ fn build_custom_tool_specs(entries: &BTreeMap<String, CustomToolConfig>) -> Vec<
         let mut schema_value = tool.parameters.clone();
         sanitize_json_schema(&mut schema_value);
         match serde_json::from_value::<JsonSchema>(schema_value) {
-            Ok(parameters) => {
+            Ok(mut parameters) => {
+                // Advertise `required` to the model as part of the schema too.
+                if let JsonSchema::Object { required, .. } = &mut parameters
+                    && !tool.required.is_empty()
+                {
+                    let required = required.get_or_insert_with(Vec::new);
+                    for name in &tool.required {
+                        if !required.contains(name) {
+                            required.push(name.clone());
+                        }
+                    }
+                }
                 let description = tool
                     .description
                     .clone()
@@ -1010,13 +1030,21 @@ fn build_custom_tool_specs(entries: &BTreeMap<String, CustomToolConfig>) -> Vec<
                     name: tool.name.clone(),
                     description,
                     parameters,
+                    required: tool.required.clone(),
                     command: tool.command.clone(),
+                    enabled: tool.enabled,
                     cwd: tool.cwd.clone(),
                     env: tool.env.clone(),
+                    env_include: env_patterns(&tool.env_include),
+                    env_exclude: env_patterns(&tool.env_exclude),
+                    env_passthrough: env_patterns(&tool.env_passthrough),
+                    env_file: tool.env_file.clone(),
                     timeout_ms: tool.timeout_ms,
                     with_escalated_permissions: tool.with_escalated_permissions,
                     parallel: tool.parallel,
                     hibernate_after_call: tool.hibernate_after_call,
+                    output_as_file: tool.output_as_file,
+                    max_output_bytes: tool.max_output_bytes,
                 });
             }
             Err(err) => {
@@ -1027,6 +1055,13 @@ fn build_custom_tool_specs(entries: &BTreeMap<String, CustomToolConfig>) -> Vec<
     specs
 }
 
+fn env_patterns(patterns: &[String]) -> Vec<EnvironmentVariablePattern> {
+    patterns
+        .iter()
+        .map(|pattern| EnvironmentVariablePattern::new_case_insensitive(pattern))
+        .collect()
+}
+
 /// Builds the tool registry builder while collecting tool specs for later serialization.
 pub(crate) fn build_specs(
     config: &ToolsConfig,
@@ -1157,7 +1192,7 @@ pub(crate) fn build_specs(
 
     if !config.custom_tools.is_empty() {
         let handler = Arc::new(CustomToolHandler::new(config.custom_tools.clone()));
-        for tool in &config.custom_tools {
+        for tool in config.custom_tools.iter().filter(|tool| tool.enabled) {
             let spec = ToolSpec::Function(ResponsesApiTool {
                 name: tool.name.clone(),
                 description: tool.description.clone(),
@@ -1,13 +1,16 @@
 #![cfg(not(target_os = "windows"))]
 
 use anyhow::Result;
+use codex_core::error::CodexErr;
 use core_test_support::assert_regex_match;
 use core_test_support::responses::ev_assistant_message;
 use core_test_support::responses::ev_completed;
 use core_test_support::responses::ev_function_call;
 use core_test_support::responses::ev_response_created;
+use core_test_support::responses::mount_sse_once;
 use core_test_support::responses::mount_sse_sequence;
 use core_test_support::responses::sse;
+use core_test_support::responses::sse_failed;
 use core_test_support::skip_if_no_network;
 use core_test_support::test_codex::TestCodexHarness;
 use pretty_assertions::assert_eq;
@@ -149,3 +152,134 @@ else:
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn stop_hook_runs_when_turn_errors() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    const STOP_LOG: &str = "stop_hook_event.json";
+
+    let harness = TestCodexHarness::with_config(|config| {
+        let stop_log_path = config.cwd.join(STOP_LOG);
+        config.stop_hook_command = Some(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            r#"cat > "$0""#.to_string(),
+            stop_log_path.to_string_lossy().into_owned(),
+        ]);
+    })
+    .await?;
+
+    mount_sse_once(
+        harness.server(),
+        sse_failed(
+            "resp-1",
+            "context_length_exceeded",
+            "Your input exceeds the context window of this model.",
+        ),
+    )
+    .await;
+
+    harness.submit("overflow the context window").await?;
+
+    let stop_event: Value = serde_json::from_str(&fs::read_to_string(harness.path(STOP_LOG))?)?;
+    assert_eq!(
+        stop_event.get("error").and_then(Value::as_str),
+        Some(CodexErr::ContextWindowExceeded.to_string().as_str())
+    );
+    assert_eq!(stop_event.get("final_message"), None);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn stop_hook_export_directive_writes_the_conversation() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    const EXPORT_PATH: &str = "exports/transcript.jsonl";
+
+    let harness = TestCodexHarness::with_config(|config| {
+        config.stop_hook_command = Some(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            r#"cat > /dev/null; printf '{"export_to":"%s"}' "$0""#.to_string(),
+            EXPORT_PATH.to_string(),
+        ]);
+    })
+    .await?;
+
+    mount_sse_once(
+        harness.server(),
+        sse(vec![
+            ev_response_created("resp-1"),
+            ev_assistant_message("msg-1", "all done"),
+            ev_completed("resp-1"),
+        ]),
+    )
+    .await;
+
+    harness.submit("export the transcript").await?;
+
+    let exported = fs::read_to_string(harness.path(EXPORT_PATH))?;
+    let messages: Vec<String> = exported
+        .lines()
+        .map(serde_json::from_str::<Value>)
+        .collect::<std::result::Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter(|line| line["type"] == "response_item" && line["payload"]["type"] == "message")
+        .filter_map(|line| {
+            line["payload"]["content"][0]["text"]
+                .as_str()
+                .map(str::to_string)
+        })
+        .filter(|text| text == "export the transcript" || text == "all done")
+        .collect();
+    assert_eq!(
+        messages,
+        vec!["export the transcript".to_string(), "all done".to_string()]
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn stop_hook_export_directive_cannot_leave_the_working_directory() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let outside = tempfile::tempdir()?;
+    let absolute = outside.path().join("x");
+    // Unique per run so another test's leftovers cannot make this pass.
+    let relative = format!("../x-{}", std::process::id());
+
+    for export_to in [relative.as_str(), absolute.to_str().expect("utf-8 path")] {
+        let directive = json!({ "export_to": export_to }).to_string();
+        let harness = TestCodexHarness::with_config(move |config| {
+            config.stop_hook_command = Some(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                r#"cat > /dev/null; printf '%s' "$0""#.to_string(),
+                directive,
+            ]);
+        })
+        .await?;
+
+        mount_sse_once(
+            harness.server(),
+            sse(vec![
+                ev_response_created("resp-1"),
+                ev_assistant_message("msg-1", "all done"),
+                ev_completed("resp-1"),
+            ]),
+        )
+        .await;
+
+        harness.submit("export the transcript").await?;
+
+        assert!(
+            !harness.path(export_to).exists(),
+            "export escaped the working directory: {export_to}"
+        );
+    }
+
+    Ok(())
+}
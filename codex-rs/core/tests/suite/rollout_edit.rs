@@ -1,14 +1,34 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::io::Read;
 
 use codex_core::PatchedToolCall;
+use codex_core::PatchedToolCalls;
+use codex_core::RolloutArchive;
+use codex_core::RolloutRecorder;
 use codex_core::ToolResultKind;
+use codex_core::ToolResultTarget;
+use codex_core::config::types::StopHookResponseItems;
+use codex_core::read_rollout_lines;
 use codex_core::replace_last_tool_result;
+use codex_core::replace_last_tool_result_and_archive;
+use codex_core::replace_last_tool_result_matching;
+use codex_core::replace_tool_results;
+use codex_core::rewrite_tool_outputs;
+use codex_core::stop_hook_event_from_rollout;
+use codex_core::write_rollout_lines;
+use codex_protocol::models::ContentItem;
 use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::EventMsg;
 use codex_protocol::protocol::RolloutItem;
 use codex_protocol::protocol::RolloutLine;
 use codex_protocol::protocol::SessionMeta;
 use codex_protocol::protocol::SessionMetaLine;
+use codex_protocol::protocol::TokenCountEvent;
+use codex_protocol::protocol::TokenUsage;
+use codex_protocol::protocol::TokenUsageInfo;
+use flate2::read::GzDecoder;
+use serde_json::json;
 use tempfile::tempdir;
 use tokio::fs;
 
@@ -31,7 +51,7 @@ async fn replace_last_function_call_output() -> anyhow::Result<()> {
             }),
         },
     ];
-    write_lines(&path, &lines).await?;
+    write_rollout_lines(&path, &lines).await?;
 
     let patched = replace_last_tool_result(&path, "final output").await?;
     assert_eq!(
@@ -39,10 +59,11 @@ async fn replace_last_function_call_output() -> anyhow::Result<()> {
         PatchedToolCall {
             call_id: "call_func".into(),
             kind: ToolResultKind::Function,
+            previous_content: "pending".into(),
         }
     );
 
-    let rewritten = read_lines(&path).await?;
+    let rewritten = read_rollout_lines(&path).await?;
     match &rewritten[1].item {
         RolloutItem::ResponseItem(ResponseItem::FunctionCallOutput { output, .. }) => {
             assert_eq!(output.content, "final output");
@@ -69,7 +90,7 @@ async fn replace_last_custom_tool_output() -> anyhow::Result<()> {
             }),
         },
     ];
-    write_lines(&path, &lines).await?;
+    write_rollout_lines(&path, &lines).await?;
 
     let patched = replace_last_tool_result(&path, "delivered").await?;
     assert_eq!(
@@ -77,10 +98,11 @@ async fn replace_last_custom_tool_output() -> anyhow::Result<()> {
         PatchedToolCall {
             call_id: "call_custom".into(),
             kind: ToolResultKind::Custom,
+            previous_content: "pending".into(),
         }
     );
 
-    let rewritten = read_lines(&path).await?;
+    let rewritten = read_rollout_lines(&path).await?;
     match &rewritten[1].item {
         RolloutItem::ResponseItem(ResponseItem::CustomToolCallOutput { output, .. }) => {
             assert_eq!(output, "delivered");
@@ -91,6 +113,455 @@ async fn replace_last_custom_tool_output() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn pending_target_skips_completed_output() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let path = dir.path().join("rollout.jsonl");
+
+    let completed = ResponseItem::FunctionCallOutput {
+        call_id: "call_done".into(),
+        output: FunctionCallOutputPayload {
+            content: "already finished".into(),
+            content_items: None,
+            success: Some(true),
+        },
+    };
+    let lines = vec![
+        session_meta_line(),
+        RolloutLine {
+            timestamp: ts(1),
+            item: RolloutItem::ResponseItem(ResponseItem::FunctionCallOutput {
+                call_id: "call_pending".into(),
+                output: FunctionCallOutputPayload {
+                    content: "pending".into(),
+                    content_items: None,
+                    success: None,
+                },
+            }),
+        },
+        RolloutLine {
+            timestamp: ts(2),
+            item: RolloutItem::ResponseItem(completed.clone()),
+        },
+    ];
+    write_rollout_lines(&path, &lines).await?;
+
+    let patched =
+        replace_last_tool_result_matching(&path, "real result", ToolResultTarget::PendingOrFailed)
+            .await?;
+    assert_eq!(
+        patched,
+        PatchedToolCall {
+            call_id: "call_pending".into(),
+            kind: ToolResultKind::Function,
+            previous_content: "pending".into(),
+        }
+    );
+
+    let rewritten = read_rollout_lines(&path).await?;
+    match &rewritten[1].item {
+        RolloutItem::ResponseItem(ResponseItem::FunctionCallOutput { output, .. }) => {
+            assert_eq!(output.content, "real result");
+        }
+        other => anyhow::bail!("unexpected item: {other:?}"),
+    }
+    match &rewritten[2].item {
+        RolloutItem::ResponseItem(item) => assert_eq!(item, &completed),
+        other => anyhow::bail!("unexpected item: {other:?}"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn pending_target_matches_failed_custom_placeholder() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let path = dir.path().join("rollout.jsonl");
+
+    let lines = vec![
+        session_meta_line(),
+        RolloutLine {
+            timestamp: ts(1),
+            item: RolloutItem::ResponseItem(ResponseItem::CustomToolCallOutput {
+                call_id: "call_custom".into(),
+                output: "pending".into(),
+            }),
+        },
+        RolloutLine {
+            timestamp: ts(2),
+            item: RolloutItem::ResponseItem(ResponseItem::CustomToolCallOutput {
+                call_id: "call_done".into(),
+                output: "done".into(),
+            }),
+        },
+    ];
+    write_rollout_lines(&path, &lines).await?;
+
+    let patched =
+        replace_last_tool_result_matching(&path, "delivered", ToolResultTarget::PendingOrFailed)
+            .await?;
+    assert_eq!(
+        patched,
+        PatchedToolCall {
+            call_id: "call_custom".into(),
+            kind: ToolResultKind::Custom,
+            previous_content: "pending".into(),
+        }
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn pending_target_errors_when_everything_completed() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let path = dir.path().join("rollout.jsonl");
+
+    let lines = vec![
+        session_meta_line(),
+        RolloutLine {
+            timestamp: ts(1),
+            item: RolloutItem::ResponseItem(ResponseItem::CustomToolCallOutput {
+                call_id: "call_done".into(),
+                output: "done".into(),
+            }),
+        },
+    ];
+    write_rollout_lines(&path, &lines).await?;
+    let original = fs::read_to_string(&path).await?;
+
+    let result =
+        replace_last_tool_result_matching(&path, "delivered", ToolResultTarget::PendingOrFailed)
+            .await;
+    assert!(result.is_err());
+    assert_eq!(fs::read_to_string(&path).await?, original);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn replace_tool_results_patches_several_calls_in_one_pass() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let path = dir.path().join("rollout.jsonl");
+
+    let function_output = |call_id: &str, content: &str| ResponseItem::FunctionCallOutput {
+        call_id: call_id.into(),
+        output: FunctionCallOutputPayload {
+            content: content.into(),
+            content_items: None,
+            success: Some(false),
+        },
+    };
+    let custom_output = |call_id: &str, output: &str| ResponseItem::CustomToolCallOutput {
+        call_id: call_id.into(),
+        output: output.into(),
+    };
+    let items = vec![
+        function_output("call_email", "pending"),
+        function_output("call_ls", "README.md"),
+        custom_output("call_review", "pending"),
+        function_output("call_deploy", "pending"),
+    ];
+    let mut lines = vec![session_meta_line()];
+    lines.extend(items.into_iter().zip(1..).map(|(item, n)| RolloutLine {
+        timestamp: ts(n),
+        item: RolloutItem::ResponseItem(item),
+    }));
+    write_rollout_lines(&path, &lines).await?;
+
+    let outputs = HashMap::from([
+        ("call_email".to_string(), "reply received".to_string()),
+        ("call_review".to_string(), "approved".to_string()),
+        ("call_deploy".to_string(), "deployed".to_string()),
+        ("call_unknown".to_string(), "ignored".to_string()),
+    ]);
+    let result = replace_tool_results(&path, &outputs).await?;
+
+    let patched = |call_id: &str, kind: ToolResultKind| PatchedToolCall {
+        call_id: call_id.into(),
+        kind,
+        previous_content: "pending".into(),
+    };
+    assert_eq!(
+        result,
+        PatchedToolCalls {
+            patched: vec![
+                patched("call_email", ToolResultKind::Function),
+                patched("call_review", ToolResultKind::Custom),
+                patched("call_deploy", ToolResultKind::Function),
+            ],
+            missing: vec!["call_unknown".to_string()],
+        }
+    );
+    let items: Vec<ResponseItem> = read_rollout_lines(&path)
+        .await?
+        .into_iter()
+        .filter_map(|line| match line.item {
+            RolloutItem::ResponseItem(item) => Some(item),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        items,
+        vec![
+            function_output("call_email", "reply received"),
+            function_output("call_ls", "README.md"),
+            custom_output("call_review", "approved"),
+            function_output("call_deploy", "deployed"),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn rewrite_tool_outputs_redacts_every_match() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let path = dir.path().join("rollout.jsonl");
+
+    let function_output = |call_id: &str, content: &str| ResponseItem::FunctionCallOutput {
+        call_id: call_id.into(),
+        output: FunctionCallOutputPayload {
+            content: content.into(),
+            content_items: None,
+            success: Some(true),
+        },
+    };
+    let custom_output = |call_id: &str, output: &str| ResponseItem::CustomToolCallOutput {
+        call_id: call_id.into(),
+        output: output.into(),
+    };
+    let items = vec![
+        function_output("call_env", "TOKEN=sk-live-123\nHOME=/root"),
+        function_output("call_ls", "README.md"),
+        custom_output("call_curl", "Authorization: Bearer sk-live-123"),
+        custom_output("call_echo", "hello"),
+    ];
+    let mut lines = vec![session_meta_line()];
+    lines.extend(items.into_iter().zip(1..).map(|(item, n)| RolloutLine {
+        timestamp: ts(n),
+        item: RolloutItem::ResponseItem(item),
+    }));
+    write_rollout_lines(&path, &lines).await?;
+
+    let rewritten = rewrite_tool_outputs(&path, |content| {
+        content
+            .contains("sk-live-123")
+            .then(|| content.replace("sk-live-123", "[REDACTED]"))
+    })
+    .await?;
+    assert_eq!(rewritten, 2);
+
+    let items: Vec<ResponseItem> = read_rollout_lines(&path)
+        .await?
+        .into_iter()
+        .filter_map(|line| match line.item {
+            RolloutItem::ResponseItem(item) => Some(item),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        items,
+        vec![
+            function_output("call_env", "TOKEN=[REDACTED]\nHOME=/root"),
+            function_output("call_ls", "README.md"),
+            custom_output("call_curl", "Authorization: Bearer [REDACTED]"),
+            custom_output("call_echo", "hello"),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn stop_hook_event_is_rebuilt_from_rollout() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let path = dir.path().join("rollout.jsonl");
+
+    let message = |role: &str, text: &str| ResponseItem::Message {
+        id: None,
+        role: role.into(),
+        content: vec![if role == "assistant" {
+            ContentItem::OutputText { text: text.into() }
+        } else {
+            ContentItem::InputText { text: text.into() }
+        }],
+    };
+    let items = vec![
+        message("user", "list the repo"),
+        ResponseItem::FunctionCallOutput {
+            call_id: "call_ls".into(),
+            output: FunctionCallOutputPayload {
+                content: "README.md".into(),
+                content_items: None,
+                success: Some(true),
+            },
+        },
+        message("assistant", "found README.md"),
+    ];
+    let usage = TokenUsage {
+        input_tokens: 10,
+        output_tokens: 5,
+        total_tokens: 15,
+        ..Default::default()
+    };
+    let meta = session_meta_line();
+    let RolloutItem::SessionMeta(meta_line) = &meta.item else {
+        anyhow::bail!("expected a session metadata line");
+    };
+    let conversation_id = meta_line.meta.id.to_string();
+    let mut lines = vec![meta];
+    lines.extend(items.iter().cloned().zip(1..).map(|(item, n)| RolloutLine {
+        timestamp: ts(n),
+        item: RolloutItem::ResponseItem(item),
+    }));
+    lines.push(RolloutLine {
+        timestamp: ts(9),
+        item: RolloutItem::EventMsg(EventMsg::TokenCount(TokenCountEvent {
+            info: Some(TokenUsageInfo {
+                total_token_usage: usage.clone(),
+                last_token_usage: usage.clone(),
+                model_context_window: None,
+            }),
+            rate_limits: None,
+        })),
+    });
+    write_rollout_lines(&path, &lines).await?;
+
+    let event = stop_hook_event_from_rollout(&path, StopHookResponseItems::Full).await?;
+
+    assert_eq!(
+        serde_json::to_value(&event)?,
+        json!({
+            "conversation_id": conversation_id,
+            "turn_id": "replay",
+            "cwd": "/tmp",
+            "final_message": "found README.md",
+            "response_items": items,
+            "token_usage": usage,
+        })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn editing_large_rollout_writes_gzip_archive() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let path = dir.path().join("rollout.jsonl");
+
+    let mut lines = vec![session_meta_line()];
+    lines.extend((1..=50).map(|n| RolloutLine {
+        timestamp: ts(n),
+        item: RolloutItem::ResponseItem(ResponseItem::FunctionCallOutput {
+            call_id: format!("call_{n}"),
+            output: FunctionCallOutputPayload {
+                content: "x".repeat(200),
+                content_items: None,
+                success: Some(true),
+            },
+        }),
+    }));
+    write_rollout_lines(&path, &lines).await?;
+
+    let (patched, archive_path) = replace_last_tool_result_and_archive(
+        &path,
+        "final output",
+        ToolResultTarget::Latest,
+        RolloutArchive::AboveBytes(4096),
+    )
+    .await?;
+    assert_eq!(
+        patched,
+        PatchedToolCall {
+            call_id: "call_50".into(),
+            kind: ToolResultKind::Function,
+            previous_content: "x".repeat(200),
+        }
+    );
+    assert_eq!(archive_path, Some(dir.path().join("rollout.jsonl.gz")));
+
+    let active = fs::read_to_string(&path).await?;
+    let mut decompressed = String::new();
+    GzDecoder::new(
+        fs::read(dir.path().join("rollout.jsonl.gz"))
+            .await?
+            .as_slice(),
+    )
+    .read_to_string(&mut decompressed)?;
+    assert_eq!(decompressed, active);
+
+    let from_archive =
+        RolloutRecorder::get_rollout_history(&dir.path().join("rollout.jsonl.gz")).await?;
+    let from_active = RolloutRecorder::get_rollout_history(&path).await?;
+    assert_eq!(
+        serde_json::to_value(from_archive.get_rollout_items())?,
+        serde_json::to_value(from_active.get_rollout_items())?
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn small_rollout_is_not_archived_below_threshold() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let path = dir.path().join("rollout.jsonl");
+
+    let lines = vec![
+        session_meta_line(),
+        RolloutLine {
+            timestamp: ts(1),
+            item: RolloutItem::ResponseItem(ResponseItem::CustomToolCallOutput {
+                call_id: "call_custom".into(),
+                output: "pending".into(),
+            }),
+        },
+    ];
+    write_rollout_lines(&path, &lines).await?;
+
+    let (_, archive_path) = replace_last_tool_result_and_archive(
+        &path,
+        "done",
+        ToolResultTarget::Latest,
+        RolloutArchive::AboveBytes(1024 * 1024),
+    )
+    .await?;
+    assert_eq!(archive_path, None);
+    assert!(!dir.path().join("rollout.jsonl.gz").exists());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn rollout_lines_round_trip_through_plain_and_archived_files() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let lines = vec![
+        session_meta_line(),
+        RolloutLine {
+            timestamp: ts(1),
+            item: RolloutItem::ResponseItem(ResponseItem::FunctionCallOutput {
+                call_id: "call_func".into(),
+                output: FunctionCallOutputPayload {
+                    content: "done".into(),
+                    content_items: None,
+                    success: Some(true),
+                },
+            }),
+        },
+    ];
+
+    let mut round_trips = Vec::new();
+    for name in ["rollout.jsonl", "rollout.jsonl.gz"] {
+        let path = dir.path().join(name);
+        write_rollout_lines(&path, &lines).await?;
+        round_trips.push(serde_json::to_value(read_rollout_lines(&path).await?)?);
+    }
+
+    let expected = serde_json::to_value(&lines)?;
+    assert_eq!(round_trips, vec![expected.clone(), expected]);
+    Ok(())
+}
+
 fn ts(n: u8) -> String {
     format!("2025-12-07T00:00:{n:02}Z")
 }
@@ -113,22 +584,3 @@ fn session_meta_line() -> RolloutLine {
         }),
     }
 }
-
-async fn write_lines(path: &Path, lines: &[RolloutLine]) -> anyhow::Result<()> {
-    let mut buf = String::new();
-    for line in lines {
-        buf.push_str(&serde_json::to_string(line)?);
-        buf.push('\n');
-    }
-    fs::write(path, buf).await?;
-    Ok(())
-}
-
-async fn read_lines(path: &Path) -> anyhow::Result<Vec<RolloutLine>> {
-    let text = fs::read_to_string(path).await?;
-    text.lines()
-        .filter(|line| !line.trim().is_empty())
-        .map(serde_json::from_str::<RolloutLine>)
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(Into::into)
-}
@@ -31,6 +31,8 @@ use core_test_support::responses::start_mock_server;
 use core_test_support::skip_if_no_network;
 use core_test_support::test_codex::test_codex;
 use core_test_support::wait_for_event;
+use image::ImageBuffer;
+use image::Rgba;
 use regex_lite::Regex;
 use serde_json::Value;
 use serde_json::json;
@@ -137,12 +139,9 @@ print(prefix + payload.get("text", ""))
                     "properties": { "text": { "type": "string" } },
                     "required": ["text"],
                 }),
-                cwd: None,
                 env,
                 timeout_ms: Some(2_000),
-                with_escalated_permissions: None,
-                parallel: false,
-                hibernate_after_call: false,
+                ..Default::default()
             },
         );
     });
@@ -194,6 +193,559 @@ print(prefix + payload.get("text", ""))
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn custom_tool_output_as_file_returns_the_path() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let server = start_mock_server().await;
+    let mut builder = test_codex();
+    builder = builder.with_config(|config| {
+        config.custom_tools.insert(
+            "custom.report".to_string(),
+            CustomToolConfig {
+                name: "custom.report".to_string(),
+                command: vec![
+                    "python3".to_string(),
+                    "-c".to_string(),
+                    "print('artifact line\\n' * 200, end='')".to_string(),
+                ],
+                description: Some("Produce a large report".to_string()),
+                parameters: json!({ "type": "object", "properties": {} }),
+                timeout_ms: Some(2_000),
+                output_as_file: true,
+                ..Default::default()
+            },
+        );
+    });
+    let test = builder.build(&server).await?;
+
+    let call_id = "custom-report";
+    mount_sse_once(
+        &server,
+        sse(vec![
+            ev_response_created("resp-1"),
+            ev_function_call(call_id, "custom.report", "{}"),
+            ev_completed("resp-1"),
+        ]),
+    )
+    .await;
+    let mock = mount_sse_once(
+        &server,
+        sse(vec![
+            ev_assistant_message("msg-1", "done"),
+            ev_completed("resp-2"),
+        ]),
+    )
+    .await;
+
+    test.submit_turn_with_policies(
+        "build the report",
+        AskForApproval::Never,
+        SandboxPolicy::DangerFullAccess,
+    )
+    .await?;
+
+    let output_path = test
+        .cwd_path()
+        .join(".codex/tool-output/custom.report-custom-report.txt");
+    let report = "artifact line\n".repeat(200);
+    assert_eq!(fs::read_to_string(&output_path)?, report);
+
+    let (content, _success) = mock
+        .single_request()
+        .function_call_output_content_and_success(call_id)
+        .expect("tool output present");
+    let payload: Value = serde_json::from_str(&content.expect("string output"))?;
+    assert_eq!(
+        payload["output"].as_str(),
+        Some(
+            format!(
+                "Output of `custom.report` ({} bytes) was written to {}. Read that file if you need its contents.",
+                report.len(),
+                output_path.display()
+            )
+            .as_str()
+        )
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn custom_tool_structured_output_returns_image_content_item() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let server = start_mock_server().await;
+    let mut builder = test_codex();
+    builder = builder.with_config(|config| {
+        config.custom_tools.insert(
+            "custom.screenshot".to_string(),
+            CustomToolConfig {
+                name: "custom.screenshot".to_string(),
+                command: vec![
+                    "echo".to_string(),
+                    r#"{"content_items": [{"type": "text", "text": "captured"}, {"type": "image", "path": "shot.png"}]}"#.to_string(),
+                ],
+                description: Some("Take a screenshot".to_string()),
+                parameters: json!({ "type": "object", "properties": {} }),
+                timeout_ms: Some(2_000),
+                ..Default::default()
+            },
+        );
+    });
+    let test = builder.build(&server).await?;
+    let image = ImageBuffer::from_pixel(2, 2, Rgba([20u8, 40, 60, 255]));
+    image.save(test.cwd_path().join("shot.png"))?;
+
+    let call_id = "custom-screenshot";
+    mount_sse_once(
+        &server,
+        sse(vec![
+            ev_response_created("resp-1"),
+            ev_function_call(call_id, "custom.screenshot", "{}"),
+            ev_completed("resp-1"),
+        ]),
+    )
+    .await;
+    let mock = mount_sse_once(
+        &server,
+        sse(vec![
+            ev_assistant_message("msg-1", "done"),
+            ev_completed("resp-2"),
+        ]),
+    )
+    .await;
+
+    test.submit_turn_with_policies(
+        "take a screenshot",
+        AskForApproval::Never,
+        SandboxPolicy::DangerFullAccess,
+    )
+    .await?;
+
+    let output = mock.single_request().function_call_output(call_id);
+    let items = output["output"]
+        .as_array()
+        .expect("structured output is sent as content items");
+    assert_eq!(
+        items[0],
+        json!({ "type": "input_text", "text": "captured" })
+    );
+    assert_eq!(items[1]["type"], "input_image");
+    let image_url = items[1]["image_url"].as_str().expect("image_url");
+    assert!(
+        image_url.starts_with("data:image/png;base64,"),
+        "unexpected image_url: {image_url}"
+    );
+    assert_eq!(items.len(), 2);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn custom_tool_env_exclude_filters_inherited_vars() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let server = start_mock_server().await;
+    let mut builder = test_codex();
+    builder = builder.with_config(|config| {
+        let script_path = config.cwd.join("custom_env.py");
+        fs::write(
+            &script_path,
+            r#"
+import json
+import os
+
+print(json.dumps({name: os.environ.get(name) for name in ["AWS_SECRET_ACCESS_KEY", "AWS_REGION", "KEEP_ME"]}))
+"#,
+        )
+        .expect("write env script");
+
+        // Stand-ins for variables inherited from the user's shell.
+        config
+            .shell_environment_policy
+            .r#set
+            .insert("AWS_SECRET_ACCESS_KEY".to_string(), "leaked".to_string());
+        config
+            .shell_environment_policy
+            .r#set
+            .insert("KEEP_ME".to_string(), "kept".to_string());
+
+        config.custom_tools.insert(
+            "custom.env".to_string(),
+            CustomToolConfig {
+                name: "custom.env".to_string(),
+                command: vec![
+                    "python3".to_string(),
+                    script_path.to_string_lossy().into_owned(),
+                ],
+                description: Some("Print selected env vars".to_string()),
+                parameters: json!({ "type": "object", "properties": {} }),
+                env: HashMap::from([("AWS_REGION".to_string(), "us-east-1".to_string())]),
+                env_exclude: vec!["AWS_*".to_string()],
+                timeout_ms: Some(2_000),
+                ..Default::default()
+            },
+        );
+    });
+    let test = builder.build(&server).await?;
+
+    let call_id = "custom-env";
+    mount_sse_once(
+        &server,
+        sse(vec![
+            ev_response_created("resp-1"),
+            ev_function_call(call_id, "custom.env", "{}"),
+            ev_completed("resp-1"),
+        ]),
+    )
+    .await;
+    let mock = mount_sse_once(
+        &server,
+        sse(vec![
+            ev_assistant_message("msg-1", "done"),
+            ev_completed("resp-2"),
+        ]),
+    )
+    .await;
+
+    test.submit_turn_with_policies(
+        "inspect the tool environment",
+        AskForApproval::Never,
+        SandboxPolicy::DangerFullAccess,
+    )
+    .await?;
+
+    let (content, _success) = mock
+        .single_request()
+        .function_call_output_content_and_success(call_id)
+        .expect("tool output present");
+    let payload: Value = serde_json::from_str(&content.expect("string output"))?;
+    let seen: Value = serde_json::from_str(payload["output"].as_str().unwrap_or_default())?;
+    assert_eq!(
+        seen,
+        json!({
+            "AWS_SECRET_ACCESS_KEY": null,
+            "AWS_REGION": "us-east-1",
+            "KEEP_ME": "kept",
+        })
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn custom_tool_reports_non_zero_exit_code() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let server = start_mock_server().await;
+    let mut builder = test_codex();
+    builder = builder.with_config(|config| {
+        config.custom_tools.insert(
+            "custom.lint".to_string(),
+            CustomToolConfig {
+                name: "custom.lint".to_string(),
+                command: vec![
+                    "sh".to_string(),
+                    "-c".to_string(),
+                    "echo lint failed; exit 2".to_string(),
+                ],
+                description: Some("Run the linter".to_string()),
+                parameters: json!({ "type": "object", "properties": {} }),
+                timeout_ms: Some(2_000),
+                ..Default::default()
+            },
+        );
+    });
+    let test = builder.build(&server).await?;
+
+    let call_id = "custom-lint";
+    mount_sse_once(
+        &server,
+        sse(vec![
+            ev_response_created("resp-1"),
+            ev_function_call(call_id, "custom.lint", "{}"),
+            ev_completed("resp-1"),
+        ]),
+    )
+    .await;
+    let mock = mount_sse_once(
+        &server,
+        sse(vec![
+            ev_assistant_message("msg-1", "done"),
+            ev_completed("resp-2"),
+        ]),
+    )
+    .await;
+
+    test.submit_turn_with_policies(
+        "run the linter",
+        AskForApproval::Never,
+        SandboxPolicy::DangerFullAccess,
+    )
+    .await?;
+
+    let (content, _success) = mock
+        .single_request()
+        .function_call_output_content_and_success(call_id)
+        .expect("tool output present");
+    let payload: Value = serde_json::from_str(&content.expect("string output"))?;
+    assert_eq!(payload["exit_code"], json!(2));
+    assert_eq!(payload["metadata"]["exit_code"], json!(2));
+    assert_eq!(payload["output"], json!("lint failed\n"));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn custom_tool_omits_top_level_exit_code_on_success() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let server = start_mock_server().await;
+    let mut builder = test_codex();
+    builder = builder.with_config(|config| {
+        config.custom_tools.insert(
+            "custom.lint".to_string(),
+            CustomToolConfig {
+                name: "custom.lint".to_string(),
+                command: vec![
+                    "sh".to_string(),
+                    "-c".to_string(),
+                    "echo lint clean".to_string(),
+                ],
+                description: Some("Run the linter".to_string()),
+                parameters: json!({ "type": "object", "properties": {} }),
+                timeout_ms: Some(2_000),
+                ..Default::default()
+            },
+        );
+    });
+    let test = builder.build(&server).await?;
+
+    let call_id = "custom-lint";
+    mount_sse_once(
+        &server,
+        sse(vec![
+            ev_response_created("resp-1"),
+            ev_function_call(call_id, "custom.lint", "{}"),
+            ev_completed("resp-1"),
+        ]),
+    )
+    .await;
+    let mock = mount_sse_once(
+        &server,
+        sse(vec![
+            ev_assistant_message("msg-1", "done"),
+            ev_completed("resp-2"),
+        ]),
+    )
+    .await;
+
+    test.submit_turn_with_policies(
+        "run the linter",
+        AskForApproval::Never,
+        SandboxPolicy::DangerFullAccess,
+    )
+    .await?;
+
+    let (content, _success) = mock
+        .single_request()
+        .function_call_output_content_and_success(call_id)
+        .expect("tool output present");
+    let payload: Value = serde_json::from_str(&content.expect("string output"))?;
+    assert_eq!(payload.get("exit_code"), None);
+    assert_eq!(payload["metadata"]["exit_code"], json!(0));
+    assert_eq!(payload["output"], json!("lint clean\n"));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn custom_tool_env_file_sits_between_inherited_and_explicit_env() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let server = start_mock_server().await;
+    let mut builder = test_codex();
+    builder = builder.with_config(|config| {
+        let script_path = config.cwd.join("custom_env_file.py");
+        fs::write(
+            &script_path,
+            r#"
+import json
+import os
+
+print(json.dumps({name: os.environ.get(name) for name in ["API_TOKEN", "REGION", "STAGE"]}))
+"#,
+        )
+        .expect("write env script");
+        fs::write(
+            config.cwd.join("tool.env"),
+            "API_TOKEN=from-file\nREGION=from-file\nSTAGE=from-file\n",
+        )
+        .expect("write env file");
+
+        // Stand-in for a variable inherited from the user's shell.
+        config
+            .shell_environment_policy
+            .r#set
+            .insert("API_TOKEN".to_string(), "inherited".to_string());
+
+        config.custom_tools.insert(
+            "custom.env_file".to_string(),
+            CustomToolConfig {
+                name: "custom.env_file".to_string(),
+                command: vec![
+                    "python3".to_string(),
+                    script_path.to_string_lossy().into_owned(),
+                ],
+                description: Some("Print selected env vars".to_string()),
+                parameters: json!({ "type": "object", "properties": {} }),
+                env: HashMap::from([("STAGE".to_string(), "explicit".to_string())]),
+                env_file: Some("tool.env".to_string()),
+                timeout_ms: Some(2_000),
+                ..Default::default()
+            },
+        );
+    });
+    let test = builder.build(&server).await?;
+
+    let call_id = "custom-env-file";
+    mount_sse_once(
+        &server,
+        sse(vec![
+            ev_response_created("resp-1"),
+            ev_function_call(call_id, "custom.env_file", "{}"),
+            ev_completed("resp-1"),
+        ]),
+    )
+    .await;
+    let mock = mount_sse_once(
+        &server,
+        sse(vec![
+            ev_assistant_message("msg-1", "done"),
+            ev_completed("resp-2"),
+        ]),
+    )
+    .await;
+
+    test.submit_turn_with_policies(
+        "inspect the tool environment",
+        AskForApproval::Never,
+        SandboxPolicy::DangerFullAccess,
+    )
+    .await?;
+
+    let (content, _success) = mock
+        .single_request()
+        .function_call_output_content_and_success(call_id)
+        .expect("tool output present");
+    let payload: Value = serde_json::from_str(&content.expect("string output"))?;
+    let seen: Value = serde_json::from_str(payload["output"].as_str().unwrap_or_default())?;
+    assert_eq!(
+        seen,
+        json!({
+            "API_TOKEN": "from-file",
+            "REGION": "from-file",
+            "STAGE": "explicit",
+        })
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn custom_tool_streams_output_deltas_before_completion() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let server = start_mock_server().await;
+    let mut builder = test_codex();
+    builder = builder.with_config(|config| {
+        let script_path = config.cwd.join("custom_ticker.py");
+        fs::write(
+            &script_path,
+            r#"
+import sys
+import time
+
+for i in range(1, 4):
+    print(f"tick {i}", flush=True)
+    time.sleep(0.2)
+"#,
+        )
+        .expect("write ticker script");
+
+        config.custom_tools.insert(
+            "custom.ticker".to_string(),
+            CustomToolConfig {
+                name: "custom.ticker".to_string(),
+                command: vec![
+                    "python3".to_string(),
+                    script_path.to_string_lossy().into_owned(),
+                ],
+                description: Some("Print ticks over time".to_string()),
+                parameters: json!({ "type": "object", "properties": {} }),
+                timeout_ms: Some(5_000),
+                ..Default::default()
+            },
+        );
+    });
+    let test = builder.build(&server).await?;
+
+    let call_id = "custom-ticker";
+    mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![
+                ev_response_created("resp-1"),
+                ev_function_call(call_id, "custom.ticker", "{}"),
+                ev_completed("resp-1"),
+            ]),
+            sse(vec![
+                ev_assistant_message("msg-1", "done"),
+                ev_completed("resp-2"),
+            ]),
+        ],
+    )
+    .await;
+
+    test.codex
+        .submit(Op::UserTurn {
+            items: vec![UserInput::Text {
+                text: "run the ticker".to_string(),
+            }],
+            final_output_json_schema: None,
+            cwd: test.cwd_path().to_path_buf(),
+            approval_policy: AskForApproval::Never,
+            sandbox_policy: SandboxPolicy::DangerFullAccess,
+            model: test.session_configured.model.clone(),
+            effort: None,
+            summary: ReasoningSummary::Auto,
+        })
+        .await?;
+
+    let mut streamed = Vec::new();
+    loop {
+        match wait_for_event(&test.codex, |event| {
+            matches!(
+                event,
+                EventMsg::ExecCommandOutputDelta(_) | EventMsg::ExecCommandEnd(_)
+            )
+        })
+        .await
+        {
+            EventMsg::ExecCommandOutputDelta(delta) if delta.call_id == call_id => {
+                streamed.extend(delta.chunk);
+            }
+            EventMsg::ExecCommandEnd(end) if end.call_id == call_id => break,
+            _ => {}
+        }
+    }
+    assert_eq!(
+        String::from_utf8_lossy(&streamed),
+        "tick 1\ntick 2\ntick 3\n"
+    );
+
+    wait_for_event(&test.codex, |event| {
+        matches!(event, EventMsg::TaskComplete(_))
+    })
+    .await;
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn custom_tool_hibernate_after_call_triggers_pending_flow() -> Result<()> {
     skip_if_no_network!(Ok(()));
@@ -229,12 +781,9 @@ print(json.dumps({"status": "pending", "ticket": ticket}))
                     "properties": { "ticket": { "type": "string" } },
                     "required": ["ticket"],
                 }),
-                cwd: None,
-                env: HashMap::new(),
                 timeout_ms: Some(2_000),
-                with_escalated_permissions: None,
-                parallel: false,
                 hibernate_after_call: true,
+                ..Default::default()
             },
         );
     });
@@ -58,10 +58,35 @@ pub struct Cli {
     #[arg(long = "skip-git-repo-check", default_value_t = false)]
     pub skip_git_repo_check: bool,
 
-    /// Path to a prompt-sequence TOML file describing multiple prompts to run sequentially.
+    /// Path (or `http(s)://` URL) of a prompt-sequence TOML file describing multiple prompts
+    /// to run sequentially.
     #[arg(long = "prompt-sequence", value_name = "FILE")]
     pub prompt_sequence: Option<PathBuf>,
 
+    /// Directory that attachment paths in a prompt sequence fetched from a URL resolve
+    /// against. Defaults to the current directory.
+    #[arg(
+        long = "prompt-sequence-base",
+        value_name = "DIR",
+        requires = "prompt_sequence"
+    )]
+    pub prompt_sequence_base: Option<PathBuf>,
+
+    /// Check the prompt sequence (attachments, `{{variable}}` placeholders) and list its steps
+    /// without running anything.
+    #[arg(
+        long = "validate",
+        default_value_t = false,
+        requires = "prompt_sequence"
+    )]
+    pub validate_sequence: bool,
+
+    /// Watch the config file (`--config-file`, or `config.toml` in the Codex home) and apply
+    /// edits to `model`, `model_reasoning_effort`, and `model_reasoning_summary` to later turns
+    /// without restarting.
+    #[arg(long = "watch-config", default_value_t = false)]
+    pub watch_config: bool,
+
     /// Additional directories that should be writable alongside the primary workspace.
     #[arg(long = "add-dir", value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
     pub add_dir: Vec<PathBuf>,
@@ -127,6 +152,15 @@ pub struct ResumeArgs {
     #[arg(long = "replace-last-toolresult", value_name = "TEXT")]
     pub replace_last_tool_result: Option<String>,
 
+    /// After --replace-last-toolresult, also write a gzip copy of the edited rollout
+    /// (`<rollout>.jsonl.gz`) when it is at least this many bytes. Use 0 to always archive.
+    #[arg(
+        long = "archive-above",
+        value_name = "BYTES",
+        requires = "replace_last_tool_result"
+    )]
+    pub archive_above_bytes: Option<u64>,
+
     /// Prompt to send after resuming the session. If `-` is used, read from stdin.
     #[arg(value_name = "PROMPT", value_hint = clap::ValueHint::Other)]
     pub prompt: Option<String>,
@@ -188,6 +222,15 @@ pub struct DeliverPendingArgs {
     /// Whether the tool succeeded (sets the `success` flag in the payload).
     #[arg(long = "success", default_value_t = true)]
     pub success: bool,
+
+    /// Key identifying this delivery. Resending with the same key for the
+    /// same call id is acknowledged without delivering the result twice.
+    #[arg(long = "idempotency-key", value_name = "KEY")]
+    pub idempotency_key: Option<String>,
+
+    /// Send the output as interim progress and keep the call waiting for a final delivery.
+    #[arg(long = "keep-pending", default_value_t = false)]
+    pub keep_pending: bool,
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
@@ -16,6 +16,7 @@ use anyhow::Context;
 pub use cli::Cli;
 pub use cli::Command;
 pub use cli::ReviewArgs;
+use codex_common::config_watch::ConfigFileWatcher;
 use codex_common::oss::ensure_oss_provider_ready;
 use codex_common::oss::get_default_model_for_oss_provider;
 use codex_core::AuthManager;
@@ -25,6 +26,7 @@ use codex_core::NewConversation;
 use codex_core::OLLAMA_OSS_PROVIDER_ID;
 use codex_core::auth::enforce_login_restrictions;
 use codex_core::config::Config;
+use codex_core::config::ConfigBuilder;
 use codex_core::config::ConfigOverrides;
 use codex_core::config::find_codex_home;
 use codex_core::config::load_config_as_toml_with_cli_overrides;
@@ -38,8 +40,10 @@ use codex_core::protocol::ReviewRequest;
 use codex_core::protocol::ReviewTarget;
 use codex_core::protocol::SessionSource;
 use codex_protocol::approvals::ElicitationAction;
+use codex_protocol::config_types::ReasoningSummary;
 use codex_protocol::config_types::SandboxMode;
 use codex_protocol::models::FunctionCallOutputPayload;
+use codex_protocol::openai_models::ReasoningEffort;
 use codex_protocol::user_input::UserInput;
 use codex_utils_absolute_path::AbsolutePathBuf;
 use event_processor_with_human_output::EventProcessorWithHumanOutput;
@@ -49,6 +53,7 @@ use std::io::IsTerminal;
 use std::io::Read;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 use supports_color::Stream;
 use time::OffsetDateTime;
 use time::format_description::FormatItem;
@@ -66,14 +71,23 @@ use crate::cli::Command as ExecCommand;
 use crate::cli::DeliverPendingArgs;
 use crate::event_processor::CodexStatus;
 use crate::event_processor::EventProcessor;
+use crate::pending_tool_ipc::PENDING_TOOL_ADDR_ENV_VAR;
+use crate::pending_tool_ipc::PENDING_TOOL_BIND_ENV_VAR;
 use crate::pending_tool_ipc::PendingToolServer;
 use crate::pending_tool_ipc::addr_from_metadata;
+use crate::pending_tool_ipc::bind_addr_from_env_value;
+use crate::pending_tool_ipc::bind_listener;
 use crate::pending_tool_ipc::load_metadata;
 use crate::pending_tool_ipc::send_pending_result;
+use crate::prompt_sequence::PromptSequenceEntry;
 use crate::prompt_sequence::PromptSequenceRunner;
+use crate::prompt_sequence::StepErrorPolicy;
+use crate::prompt_sequence::StepExpectation;
+use codex_core::RolloutArchive;
+use codex_core::ToolResultTarget;
 use codex_core::default_client::set_default_originator;
 use codex_core::find_conversation_path_by_selector_str;
-use codex_core::replace_last_tool_result as patch_last_tool_result;
+use codex_core::replace_last_tool_result_and_archive;
 
 enum InitialOperation {
     UserTurn {
@@ -102,6 +116,9 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         cwd,
         skip_git_repo_check,
         prompt_sequence,
+        prompt_sequence_base,
+        validate_sequence,
+        watch_config,
         add_dir,
         color,
         last_message_file,
@@ -139,6 +156,26 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         }
     }
 
+    if validate_sequence && let Some(source) = &prompt_sequence {
+        let descriptions = if PromptSequenceRunner::is_url(source) {
+            let base_dir = prompt_sequence_base.unwrap_or_else(|| PathBuf::from("."));
+            PromptSequenceRunner::load_url(&source.to_string_lossy(), &base_dir)
+                .await?
+                .validate_steps()?
+        } else {
+            PromptSequenceRunner::validate(source)?
+        };
+        for (index, description) in descriptions.iter().enumerate() {
+            eprintln!("{}. {description}", index + 1);
+        }
+        eprintln!(
+            "prompt-sequence {} is valid ({} steps)",
+            source.display(),
+            descriptions.len()
+        );
+        return Ok(());
+    }
+
     let (stdout_with_ansi, stderr_with_ansi) = match color {
         cli::Color::Always => (true, true),
         cli::Color::Never => (false, false),
@@ -170,7 +207,10 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
     };
 
     // Parse `-c` overrides from the CLI.
-    let cli_kv_overrides = match config_overrides.parse_overrides() {
+    let (cli_kv_overrides, loader_overrides) = match config_overrides
+        .parse_overrides()
+        .and_then(|kv| Ok((kv, config_overrides.loader_overrides()?)))
+    {
         Ok(v) => v,
         #[allow(clippy::print_stderr)]
         Err(e) => {
@@ -200,6 +240,7 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
             &codex_home,
             &config_cwd,
             cli_kv_overrides.clone(),
+            loader_overrides.clone(),
         )
         .await
         {
@@ -261,8 +302,18 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         additional_writable_roots: add_dir,
     };
 
-    let config =
-        Config::load_with_cli_overrides_and_harness_overrides(cli_kv_overrides, overrides).await?;
+    // Kept to rebuild the config with the same flags when `--watch-config`
+    // reports an edit.
+    let config_builder = ConfigBuilder::default()
+        .cli_overrides(cli_kv_overrides)
+        .harness_overrides(overrides)
+        .loader_overrides(loader_overrides);
+    let mut config = config_builder.clone().build().await?;
+
+    if let Err(err) = config_overrides.write_config_dump(&config) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
 
     if let Err(err) = enforce_login_restrictions(&config).await {
         eprintln!("{err}");
@@ -319,8 +370,8 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
     let default_cwd = config.cwd.to_path_buf();
     let default_approval_policy = config.approval_policy.value();
     let default_sandbox_policy = config.sandbox_policy.get();
-    let default_effort = config.model_reasoning_effort;
-    let default_summary = config.model_reasoning_summary;
+    let mut default_effort = config.model_reasoning_effort;
+    let mut default_summary = config.model_reasoning_summary;
 
     if !skip_git_repo_check && get_git_repo_root(&default_cwd).is_none() {
         eprintln!("Not inside a trusted directory and --skip-git-repo-check was not specified.");
@@ -333,10 +384,37 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         config.cli_auth_credentials_store_mode,
     );
     let conversation_manager = ConversationManager::new(auth_manager.clone(), SessionSource::Exec);
-    let default_model = conversation_manager
+    let mut default_model = conversation_manager
         .get_models_manager()
         .get_model(&config.model, &config)
         .await;
+    let mut configured_model = config.model.clone();
+
+    // Bind the pending tool listener before the conversation starts so its
+    // address can be exported to tool subprocesses.
+    let pending_tool_listener =
+        match bind_addr_from_env_value(std::env::var(PENDING_TOOL_BIND_ENV_VAR).ok().as_deref()) {
+            Ok(addr) => match bind_listener(addr).await {
+                Ok(listener) => Some(listener),
+                Err(err) => {
+                    warn!(?err, "failed to bind pending tool IPC listener");
+                    None
+                }
+            },
+            Err(err) => {
+                warn!(?err, "ignoring {PENDING_TOOL_BIND_ENV_VAR}");
+                None
+            }
+        };
+    if let Some(addr) = pending_tool_listener
+        .as_ref()
+        .and_then(|listener| listener.local_addr().ok())
+    {
+        config
+            .shell_environment_policy
+            .r#set
+            .insert(PENDING_TOOL_ADDR_ENV_VAR.to_string(), addr.to_string());
+    }
 
     // Handle resume subcommand by resolving a rollout path and using explicit resume API.
     let NewConversation {
@@ -352,11 +430,20 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
                     "--replace-last-toolresult requires specifying a session id or --last"
                 )
             })?;
-            patch_last_tool_result(path, replacement)
-                .await
-                .with_context(|| {
-                    format!("failed to replace last tool result in {}", path.display())
-                })?;
+            let archive = args
+                .archive_above_bytes
+                .map_or(RolloutArchive::Never, RolloutArchive::AboveBytes);
+            let (_, archive_path) = replace_last_tool_result_and_archive(
+                path,
+                replacement,
+                ToolResultTarget::Latest,
+                archive,
+            )
+            .await
+            .with_context(|| format!("failed to replace last tool result in {}", path.display()))?;
+            if let Some(archive_path) = archive_path {
+                info!("Archived edited rollout to {}", archive_path.display());
+            }
         }
 
         if let Some(path) = resume_path.take() {
@@ -373,18 +460,29 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
             .new_conversation(config.clone())
             .await?
     };
-    let _pending_tool_server =
-        match PendingToolServer::start(&config.codex_home, &conversation_id, conversation.clone())
-            .await
+    let pending_tool_server = match pending_tool_listener {
+        Some(listener) => match PendingToolServer::start(
+            listener,
+            &config.codex_home,
+            &conversation_id,
+            conversation.clone(),
+        )
+        .await
         {
             Ok(server) => Some(server),
             Err(err) => {
                 warn!(?err, "failed to start pending tool IPC server");
                 None
             }
-        };
+        },
+        None => None,
+    };
 
     let mut prompt_sequence_runner = match prompt_sequence {
+        Some(url) if PromptSequenceRunner::is_url(&url) => {
+            let base_dir = prompt_sequence_base.unwrap_or_else(|| PathBuf::from("."));
+            Some(PromptSequenceRunner::load_url(&url.to_string_lossy(), &base_dir).await?)
+        }
         Some(path) => Some(PromptSequenceRunner::load(&path)?),
         None => None,
     };
@@ -401,13 +499,19 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         })?);
     }
 
+    let initial_approval_policy = initial_sequence_entry
+        .as_ref()
+        .and_then(|entry| entry.approval)
+        .unwrap_or(default_approval_policy);
+    let mut step_deadline = initial_sequence_entry
+        .as_ref()
+        .and_then(StepDeadline::for_entry);
+    let mut step_check = initial_sequence_entry
+        .as_ref()
+        .and_then(StepCheck::for_entry);
+
     let (initial_operation, prompt_summary) = if let Some(entry) = initial_sequence_entry {
-        let description = format!(
-            "{} ({}/{})",
-            entry.description,
-            entry.index + 1,
-            entry.total
-        );
+        let description = step_label(&entry);
         (
             InitialOperation::UserTurn {
                 items: entry.items,
@@ -548,7 +652,7 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
                 .submit(Op::UserTurn {
                     items,
                     cwd: default_cwd.clone(),
-                    approval_policy: default_approval_policy,
+                    approval_policy: initial_approval_policy,
                     sandbox_policy: default_sandbox_policy.clone(),
                     model: default_model.clone(),
                     effort: default_effort,
@@ -566,17 +670,124 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         }
     };
 
+    let (reload_tx, mut reload_rx) = tokio::sync::mpsc::unbounded_channel::<()>();
+    let _config_watcher: Option<ConfigFileWatcher> = if watch_config {
+        let watcher = config_overrides
+            .watch_config_file(&config.codex_home, move || {
+                let _ = reload_tx.send(());
+            })
+            .map_err(anyhow::Error::msg)?;
+        Some(watcher)
+    } else {
+        None
+    };
+
     // Run the loop until the task is complete.
     // Track whether a fatal error was reported by the server so we can
     // exit with a non-zero status for automation-friendly signaling.
     let mut error_seen = false;
-    while let Some(event) = rx.recv().await {
+    // Whether the running prompt-sequence step reported an error.
+    let mut step_failed = false;
+    loop {
+        let event = tokio::select! {
+            event = rx.recv() => event,
+            Some(()) = reload_rx.recv() => {
+                match config_builder.clone().build().await {
+                    Ok(reloaded) => {
+                        let model_changed = apply_config_reload(
+                            &reloaded,
+                            &mut configured_model,
+                            &mut default_effort,
+                            &mut default_summary,
+                        );
+                        if model_changed {
+                            default_model = conversation_manager
+                                .get_models_manager()
+                                .get_model(&reloaded.model, &reloaded)
+                                .await;
+                        }
+                    }
+                    Err(err) => warn!("Config reload: ignoring config that fails to load: {err}"),
+                }
+                continue;
+            }
+            () = sleep_until_deadline(step_deadline.as_ref().map(|deadline| deadline.at)) => {
+                let Some(deadline) = step_deadline.take() else {
+                    continue;
+                };
+                // Cancel the wedged step and stop the sequence; the
+                // timeout counts as a failure for the exit status.
+                eprintln!(
+                    "Prompt sequence step {} timed out after {:?}; cancelling the run.",
+                    deadline.description, deadline.timeout
+                );
+                error_seen = true;
+                prompt_sequence_runner = None;
+                conversation.submit(Op::Interrupt).await?;
+                conversation.submit(Op::Shutdown).await?;
+                continue;
+            }
+        };
+        let Some(event) = event else {
+            break;
+        };
+
         let mut queued_sequence_step = None;
-        if let Some(runner) = prompt_sequence_runner.as_mut()
-            && matches!(&event.msg, EventMsg::TaskComplete(_))
-            && runner.has_remaining()
-        {
-            queued_sequence_step = runner.next_entry();
+        if let EventMsg::TaskComplete(complete) = &event.msg {
+            step_deadline = None;
+            let check = step_check.take();
+            if std::mem::take(&mut step_failed)
+                && let Some(runner) = prompt_sequence_runner.as_mut()
+            {
+                // The step's `on_error` policy decides what happens next; its
+                // assertions are not checked against a failed turn.
+                match runner.retry_entry() {
+                    Some(entry) => {
+                        eprintln!(
+                            "Prompt sequence step {} failed; retrying.",
+                            step_label(&entry)
+                        );
+                        // Only a failure of the retried turn fails the run.
+                        error_seen = false;
+                        queued_sequence_step = Some(entry);
+                    }
+                    None if runner.error_policy() == StepErrorPolicy::Continue => {}
+                    None => {
+                        eprintln!("Prompt sequence step failed; skipping the remaining steps.");
+                        prompt_sequence_runner = None;
+                    }
+                }
+            } else if let Some(check) = check
+                && let Err(failure) = check.verify(complete.last_agent_message.as_deref())
+            {
+                // A failed assertion stops the sequence and fails the run.
+                eprintln!(
+                    "Prompt sequence step {} failed: {failure}; skipping the remaining steps.",
+                    check.description
+                );
+                error_seen = true;
+                prompt_sequence_runner = None;
+            }
+            if queued_sequence_step.is_none()
+                && let Some(runner) = prompt_sequence_runner.as_mut()
+                && let Err(err) = runner.capture_output(complete.last_agent_message.as_deref())
+            {
+                eprintln!("{err}; skipping the remaining steps.");
+                error_seen = true;
+                prompt_sequence_runner = None;
+            }
+            if queued_sequence_step.is_none()
+                && let Some(runner) = prompt_sequence_runner.as_mut()
+            {
+                runner.finish_step();
+                if runner.has_remaining() {
+                    queued_sequence_step = runner.next_entry();
+                }
+                if runner.is_finished() {
+                    let (completed, total) = runner.progress();
+                    info!("Prompt sequence: {completed}/{total} done");
+                }
+            }
         }
 
         if let EventMsg::ElicitationRequest(ev) = &event.msg {
@@ -589,8 +800,14 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
                 })
                 .await?;
         }
+        if let (Some(server), EventMsg::PendingToolState(state)) =
+            (pending_tool_server.as_ref(), &event.msg)
+        {
+            server.record_pending_state(state);
+        }
         if matches!(&event.msg, EventMsg::Error(_)) {
             error_seen = true;
+            step_failed = prompt_sequence_runner.is_some();
         }
         let mut shutdown: CodexStatus = event_processor.process_event(event);
 
@@ -601,11 +818,13 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
                 entry.total,
                 entry.description
             );
+            step_deadline = StepDeadline::for_entry(&entry);
+            step_check = StepCheck::for_entry(&entry);
             conversation
                 .submit(Op::UserTurn {
                     items: entry.items,
                     cwd: default_cwd.clone(),
-                    approval_policy: default_approval_policy,
+                    approval_policy: entry.approval.unwrap_or(default_approval_policy),
                     sandbox_policy: default_sandbox_policy.clone(),
                     model: default_model.clone(),
                     effort: default_effort,
@@ -634,25 +853,140 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
     Ok(())
 }
 
+/// Wait until `deadline`, or forever when there is none.
+async fn sleep_until_deadline(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Adopt the hot-reloadable settings (`model`, `model_reasoning_effort`,
+/// and `model_reasoning_summary`) of `reloaded` for the turns submitted after
+/// this point. `reloaded` is built with the same flags as startup, so `-c`,
+/// `-m`/`--oss`, the active profile, and project or managed layers still win
+/// over the edited file. Returns whether the model changed, which the caller
+/// resolves the same way startup does.
+fn apply_config_reload(
+    reloaded: &Config,
+    model: &mut Option<String>,
+    effort: &mut Option<ReasoningEffort>,
+    summary: &mut ReasoningSummary,
+) -> bool {
+    if reloaded.model_reasoning_effort != *effort {
+        info!(
+            "Config reload: model_reasoning_effort = {:?}",
+            reloaded.model_reasoning_effort
+        );
+        *effort = reloaded.model_reasoning_effort;
+    }
+    if reloaded.model_reasoning_summary != *summary {
+        info!(
+            "Config reload: model_reasoning_summary = {:?}",
+            reloaded.model_reasoning_summary
+        );
+        *summary = reloaded.model_reasoning_summary;
+    }
+    if reloaded.model == *model {
+        return false;
+    }
+    info!("Config reload: model = {:?}", reloaded.model);
+    *model = reloaded.model.clone();
+    true
+}
+
+/// Deadline for the prompt-sequence step currently running.
+struct StepDeadline {
+    at: tokio::time::Instant,
+    timeout: Duration,
+    description: String,
+}
+
+impl StepDeadline {
+    fn for_entry(entry: &PromptSequenceEntry) -> Option<Self> {
+        entry.timeout.map(|timeout| Self {
+            at: tokio::time::Instant::now() + timeout,
+            timeout,
+            description: step_label(entry),
+        })
+    }
+}
+
+/// `expect_contains` / `expect_regex` assertions for the prompt-sequence step
+/// currently running.
+struct StepCheck {
+    expectations: Vec<StepExpectation>,
+    description: String,
+}
+
+impl StepCheck {
+    fn for_entry(entry: &PromptSequenceEntry) -> Option<Self> {
+        (!entry.expectations.is_empty()).then(|| Self {
+            expectations: entry.expectations.clone(),
+            description: step_label(entry),
+        })
+    }
+
+    /// Check the turn's final agent message against every expectation.
+    fn verify(&self, last_agent_message: Option<&str>) -> Result<(), String> {
+        let Some(message) = last_agent_message else {
+            return Err("the turn ended without a final message to check".to_string());
+        };
+        match self
+            .expectations
+            .iter()
+            .find(|expectation| !expectation.is_met(message))
+        {
+            Some(expectation) => Err(format!("expected the final message to {expectation}")),
+            None => Ok(()),
+        }
+    }
+}
+
+fn step_label(entry: &PromptSequenceEntry) -> String {
+    format!(
+        "{} ({}/{})",
+        entry.description,
+        entry.index + 1,
+        entry.total
+    )
+}
+
 async fn run_deliver_pending(args: DeliverPendingArgs) -> anyhow::Result<()> {
-    let codex_home = find_codex_home().context("failed to locate codex home")?;
-    let metadata_path = codex_home
-        .join("live")
-        .join(format!("{}.json", args.session_id));
-    let bytes = fs::read(&metadata_path)
-        .await
-        .with_context(|| format!("failed to read {}", metadata_path.display()))?;
-    let value: Value = serde_json::from_slice(&bytes)?;
-    let meta = load_metadata(value)?;
-    let addr = addr_from_metadata(meta)?;
+    // Tool subprocesses (and CI pipelines using a fixed bind) already know the
+    // listener address, so skip the metadata lookup when it is provided.
+    let addr = match std::env::var(PENDING_TOOL_ADDR_ENV_VAR) {
+        Ok(addr) if !addr.trim().is_empty() => addr
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid {PENDING_TOOL_ADDR_ENV_VAR} address `{addr}`"))?,
+        _ => {
+            let codex_home = find_codex_home().context("failed to locate codex home")?;
+            let metadata_path = codex_home
+                .join("live")
+                .join(format!("{}.json", args.session_id));
+            let bytes = fs::read(&metadata_path)
+                .await
+                .with_context(|| format!("failed to read {}", metadata_path.display()))?;
+            let value: Value = serde_json::from_slice(&bytes)?;
+            addr_from_metadata(load_metadata(value)?)?
+        }
+    };
     let payload = FunctionCallOutputPayload {
         content: args.output,
         success: Some(args.success),
         ..Default::default()
     };
-    send_pending_result(addr, args.call_id, payload).await?;
+    let status = send_pending_result(
+        addr,
+        args.call_id,
+        payload,
+        args.idempotency_key,
+        args.keep_pending,
+    )
+    .await?;
     eprintln!(
-        "Delivered pending tool result for session {}.",
+        "Delivered pending tool result for session {} ({status}).",
         args.session_id
     );
     Ok(())
@@ -837,6 +1171,7 @@ fn build_review_request(args: ReviewArgs) -> anyhow::Result<ReviewRequest> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use codex_core::config::CONFIG_TOML_FILE;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -900,4 +1235,72 @@ mod tests {
 
         assert_eq!(request, expected);
     }
+
+    /// Build the startup config, replace the config file with `edit`, and
+    /// apply the reloaded config. Returns whether the model changed, plus the
+    /// model and effort used for later turns.
+    async fn reload_after_edit(
+        builder: ConfigBuilder,
+        codex_home: &Path,
+        edit: &str,
+    ) -> (bool, Option<String>, Option<ReasoningEffort>) {
+        let config_path = codex_home.join(CONFIG_TOML_FILE);
+        std::fs::write(&config_path, "model = \"gpt-file\"\n").expect("write config");
+        let builder = builder.codex_home(codex_home.to_path_buf());
+        let config = builder.clone().build().await.expect("config");
+        std::fs::write(&config_path, edit).expect("edit config");
+        let reloaded = builder.build().await.expect("reloaded config");
+
+        let mut model = config.model.clone();
+        let mut effort = config.model_reasoning_effort;
+        let mut summary = config.model_reasoning_summary;
+        let model_changed = apply_config_reload(&reloaded, &mut model, &mut effort, &mut summary);
+        (model_changed, model, effort)
+    }
+
+    #[tokio::test]
+    async fn config_file_edit_reloads_model_and_effort() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+
+        let reloaded = reload_after_edit(
+            ConfigBuilder::default(),
+            codex_home.path(),
+            "model = \"gpt-edited\"\nmodel_reasoning_effort = \"high\"\n",
+        )
+        .await;
+
+        assert_eq!(
+            reloaded,
+            (
+                true,
+                Some("gpt-edited".to_string()),
+                Some(ReasoningEffort::High)
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn model_from_cli_wins_over_config_file_edit() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let builder = ConfigBuilder::default().harness_overrides(ConfigOverrides {
+            model: Some("gpt-cli".to_string()),
+            ..Default::default()
+        });
+
+        let reloaded = reload_after_edit(
+            builder,
+            codex_home.path(),
+            "model = \"gpt-edited\"\nmodel_reasoning_effort = \"high\"\n",
+        )
+        .await;
+
+        assert_eq!(
+            reloaded,
+            (
+                false,
+                Some("gpt-cli".to_string()),
+                Some(ReasoningEffort::High)
+            )
+        );
+    }
 }
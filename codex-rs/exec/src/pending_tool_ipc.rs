@@ -1,20 +1,32 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::future::Future;
 use std::net::IpAddr;
 use std::net::SocketAddr;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 use anyhow::Context;
 use codex_core::CodexConversation;
 use codex_core::protocol::Op;
 use codex_protocol::ConversationId;
 use codex_protocol::models::FunctionCallOutputPayload;
+use codex_protocol::protocol::PendingToolStateEvent;
+use codex_protocol::protocol::PendingToolStatus;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
 use serde_json::json;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncRead;
 use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWrite;
 use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
 use tokio::net::TcpListener;
 use tokio::net::TcpStream;
 use tokio::select;
@@ -23,18 +35,148 @@ use tokio::sync::oneshot;
 #[derive(Deserialize, Serialize)]
 struct DeliverPendingRequest {
     call_id: String,
+    /// Appended to any chunks sent with `append`; may be omitted when those
+    /// already hold the whole result.
+    #[serde(default)]
     output: FunctionCallOutputPayload,
+    /// Resending a delivery with the same key for the same `call_id` returns
+    /// the recorded status instead of submitting the result again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    idempotency_key: Option<String>,
+    /// Announce `output` as interim progress and keep the call pending.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    keep_pending: bool,
 }
 
+/// Params of the framed `append` method: part of a pending call's output,
+/// buffered until the call is finished with `finish` (or `deliver`).
+#[derive(Deserialize)]
+struct AppendPendingRequest {
+    call_id: String,
+    chunk: String,
+}
+
+/// Body of a `<call_id>.json` file dropped into the delivery directory. The
+/// call id defaults to the file stem.
+#[derive(Deserialize)]
+struct DeliveryFile {
+    #[serde(default)]
+    call_id: Option<String>,
+    output: FunctionCallOutputPayload,
+    #[serde(default)]
+    idempotency_key: Option<String>,
+    #[serde(default)]
+    keep_pending: bool,
+}
+
+/// How often the delivery directory is scanned for new files.
+const DELIVERY_DIR_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Status written back to the client once a delivery has been submitted.
+const DELIVERED_STATUS: &str = "ok";
+
+/// Keyed deliveries already submitted, by `call_id`.
+#[derive(Default)]
+struct DeliveryLog {
+    delivered: tokio::sync::Mutex<HashMap<String, (String, &'static str)>>,
+}
+
+impl DeliveryLog {
+    /// Run `submit` for `request` unless a delivery with the same call id and
+    /// idempotency key already succeeded. The lock is held across `submit` so
+    /// concurrent duplicates cannot both get through.
+    async fn deliver<F, Fut>(
+        &self,
+        request: DeliverPendingRequest,
+        submit: F,
+    ) -> anyhow::Result<&'static str>
+    where
+        F: FnOnce(Op) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>>,
+    {
+        let mut delivered = self.delivered.lock().await;
+        if let Some(key) = &request.idempotency_key
+            && let Some((recorded_key, status)) = delivered.get(&request.call_id)
+            && recorded_key == key
+        {
+            tracing::info!(
+                call_id = %request.call_id,
+                "ignoring duplicate pending tool delivery"
+            );
+            return Ok(*status);
+        }
+
+        submit(Op::DeliverPendingToolResult {
+            call_id: request.call_id.clone(),
+            output: request.output,
+            keep_pending: request.keep_pending,
+        })
+        .await?;
+        if let Some(key) = request.idempotency_key {
+            delivered.insert(request.call_id, (key, DELIVERED_STATUS));
+        }
+        Ok(DELIVERED_STATUS)
+    }
+}
+
+/// One newline-delimited JSON-RPC 2.0 request. A connection whose first line
+/// parses as one of these stays open for further requests; anything else is
+/// read to EOF as a single legacy [`DeliverPendingRequest`].
+#[derive(Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Calls currently waiting on an external result, by `call_id`, with their
+/// tool names. Kept up to date from `PendingToolState` events.
+type PendingCalls = Arc<std::sync::Mutex<BTreeMap<String, String>>>;
+
+/// Fixed `host:port` for the pending tool listener. Useful in CI where the
+/// `live/` metadata file may not be discoverable by the delivering process.
+pub const PENDING_TOOL_BIND_ENV_VAR: &str = "CODEX_PENDING_TOOL_BIND";
+
+/// Exported to tool subprocesses with the address the listener is bound to.
+pub const PENDING_TOOL_ADDR_ENV_VAR: &str = "CODEX_PENDING_TOOL_ADDR";
+
 pub struct PendingToolServer {
     shutdown_tx: Option<oneshot::Sender<()>>,
     metadata_path: PathBuf,
+    deliver_dir: PathBuf,
+    pending_calls: PendingCalls,
 }
 
-#[derive(Debug, Deserialize)]
+/// Contents of `live/<conversation_id>.json`.
+#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct PendingToolSocketMetadata {
     pub host: String,
     pub port: u16,
+    /// PID of the `codex exec` process that owns the listener, so a client
+    /// that finds the file after a crash can probe whether it is still alive
+    /// (e.g. with `kill -0`). Missing in files written by older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    /// When the listener started, in seconds since the Unix epoch. Guards
+    /// against the PID having been reused by an unrelated process.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+}
+
+impl PendingToolSocketMetadata {
+    fn for_current_process(addr: SocketAddr) -> Self {
+        Self {
+            host: addr.ip().to_string(),
+            port: addr.port(),
+            pid: Some(std::process::id()),
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|elapsed| elapsed.as_secs()),
+        }
+    }
 }
 
 pub fn metadata_path_for(codex_home: &Path, conversation_id: &ConversationId) -> PathBuf {
@@ -43,29 +185,65 @@ pub fn metadata_path_for(codex_home: &Path, conversation_id: &ConversationId) ->
         .join(format!("{conversation_id}.json"))
 }
 
+/// Directory watched for file-based deliveries: `live/<conversation_id>.deliver/`.
+pub fn deliver_dir_for(codex_home: &Path, conversation_id: &ConversationId) -> PathBuf {
+    codex_home
+        .join("live")
+        .join(format!("{conversation_id}.deliver"))
+}
+
+/// Resolve the address to bind from the value of `CODEX_PENDING_TOOL_BIND`,
+/// defaulting to an ephemeral loopback port.
+pub fn bind_addr_from_env_value(value: Option<&str>) -> anyhow::Result<SocketAddr> {
+    match value.map(str::trim).filter(|value| !value.is_empty()) {
+        Some(value) => value
+            .parse()
+            .with_context(|| format!("invalid {PENDING_TOOL_BIND_ENV_VAR} address `{value}`")),
+        None => Ok(SocketAddr::from(([127, 0, 0, 1], 0))),
+    }
+}
+
+pub async fn bind_listener(addr: SocketAddr) -> anyhow::Result<TcpListener> {
+    TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to bind pending tool listener on {addr}"))
+}
+
 impl PendingToolServer {
     pub async fn start(
+        listener: TcpListener,
         codex_home: &Path,
         conversation_id: &ConversationId,
         conversation: Arc<CodexConversation>,
     ) -> anyhow::Result<Self> {
-        let listener = TcpListener::bind(("127.0.0.1", 0))
-            .await
-            .context("failed to bind pending tool listener")?;
         let addr = listener
             .local_addr()
             .context("listener missing local addr")?;
         let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+        let deliveries = Arc::new(DeliveryLog::default());
+        let pending_calls = PendingCalls::default();
+        let accepted_pending_calls = Arc::clone(&pending_calls);
 
         let metadata_path = metadata_path_for(codex_home, conversation_id);
-        if let Some(parent) = metadata_path.parent() {
-            tokio::fs::create_dir_all(parent).await?;
+        // The metadata file is only a discovery aid; clients can still reach
+        // the listener through `CODEX_PENDING_TOOL_ADDR` when it can't be written.
+        if let Err(err) = write_metadata(&metadata_path, addr).await {
+            tracing::warn!(
+                "failed to write pending tool metadata {}: {err:?}",
+                metadata_path.display()
+            );
         }
-        let metadata = json!({
-            "host": addr.ip().to_string(),
-            "port": addr.port(),
-        });
-        tokio::fs::write(&metadata_path, serde_json::to_vec(&metadata)?).await?;
+
+        let deliver_dir = deliver_dir_for(codex_home, conversation_id);
+        if let Err(err) = tokio::fs::create_dir_all(&deliver_dir).await {
+            tracing::warn!(
+                "failed to create pending tool delivery dir {}: {err:?}",
+                deliver_dir.display()
+            );
+        }
+        let watched_dir = deliver_dir.clone();
+        let mut poll = tokio::time::interval(DELIVERY_DIR_POLL_INTERVAL);
+        poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
         tokio::spawn(async move {
             loop {
@@ -73,12 +251,31 @@ impl PendingToolServer {
                     _ = &mut shutdown_rx => {
                         break;
                     }
+                    _ = poll.tick() => {
+                        let submit = |op: Op| {
+                            let convo = Arc::clone(&conversation);
+                            async move {
+                                convo.submit(op).await?;
+                                anyhow::Ok(())
+                            }
+                        };
+                        ingest_delivery_dir(&watched_dir, &deliveries, submit).await;
+                    }
                     accept_result = listener.accept() => {
                         match accept_result {
                             Ok((stream, _)) => {
                                 let convo = Arc::clone(&conversation);
+                                let deliveries = Arc::clone(&deliveries);
+                                let pending_calls = Arc::clone(&accepted_pending_calls);
                                 tokio::spawn(async move {
-                                    if let Err(err) = handle_connection(stream, convo).await {
+                                    let submit = |op: Op| {
+                                        let convo = Arc::clone(&convo);
+                                        async move {
+                                            convo.submit(op).await?;
+                                            anyhow::Ok(())
+                                        }
+                                    };
+                                    if let Err(err) = handle_connection(stream, &deliveries, &pending_calls, submit).await {
                                         tracing::warn!("pending tool IPC error: {err:?}");
                                     }
                                 });
@@ -95,8 +292,35 @@ impl PendingToolServer {
         Ok(Self {
             shutdown_tx: Some(shutdown_tx),
             metadata_path,
+            deliver_dir,
+            pending_calls,
         })
     }
+
+    /// Track which calls are waiting so framed `list` requests can report them.
+    pub fn record_pending_state(&self, event: &PendingToolStateEvent) {
+        let mut pending = self
+            .pending_calls
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        match event.status {
+            PendingToolStatus::Waiting => {
+                pending.insert(event.call_id.clone(), event.tool_name.clone());
+            }
+            PendingToolStatus::Resolved | PendingToolStatus::Cancelled => {
+                pending.remove(&event.call_id);
+            }
+        }
+    }
+}
+
+async fn write_metadata(metadata_path: &Path, addr: SocketAddr) -> anyhow::Result<()> {
+    if let Some(parent) = metadata_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let metadata = PendingToolSocketMetadata::for_current_process(addr);
+    tokio::fs::write(metadata_path, serde_json::to_vec(&metadata)?).await?;
+    Ok(())
 }
 
 impl Drop for PendingToolServer {
@@ -105,45 +329,206 @@ impl Drop for PendingToolServer {
             let _ = tx.send(());
         }
         let path = self.metadata_path.clone();
-        // Best-effort cleanup.
+        // Best-effort cleanup; a delivery directory that still holds files is
+        // left for inspection.
         let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_dir(&self.deliver_dir);
     }
 }
 
-async fn handle_connection(
-    mut stream: TcpStream,
-    conversation: Arc<CodexConversation>,
-) -> anyhow::Result<()> {
-    let mut buf = Vec::new();
-    stream.read_to_end(&mut buf).await?;
-    if buf.is_empty() {
+/// Submit every `*.json` delivery file in `dir` (in file-name order) and
+/// delete the ones that were submitted. Files that cannot be parsed are
+/// renamed to `<name>.invalid` so they are not retried; files whose
+/// submission fails stay in place for the next scan.
+async fn ingest_delivery_dir<F, Fut>(dir: &Path, deliveries: &DeliveryLog, submit: F)
+where
+    F: Fn(Op) -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return;
+    };
+    let mut files = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    for path in files {
+        let request = match read_delivery_file(&path).await {
+            Ok(request) => request,
+            Err(err) => {
+                tracing::warn!(
+                    "invalid pending tool delivery file {}: {err:#}",
+                    path.display()
+                );
+                let mut rejected = path.clone().into_os_string();
+                rejected.push(".invalid");
+                let _ = tokio::fs::rename(&path, rejected).await;
+                continue;
+            }
+        };
+        match deliveries.deliver(request, &submit).await {
+            Ok(_) => {
+                if let Err(err) = tokio::fs::remove_file(&path).await {
+                    tracing::warn!(
+                        "failed to remove delivered file {}: {err:?}",
+                        path.display()
+                    );
+                }
+            }
+            Err(err) => {
+                tracing::warn!(
+                    "failed to submit pending tool delivery {}: {err:#}",
+                    path.display()
+                );
+            }
+        }
+    }
+}
+
+async fn read_delivery_file(path: &Path) -> anyhow::Result<DeliverPendingRequest> {
+    let contents = tokio::fs::read(path).await?;
+    let file: DeliveryFile = serde_json::from_slice(&contents)?;
+    let call_id = match file.call_id {
+        Some(call_id) => call_id,
+        None => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .context("delivery file has no name to take the call id from")?,
+    };
+    Ok(DeliverPendingRequest {
+        call_id,
+        output: file.output,
+        idempotency_key: file.idempotency_key,
+        keep_pending: file.keep_pending,
+    })
+}
+
+async fn handle_connection<S, F, Fut>(
+    stream: S,
+    deliveries: &DeliveryLog,
+    pending_calls: &PendingCalls,
+    submit: F,
+) -> anyhow::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    F: Fn(Op) -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+    if line.trim().is_empty() {
         return Ok(());
     }
-    let request: DeliverPendingRequest = serde_json::from_slice(&buf)?;
-    conversation
-        .submit(Op::DeliverPendingToolResult {
-            call_id: request.call_id,
-            output: request.output,
-        })
-        .await?;
-    stream.write_all(b"ok").await?;
-    Ok(())
+
+    if serde_json::from_str::<RpcRequest>(line.trim()).is_err() {
+        // Legacy clients send one request and close their write half.
+        let mut buf = line.into_bytes();
+        reader.read_to_end(&mut buf).await?;
+        let request: DeliverPendingRequest = serde_json::from_slice(&buf)?;
+        let status = deliveries.deliver(request, &submit).await?;
+        reader.get_mut().write_all(status.as_bytes()).await?;
+        return Ok(());
+    }
+
+    loop {
+        if !line.trim().is_empty() {
+            let response = match serde_json::from_str::<RpcRequest>(line.trim()) {
+                Ok(request) => {
+                    let id = request.id.clone();
+                    match handle_rpc_request(request, deliveries, pending_calls, &submit).await {
+                        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                        Err(err) => json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "error": { "message": format!("{err:#}") },
+                        }),
+                    }
+                }
+                Err(err) => json!({
+                    "jsonrpc": "2.0",
+                    "id": Value::Null,
+                    "error": { "message": format!("invalid request: {err}") },
+                }),
+            };
+            let mut encoded = serde_json::to_vec(&response)?;
+            encoded.push(b'\n');
+            reader.get_mut().write_all(&encoded).await?;
+        }
+
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(());
+        }
+    }
 }
 
+async fn handle_rpc_request<F, Fut>(
+    request: RpcRequest,
+    deliveries: &DeliveryLog,
+    pending_calls: &PendingCalls,
+    submit: F,
+) -> anyhow::Result<Value>
+where
+    F: FnOnce(Op) -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    match request.method.as_str() {
+        "ping" => Ok(json!("pong")),
+        "list" => {
+            let pending = pending_calls
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            Ok(pending
+                .iter()
+                .map(|(call_id, tool_name)| json!({ "call_id": call_id, "tool_name": tool_name }))
+                .collect())
+        }
+        "append" => {
+            let AppendPendingRequest { call_id, chunk } =
+                serde_json::from_value(request.params).context("invalid append params")?;
+            submit(Op::AppendPendingToolOutput { call_id, chunk }).await?;
+            Ok(json!(DELIVERED_STATUS))
+        }
+        "deliver" | "finish" => {
+            let request: DeliverPendingRequest =
+                serde_json::from_value(request.params).context("invalid deliver params")?;
+            let status = deliveries.deliver(request, submit).await?;
+            Ok(json!(status))
+        }
+        other => anyhow::bail!("unknown method `{other}`"),
+    }
+}
+
+/// Deliver `output` for `call_id` and return the status reported by the
+/// listener.
 pub async fn send_pending_result(
     addr: SocketAddr,
     call_id: String,
     output: FunctionCallOutputPayload,
-) -> anyhow::Result<()> {
+    idempotency_key: Option<String>,
+    keep_pending: bool,
+) -> anyhow::Result<String> {
     let mut stream = TcpStream::connect(addr)
         .await
         .context("failed to connect to pending tool listener")?;
-    let request = DeliverPendingRequest { call_id, output };
+    let request = DeliverPendingRequest {
+        call_id,
+        output,
+        idempotency_key,
+        keep_pending,
+    };
     let body = serde_json::to_vec(&request)?;
     stream.write_all(&body).await?;
+    stream.shutdown().await?;
     let mut buf = Vec::new();
     stream.read_to_end(&mut buf).await?;
-    Ok(())
+    Ok(String::from_utf8_lossy(&buf).into_owned())
 }
 
 pub fn addr_from_metadata(meta: PendingToolSocketMetadata) -> anyhow::Result<SocketAddr> {
@@ -155,3 +540,269 @@ pub fn load_metadata(contents: Value) -> anyhow::Result<PendingToolSocketMetadat
     let meta: PendingToolSocketMetadata = serde_json::from_value(contents)?;
     Ok(meta)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn defaults_to_ephemeral_loopback_port() {
+        assert_eq!(
+            bind_addr_from_env_value(None).unwrap(),
+            SocketAddr::from(([127, 0, 0, 1], 0))
+        );
+        assert_eq!(
+            bind_addr_from_env_value(Some("  ")).unwrap(),
+            SocketAddr::from(([127, 0, 0, 1], 0))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_bind_address() {
+        assert!(bind_addr_from_env_value(Some("not-an-addr")).is_err());
+    }
+
+    #[tokio::test]
+    async fn metadata_records_owning_pid() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("live").join("session.json");
+        let addr = SocketAddr::from(([127, 0, 0, 1], 4242));
+
+        write_metadata(&path, addr).await.expect("write metadata");
+
+        let contents: Value =
+            serde_json::from_slice(&std::fs::read(&path).expect("read metadata")).expect("json");
+        let metadata = load_metadata(contents).expect("parse metadata");
+        assert_eq!(
+            (metadata.pid, addr_from_metadata(metadata).expect("addr")),
+            (Some(std::process::id()), addr)
+        );
+    }
+
+    #[test]
+    fn metadata_without_pid_still_loads() {
+        let metadata = load_metadata(json!({ "host": "127.0.0.1", "port": 4242 }))
+            .expect("parse legacy metadata");
+        assert_eq!(
+            metadata,
+            PendingToolSocketMetadata {
+                host: "127.0.0.1".to_string(),
+                port: 4242,
+                pid: None,
+                started_at: None,
+            }
+        );
+    }
+
+    fn deliver_op(call_id: &str, content: &str) -> Op {
+        Op::DeliverPendingToolResult {
+            call_id: call_id.to_string(),
+            output: FunctionCallOutputPayload {
+                content: content.to_string(),
+                ..Default::default()
+            },
+            keep_pending: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn duplicate_keyed_delivery_submits_once() {
+        let deliveries = DeliveryLog::default();
+        let submitted = std::sync::Mutex::new(Vec::new());
+        let request = || DeliverPendingRequest {
+            call_id: "call-1".to_string(),
+            output: FunctionCallOutputPayload {
+                content: "done".to_string(),
+                ..Default::default()
+            },
+            idempotency_key: Some("retry-abc".to_string()),
+            keep_pending: false,
+        };
+
+        let mut statuses = Vec::new();
+        for _ in 0..2 {
+            let status = deliveries
+                .deliver(request(), |op| {
+                    submitted.lock().unwrap().push(op);
+                    async { Ok(()) }
+                })
+                .await
+                .unwrap();
+            statuses.push(status);
+        }
+
+        assert_eq!(statuses, vec![DELIVERED_STATUS, DELIVERED_STATUS]);
+        assert_eq!(
+            submitted.into_inner().unwrap(),
+            vec![deliver_op("call-1", "done")]
+        );
+    }
+
+    #[tokio::test]
+    async fn framed_requests_share_one_connection() {
+        let deliveries = DeliveryLog::default();
+        let pending_calls = PendingCalls::default();
+        pending_calls
+            .lock()
+            .unwrap()
+            .insert("call-1".to_string(), "wait_for_email".to_string());
+        let submitted = std::sync::Mutex::new(Vec::new());
+        let (client, server) = tokio::io::duplex(4096);
+
+        let serve = handle_connection(server, &deliveries, &pending_calls, |op| {
+            submitted.lock().unwrap().push(op);
+            async { Ok(()) }
+        });
+        let exchange = async {
+            let (read_half, mut write_half) = tokio::io::split(client);
+            let requests = [
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" }),
+                json!({ "jsonrpc": "2.0", "id": 2, "method": "list" }),
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 3,
+                    "method": "deliver",
+                    "params": { "call_id": "call-1", "output": "done" },
+                }),
+            ];
+            for request in &requests {
+                let mut line = serde_json::to_vec(request).unwrap();
+                line.push(b'\n');
+                write_half.write_all(&line).await.unwrap();
+            }
+            let mut lines = BufReader::new(read_half).lines();
+            let mut responses = Vec::new();
+            for _ in 0..requests.len() {
+                let line = lines.next_line().await.unwrap().unwrap();
+                responses.push(serde_json::from_str::<Value>(&line).unwrap());
+            }
+            write_half.shutdown().await.unwrap();
+            responses
+        };
+        let (served, responses) = tokio::join!(serve, exchange);
+        served.unwrap();
+
+        assert_eq!(
+            responses,
+            vec![
+                json!({ "jsonrpc": "2.0", "id": 1, "result": "pong" }),
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 2,
+                    "result": [{ "call_id": "call-1", "tool_name": "wait_for_email" }],
+                }),
+                json!({ "jsonrpc": "2.0", "id": 3, "result": DELIVERED_STATUS }),
+            ]
+        );
+        assert_eq!(
+            submitted.into_inner().unwrap(),
+            vec![deliver_op("call-1", "done")]
+        );
+    }
+
+    #[tokio::test]
+    async fn framed_append_then_finish_streams_output() {
+        let deliveries = DeliveryLog::default();
+        let pending_calls = PendingCalls::default();
+        let submitted = std::sync::Mutex::new(Vec::new());
+        let (client, server) = tokio::io::duplex(4096);
+
+        let serve = handle_connection(server, &deliveries, &pending_calls, |op| {
+            submitted.lock().unwrap().push(op);
+            async { Ok(()) }
+        });
+        let exchange = async {
+            let (read_half, mut write_half) = tokio::io::split(client);
+            let requests = [
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "append",
+                    "params": { "call_id": "call-1", "chunk": "step 1 ok\n" },
+                }),
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 2,
+                    "method": "append",
+                    "params": { "call_id": "call-1", "chunk": "step 2 ok\n" },
+                }),
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 3,
+                    "method": "finish",
+                    "params": { "call_id": "call-1" },
+                }),
+            ];
+            for request in &requests {
+                let mut line = serde_json::to_vec(request).unwrap();
+                line.push(b'\n');
+                write_half.write_all(&line).await.unwrap();
+            }
+            let mut lines = BufReader::new(read_half).lines();
+            for _ in 0..requests.len() {
+                lines.next_line().await.unwrap().unwrap();
+            }
+            write_half.shutdown().await.unwrap();
+        };
+        let (served, ()) = tokio::join!(serve, exchange);
+        served.unwrap();
+
+        assert_eq!(
+            submitted.into_inner().unwrap(),
+            vec![
+                Op::AppendPendingToolOutput {
+                    call_id: "call-1".to_string(),
+                    chunk: "step 1 ok\n".to_string(),
+                },
+                Op::AppendPendingToolOutput {
+                    call_id: "call-1".to_string(),
+                    chunk: "step 2 ok\n".to_string(),
+                },
+                deliver_op("call-1", ""),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn dropped_delivery_file_is_submitted_and_removed() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let delivery = dir.path().join("call-1.json");
+        std::fs::write(&delivery, r#"{"output": "done"}"#).expect("write delivery");
+        std::fs::write(dir.path().join("call-2.json.tmp"), "{").expect("write partial");
+        let deliveries = DeliveryLog::default();
+        let submitted = std::sync::Mutex::new(Vec::new());
+
+        ingest_delivery_dir(dir.path(), &deliveries, |op| {
+            submitted.lock().unwrap().push(op);
+            async { Ok(()) }
+        })
+        .await;
+
+        assert_eq!(
+            submitted.into_inner().unwrap(),
+            vec![deliver_op("call-1", "done")]
+        );
+        let mut remaining: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        remaining.sort();
+        assert_eq!(remaining, vec!["call-2.json.tmp".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn binds_requested_address() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let requested = format!("127.0.0.1:{port}");
+
+        let addr = bind_addr_from_env_value(Some(&requested)).unwrap();
+        let listener = bind_listener(addr).await.unwrap();
+
+        assert_eq!(listener.local_addr().unwrap(), addr);
+    }
+}
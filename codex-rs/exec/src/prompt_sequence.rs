@@ -1,16 +1,48 @@
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::Context;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use codex_core::default_client::build_reqwest_client;
+use codex_protocol::protocol::AskForApproval;
 use codex_protocol::user_input::UserInput;
+use regex_lite::Regex;
 use serde::Deserialize;
+use tracing::warn;
 
 #[derive(Debug, Clone)]
 pub struct PromptSequenceRunner {
     steps: Vec<PromptSequenceStep>,
     current: usize,
+    /// Steps whose turn finished, plus steps skipped as empty.
+    completed: usize,
+    /// Whether the last entry handed out is still running.
+    in_flight: bool,
     source: PathBuf,
+    variables: HashMap<String, String>,
+    /// Final messages of finished steps that set `capture`, keyed by name.
+    captured: HashMap<String, String>,
+    error_policy: StepErrorPolicy,
+    /// Times the in-flight step has been handed out again after failing.
+    retries: u32,
+}
+
+/// What the driver does when a step's turn ends in an error, set with the
+/// sequence-level `on_error` key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StepErrorPolicy {
+    /// Stop the sequence and skip the remaining steps.
+    #[default]
+    Abort,
+    /// Move on to the next step.
+    Continue,
+    /// Run the failed step again, at most `max_retries` times, then abort.
+    Retry { max_retries: u32 },
 }
 
 #[derive(Debug, Clone)]
@@ -19,14 +51,141 @@ pub struct PromptSequenceEntry {
     pub description: String,
     pub index: usize,
     pub total: usize,
+    /// Approval policy for this step's turn; `None` keeps the session default.
+    pub approval: Option<AskForApproval>,
+    /// Maximum time the step's turn may run before it is cancelled.
+    pub timeout: Option<Duration>,
+    /// Conditions the turn's final agent message must satisfy; the driver
+    /// aborts the sequence when one does not hold.
+    pub expectations: Vec<StepExpectation>,
+}
+
+/// A success condition declared on a step (`expect_contains` or
+/// `expect_regex`), checked against the final message of the step's turn.
+#[derive(Debug, Clone)]
+pub enum StepExpectation {
+    Contains(String),
+    Regex(Regex),
+}
+
+impl StepExpectation {
+    pub fn is_met(&self, message: &str) -> bool {
+        match self {
+            Self::Contains(needle) => message.contains(needle.as_str()),
+            Self::Regex(regex) => regex.is_match(message),
+        }
+    }
+}
+
+impl PartialEq for StepExpectation {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Contains(a), Self::Contains(b)) => a == b,
+            (Self::Regex(a), Self::Regex(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for StepExpectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Contains(needle) => write!(f, "contain `{needle}`"),
+            Self::Regex(regex) => write!(f, "match /{}/", regex.as_str()),
+        }
+    }
 }
 
 impl PromptSequenceRunner {
     pub fn load(path: &Path) -> anyhow::Result<Self> {
         let data = fs::read_to_string(path)
             .with_context(|| format!("failed to read prompt-sequence {}", path.display()))?;
-        let sequence: PromptSequenceToml = toml::from_str(&data)
-            .with_context(|| format!("invalid prompt-sequence {}", path.display()))?;
+        let base_dir = path
+            .parent()
+            .map(std::path::Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        Self::parse(&data, path.to_path_buf(), &base_dir)
+    }
+
+    /// Fetch a sequence from an `http(s)://` URL. Remote attachments are not
+    /// fetched: attachment paths resolve against the local `base_dir`. A URL
+    /// whose path ends in `.json` is parsed as JSON, anything else as TOML.
+    pub async fn load_url(url: &str, base_dir: &Path) -> anyhow::Result<Self> {
+        let response = build_reqwest_client()
+            .get(url)
+            .send()
+            .await
+            .with_context(|| format!("failed to fetch prompt-sequence {url}"))?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("failed to fetch prompt-sequence {url}: server returned {status}");
+        }
+        let data = response
+            .text()
+            .await
+            .with_context(|| format!("failed to read prompt-sequence {url}"))?;
+        Self::parse(&data, PathBuf::from(url), base_dir)
+    }
+
+    /// Load the sequence at `path` and check it without running any step. See
+    /// [`PromptSequenceRunner::validate_steps`].
+    pub fn validate(path: &Path) -> anyhow::Result<Vec<String>> {
+        Self::load(path)?.validate_steps()
+    }
+
+    /// Check what loading alone does not: image attachments exist and every
+    /// `{{name}}` placeholder names a `[variables]` entry or an earlier
+    /// capture. Returns the step descriptions, in order.
+    pub fn validate_steps(&self) -> anyhow::Result<Vec<String>> {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let description = step_description(step, index);
+                for attachment in step.attachments.iter().flatten() {
+                    if let StepAttachment::Image(path) = attachment
+                        && !path.is_file()
+                    {
+                        anyhow::bail!(
+                            "prompt-sequence {} step {description} attaches {}, which does not exist",
+                            self.source.display(),
+                            path.display()
+                        );
+                    }
+                }
+                for name in placeholder_names(&step.prompt) {
+                    if !name.starts_with(CAPTURED_PREFIX) && !self.variables.contains_key(name) {
+                        anyhow::bail!(
+                            "prompt-sequence {} step {description} references `{{{{{name}}}}}`, which is not defined under [variables]",
+                            self.source.display()
+                        );
+                    }
+                }
+                Ok(description)
+            })
+            .collect()
+    }
+
+    /// Whether `source` names a sequence to fetch with
+    /// [`PromptSequenceRunner::load_url`] rather than a local file.
+    pub fn is_url(source: &Path) -> bool {
+        source
+            .to_str()
+            .is_some_and(|source| source.starts_with("http://") || source.starts_with("https://"))
+    }
+
+    fn parse(data: &str, source: PathBuf, base_dir: &Path) -> anyhow::Result<Self> {
+        let path = source.as_path();
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let sequence: PromptSequenceToml = if is_json {
+            serde_json::from_str(data)
+                .with_context(|| format!("invalid prompt-sequence {}", path.display()))?
+        } else {
+            toml::from_str(data)
+                .with_context(|| format!("invalid prompt-sequence {}", path.display()))?
+        };
         if sequence.steps.is_empty() {
             anyhow::bail!(
                 "prompt-sequence {} does not define any [[steps]] entries",
@@ -34,23 +193,41 @@ impl PromptSequenceRunner {
             );
         }
 
-        let base_dir = path
-            .parent()
-            .map(std::path::Path::to_path_buf)
-            .unwrap_or_else(|| PathBuf::from("."));
         let steps = sequence
             .steps
             .into_iter()
-            .map(|step| PromptSequenceStep::from_toml(step, &base_dir))
+            .map(|step| PromptSequenceStep::from_toml(step, base_dir))
             .collect::<anyhow::Result<Vec<_>>>()?;
+        check_captured_references(&steps, path)?;
+        let error_policy = match (sequence.on_error, sequence.max_retries) {
+            (None | Some(OnErrorToml::Abort), None) => StepErrorPolicy::Abort,
+            (Some(OnErrorToml::Continue), None) => StepErrorPolicy::Continue,
+            (Some(OnErrorToml::Retry), max_retries) => StepErrorPolicy::Retry {
+                max_retries: max_retries.unwrap_or(DEFAULT_MAX_RETRIES),
+            },
+            (_, Some(_)) => anyhow::bail!(
+                "prompt-sequence {} sets max_retries without on_error = \"retry\"",
+                path.display()
+            ),
+        };
 
         Ok(Self {
             steps,
             current: 0,
-            source: path.to_path_buf(),
+            completed: 0,
+            in_flight: false,
+            source,
+            variables: sequence.variables,
+            captured: HashMap::new(),
+            error_policy,
+            retries: 0,
         })
     }
 
+    pub fn error_policy(&self) -> StepErrorPolicy {
+        self.error_policy
+    }
+
     pub fn source(&self) -> &Path {
         &self.source
     }
@@ -59,38 +236,310 @@ impl PromptSequenceRunner {
         self.current < self.steps.len()
     }
 
+    /// Returns `(completed, total)`. Skipped steps count as completed.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.completed, self.steps.len())
+    }
+
+    /// Record that the turn for the most recent entry finished.
+    pub fn finish_step(&mut self) {
+        if self.in_flight {
+            self.in_flight = false;
+            self.completed += 1;
+        }
+    }
+
+    /// Store the in-flight step's final message under its `capture` name so
+    /// later prompts can use `{{captured.<name>}}`. Fails when the step
+    /// captures but its turn produced no final message.
+    pub fn capture_output(&mut self, last_agent_message: Option<&str>) -> anyhow::Result<()> {
+        if !self.in_flight {
+            return Ok(());
+        }
+        let Some(index) = self.current.checked_sub(1) else {
+            return Ok(());
+        };
+        let Some(step) = self.steps.get(index) else {
+            return Ok(());
+        };
+        let Some(name) = step.capture.clone() else {
+            return Ok(());
+        };
+        let Some(message) = last_agent_message else {
+            anyhow::bail!(
+                "prompt-sequence step {} ended without a final message to capture as `{name}`",
+                step_description(step, index)
+            );
+        };
+        self.captured.insert(name, message.to_string());
+        Ok(())
+    }
+
+    /// True once every step has been handed out and its turn has finished.
+    pub fn is_finished(&self) -> bool {
+        !self.has_remaining() && !self.in_flight
+    }
+
+    /// Returns the next step to submit. Steps whose prompt is blank after
+    /// `{{variable}}` substitution (and that carry no attachments) are skipped
+    /// with a warning so they don't produce an empty turn.
     pub fn next_entry(&mut self) -> Option<PromptSequenceEntry> {
-        let step = self.steps.get(self.current)?;
-        let index = self.current;
-        self.current += 1;
+        loop {
+            let index = self.current;
+            let step = self.steps.get(index)?;
+            self.current += 1;
 
-        let mut items: Vec<UserInput> = step
-            .attachments
+            let Some(entry) = self.build_entry(index) else {
+                warn!(
+                    "prompt-sequence step {}/{} ({}) is empty after substitution; skipping",
+                    index + 1,
+                    self.steps.len(),
+                    step_description(step, index)
+                );
+                self.completed += 1;
+                continue;
+            };
+
+            self.in_flight = true;
+            self.retries = 0;
+            return Some(entry);
+        }
+    }
+
+    /// Hand out the in-flight step again after its turn failed, if the
+    /// `on_error = "retry"` budget allows. Returns `None` under any other
+    /// policy or once `max_retries` is used up.
+    pub fn retry_entry(&mut self) -> Option<PromptSequenceEntry> {
+        let StepErrorPolicy::Retry { max_retries } = self.error_policy else {
+            return None;
+        };
+        if !self.in_flight || self.retries >= max_retries {
+            return None;
+        }
+        let entry = self.build_entry(self.current.checked_sub(1)?)?;
+        self.retries += 1;
+        Some(entry)
+    }
+
+    /// Build the entry for `index`, or `None` when its prompt is blank after
+    /// substitution and it has no attachments.
+    fn build_entry(&self, index: usize) -> Option<PromptSequenceEntry> {
+        let step = self.steps.get(index)?;
+        let mut variables = self.variables.clone();
+        variables.extend(
+            self.captured
+                .iter()
+                .map(|(name, value)| (format!("{CAPTURED_PREFIX}{name}"), value.clone())),
+        );
+        let prompt = substitute_variables(&step.prompt, &variables);
+        let attachments = step.attachments.as_deref().unwrap_or_default();
+        if prompt.trim().is_empty() && attachments.is_empty() {
+            return None;
+        }
+
+        let mut items: Vec<UserInput> = attachments
             .iter()
-            .flatten()
-            .map(|path| UserInput::LocalImage { path: path.clone() })
+            .map(StepAttachment::to_user_input)
             .collect();
-        items.push(UserInput::Text {
-            text: step.prompt.clone(),
-        });
+        if !prompt.trim().is_empty() {
+            items.push(UserInput::Text { text: prompt });
+        }
 
         Some(PromptSequenceEntry {
             items,
-            description: step
-                .name
-                .clone()
-                .unwrap_or_else(|| format!("Step {}", index + 1)),
+            description: step_description(step, index),
             index,
             total: self.steps.len(),
+            approval: step.approval,
+            timeout: step.timeout,
+            expectations: step.expectations.clone(),
         })
     }
 }
 
+fn step_description(step: &PromptSequenceStep, index: usize) -> String {
+    step.name
+        .clone()
+        .unwrap_or_else(|| format!("Step {}", index + 1))
+}
+
+/// Placeholder prefix for values captured from earlier steps.
+const CAPTURED_PREFIX: &str = "captured.";
+
+/// Reject `{{captured.<name>}}` placeholders that no earlier step captures,
+/// so a typo fails at load instead of after the first turns have run.
+fn check_captured_references(steps: &[PromptSequenceStep], source: &Path) -> anyhow::Result<()> {
+    let mut defined: Vec<&str> = Vec::new();
+    for (index, step) in steps.iter().enumerate() {
+        for name in placeholder_names(&step.prompt) {
+            if let Some(captured) = name.strip_prefix(CAPTURED_PREFIX)
+                && !defined.contains(&captured)
+            {
+                anyhow::bail!(
+                    "prompt-sequence {} step {} references `{{{{{name}}}}}`, but no earlier step sets capture = \"{captured}\"",
+                    source.display(),
+                    step_description(step, index)
+                );
+            }
+        }
+        if let Some(capture) = &step.capture {
+            defined.push(capture);
+        }
+    }
+    Ok(())
+}
+
+/// Names of the `{{name}}` placeholders in `prompt`, trimmed.
+fn placeholder_names(prompt: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = prompt;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        names.push(rest[start + 2..start + 2 + len].trim());
+        rest = &rest[start + len + 4..];
+    }
+    names
+}
+
+/// Replace `{{name}}` placeholders with values from `variables`. Unknown
+/// names are left untouched so typos stay visible in the submitted prompt.
+fn substitute_variables(prompt: &str, variables: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(prompt.len());
+    let mut rest = prompt;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + len].trim();
+        result.push_str(&rest[..start]);
+        match variables.get(name) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[start..start + len + 4]),
+        }
+        rest = &rest[start + len + 4..];
+    }
+    result.push_str(rest);
+    result
+}
+
 #[derive(Debug, Clone)]
 struct PromptSequenceStep {
     prompt: String,
     name: Option<String>,
-    attachments: Option<Vec<PathBuf>>,
+    attachments: Option<Vec<StepAttachment>>,
+    approval: Option<AskForApproval>,
+    timeout: Option<Duration>,
+    expectations: Vec<StepExpectation>,
+    capture: Option<String>,
+}
+
+/// An attachment resolved at load time. Text files are read eagerly so a
+/// missing or unreadable file fails the whole sequence before any turn runs.
+#[derive(Debug, Clone)]
+enum StepAttachment {
+    Image(PathBuf),
+    Text {
+        path: PathBuf,
+        contents: String,
+    },
+    /// Image embedded in the sequence file, kept as a `data:` URL.
+    InlineImage {
+        data_url: String,
+    },
+}
+
+impl StepAttachment {
+    const INLINE_IMAGE_MIME_TYPES: &[&str] =
+        &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+    fn load(toml: AttachmentToml, base_dir: &Path) -> anyhow::Result<Self> {
+        let (path, kind) = match toml {
+            AttachmentToml::Path(path) => (path, None),
+            AttachmentToml::Typed { path, kind } => (path, kind),
+            AttachmentToml::Inline { data, mime } => return Self::load_inline(&data, &mime),
+        };
+        let path = if path.is_absolute() {
+            path
+        } else {
+            base_dir.join(path)
+        };
+        let kind = match kind.as_deref() {
+            Some("image") => AttachmentKind::Image,
+            Some("text") => AttachmentKind::Text,
+            Some(other) => anyhow::bail!(
+                "unsupported type `{other}` for prompt-sequence attachment {} (use \"image\" or \"text\")",
+                path.display()
+            ),
+            None => AttachmentKind::infer(&path),
+        };
+        match kind {
+            AttachmentKind::Image => Ok(Self::Image(path)),
+            AttachmentKind::Text => {
+                let contents = fs::read_to_string(&path).with_context(|| {
+                    format!(
+                        "failed to read prompt-sequence text attachment {}",
+                        path.display()
+                    )
+                })?;
+                Ok(Self::Text { path, contents })
+            }
+        }
+    }
+
+    /// Validate an inline `{ data = "...", mime = "image/png" }` attachment.
+    /// Whitespace inside `data` is ignored so long payloads can be wrapped.
+    fn load_inline(data: &str, mime: &str) -> anyhow::Result<Self> {
+        let mime = mime.trim().to_ascii_lowercase();
+        if !Self::INLINE_IMAGE_MIME_TYPES.contains(&mime.as_str()) {
+            anyhow::bail!(
+                "unsupported MIME type `{mime}` for inline prompt-sequence attachment (use one of {})",
+                Self::INLINE_IMAGE_MIME_TYPES.join(", ")
+            );
+        }
+        let data: String = data.split_whitespace().collect();
+        BASE64_STANDARD
+            .decode(&data)
+            .context("inline prompt-sequence attachment is not valid base64")?;
+        Ok(Self::InlineImage {
+            data_url: format!("data:{mime};base64,{data}"),
+        })
+    }
+
+    fn to_user_input(&self) -> UserInput {
+        match self {
+            Self::Image(path) => UserInput::LocalImage { path: path.clone() },
+            Self::InlineImage { data_url } => UserInput::Image {
+                image_url: data_url.clone(),
+            },
+            Self::Text { path, contents } => UserInput::Text {
+                text: format!("Attached file `{}`:\n\n{contents}", path.display()),
+            },
+        }
+    }
+}
+
+impl AttachmentKind {
+    const TEXT_EXTENSIONS: &[&str] = &[
+        "txt", "md", "markdown", "json", "jsonl", "toml", "yaml", "yml", "csv", "log", "xml",
+        "html",
+    ];
+
+    /// Infer the attachment type from the file extension: common text files
+    /// are read as text, and any other path is attached as a local image.
+    fn infer(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+        if Self::TEXT_EXTENSIONS.contains(&extension.as_str()) {
+            Self::Text
+        } else {
+            Self::Image
+        }
+    }
 }
 
 impl PromptSequenceStep {
@@ -99,38 +548,758 @@ impl PromptSequenceStep {
             anyhow::bail!("prompt-sequence step is missing a prompt");
         }
 
-        let attachments = toml.attachments.map(|paths| {
-            paths
-                .into_iter()
-                .map(|path| {
-                    if path.is_absolute() {
-                        path
-                    } else {
-                        base_dir.join(path)
-                    }
-                })
-                .collect()
-        });
+        let attachments = toml
+            .attachments
+            .map(|attachments| {
+                attachments
+                    .into_iter()
+                    .map(|attachment| StepAttachment::load(attachment, base_dir))
+                    .collect::<anyhow::Result<Vec<_>>>()
+            })
+            .transpose()?;
+
+        let timeout = toml
+            .timeout
+            .as_deref()
+            .map(parse_step_timeout)
+            .transpose()?;
+
+        let mut expectations = Vec::new();
+        if let Some(needle) = toml.expect_contains {
+            expectations.push(StepExpectation::Contains(needle));
+        }
+        if let Some(pattern) = toml.expect_regex {
+            let regex = Regex::new(&pattern)
+                .with_context(|| format!("invalid prompt-sequence expect_regex `{pattern}`"))?;
+            expectations.push(StepExpectation::Regex(regex));
+        }
+
+        let capture = toml.capture.map(|name| name.trim().to_string());
+        if capture.as_deref().is_some_and(str::is_empty) {
+            anyhow::bail!("prompt-sequence step has an empty capture name");
+        }
 
         Ok(Self {
             prompt: toml.prompt,
             name: toml.name,
             attachments,
+            approval: toml.approval,
+            timeout,
+            expectations,
+            capture,
         })
     }
 }
 
+/// Parse a step timeout such as `"30s"`, `"5m"`, `"1h"`, or `"1500ms"`.
+fn parse_step_timeout(raw: &str) -> anyhow::Result<Duration> {
+    let trimmed = raw.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (amount, unit) = trimmed.split_at(split);
+    let amount: u64 = amount.parse().with_context(|| {
+        format!("invalid prompt-sequence timeout `{raw}` (expected e.g. \"30s\" or \"5m\")")
+    })?;
+    let duration = match unit.trim() {
+        "ms" => Duration::from_millis(amount),
+        "s" => Duration::from_secs(amount),
+        "m" => Duration::from_secs(amount.saturating_mul(60)),
+        "h" => Duration::from_secs(amount.saturating_mul(60 * 60)),
+        other => anyhow::bail!(
+            "invalid prompt-sequence timeout `{raw}`: unknown unit `{other}` (use ms, s, m, or h)"
+        ),
+    };
+    if duration.is_zero() {
+        anyhow::bail!("invalid prompt-sequence timeout `{raw}`: must be greater than zero");
+    }
+    Ok(duration)
+}
+
+/// Retries allowed by `on_error = "retry"` when `max_retries` is not set.
+const DEFAULT_MAX_RETRIES: u32 = 1;
+
 #[derive(Debug, Deserialize)]
 struct PromptSequenceToml {
+    #[serde(default)]
+    variables: HashMap<String, String>,
+    #[serde(default)]
+    on_error: Option<OnErrorToml>,
+    #[serde(default)]
+    max_retries: Option<u32>,
     #[serde(default)]
     steps: Vec<PromptSequenceStepToml>,
 }
 
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum OnErrorToml {
+    Abort,
+    Continue,
+    Retry,
+}
+
 #[derive(Debug, Deserialize)]
 struct PromptSequenceStepToml {
     prompt: String,
     #[serde(default)]
     name: Option<String>,
     #[serde(default)]
-    attachments: Option<Vec<PathBuf>>,
+    attachments: Option<Vec<AttachmentToml>>,
+    #[serde(default)]
+    approval: Option<AskForApproval>,
+    #[serde(default)]
+    timeout: Option<String>,
+    #[serde(default)]
+    expect_contains: Option<String>,
+    #[serde(default)]
+    expect_regex: Option<String>,
+    /// Store this step's final message for `{{captured.<name>}}` placeholders
+    /// in later steps.
+    #[serde(default)]
+    capture: Option<String>,
+}
+
+/// Either a bare path (type inferred from the extension),
+/// `{ path = "...", type = "image" | "text" }`, or an inline image
+/// `{ data = "<base64>", mime = "image/png" }`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AttachmentToml {
+    Path(PathBuf),
+    Typed {
+        path: PathBuf,
+        #[serde(rename = "type")]
+        kind: Option<String>,
+    },
+    Inline {
+        data: String,
+        mime: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum AttachmentKind {
+    Image,
+    Text,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn load_sequence(contents: &str) -> PromptSequenceRunner {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("sequence.toml");
+        fs::write(&path, contents).expect("write sequence");
+        PromptSequenceRunner::load(&path).expect("load sequence")
+    }
+
+    #[test]
+    fn entry_reflects_per_step_approval() {
+        let mut runner = load_sequence(
+            r#"
+[[steps]]
+prompt = "inspect"
+approval = "never"
+
+[[steps]]
+prompt = "deploy"
+"#,
+        );
+
+        let approvals: Vec<Option<AskForApproval>> =
+            std::iter::from_fn(|| runner.next_entry().map(|entry| entry.approval)).collect();
+        assert_eq!(approvals, vec![Some(AskForApproval::Never), None]);
+    }
+
+    #[test]
+    fn parses_on_error_policies() {
+        let policy = |header: &str| {
+            load_sequence(&format!("{header}\n[[steps]]\nprompt = \"build\"\n")).error_policy()
+        };
+
+        assert_eq!(
+            [
+                policy(""),
+                policy(r#"on_error = "abort""#),
+                policy(r#"on_error = "continue""#),
+                policy(r#"on_error = "retry""#),
+            ],
+            [
+                StepErrorPolicy::Abort,
+                StepErrorPolicy::Abort,
+                StepErrorPolicy::Continue,
+                StepErrorPolicy::Retry { max_retries: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn retry_policy_bounds_retries() {
+        let mut runner = load_sequence(
+            r#"
+on_error = "retry"
+max_retries = 2
+
+[[steps]]
+name = "Build"
+prompt = "build"
+
+[[steps]]
+prompt = "deploy"
+"#,
+        );
+        assert_eq!(
+            runner.error_policy(),
+            StepErrorPolicy::Retry { max_retries: 2 }
+        );
+
+        let first = runner.next_entry().expect("entry").index;
+        let retried: Vec<usize> =
+            std::iter::from_fn(|| runner.retry_entry().map(|entry| entry.index)).collect();
+        runner.finish_step();
+        let next = runner.next_entry().expect("entry").index;
+
+        assert_eq!((first, retried, next), (0, vec![0, 0], 1));
+        assert_eq!(
+            runner.retry_entry().map(|entry| entry.index),
+            Some(1),
+            "the retry budget resets for each step"
+        );
+    }
+
+    #[test]
+    fn rejects_max_retries_without_retry_policy() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("sequence.toml");
+        fs::write(
+            &path,
+            "on_error = \"continue\"\nmax_retries = 3\n\n[[steps]]\nprompt = \"build\"\n",
+        )
+        .expect("write sequence");
+
+        let err = PromptSequenceRunner::load(&path).expect_err("max_retries needs retry");
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "prompt-sequence {} sets max_retries without on_error = \"retry\"",
+                path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn parses_step_timeouts() {
+        let mut runner = load_sequence(
+            r#"
+[[steps]]
+prompt = "quick"
+timeout = "30s"
+
+[[steps]]
+prompt = "slow"
+timeout = "5m"
+
+[[steps]]
+prompt = "unbounded"
+"#,
+        );
+
+        let timeouts: Vec<Option<Duration>> =
+            std::iter::from_fn(|| runner.next_entry().map(|entry| entry.timeout)).collect();
+        assert_eq!(
+            timeouts,
+            vec![
+                Some(Duration::from_secs(30)),
+                Some(Duration::from_secs(300)),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_step_timeout() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("sequence.toml");
+        fs::write(
+            &path,
+            r#"
+[[steps]]
+prompt = "inspect"
+timeout = "5 minutes"
+"#,
+        )
+        .expect("write sequence");
+
+        let err = PromptSequenceRunner::load(&path).expect_err("invalid timeout");
+        assert_eq!(
+            err.to_string(),
+            "invalid prompt-sequence timeout `5 minutes`: unknown unit `minutes` (use ms, s, m, or h)"
+        );
+    }
+
+    #[test]
+    fn entry_carries_step_expectations() {
+        let mut runner = load_sequence(
+            r#"
+[[steps]]
+prompt = "run the tests"
+expect_contains = "ALL TESTS PASS"
+expect_regex = "passed: \\d+"
+
+[[steps]]
+prompt = "summarize"
+"#,
+        );
+
+        let expectations: Vec<Vec<StepExpectation>> =
+            std::iter::from_fn(|| runner.next_entry().map(|entry| entry.expectations)).collect();
+        assert_eq!(
+            expectations,
+            vec![
+                vec![
+                    StepExpectation::Contains("ALL TESTS PASS".to_string()),
+                    StepExpectation::Regex(Regex::new(r"passed: \d+").expect("regex")),
+                ],
+                Vec::new(),
+            ]
+        );
+        assert_eq!(
+            expectations[0]
+                .iter()
+                .map(|expectation| expectation.is_met("ALL TESTS PASS (passed: 12)"))
+                .collect::<Vec<_>>(),
+            vec![true, true]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_expect_regex() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("sequence.toml");
+        fs::write(
+            &path,
+            r#"
+[[steps]]
+prompt = "inspect"
+expect_regex = "(unclosed"
+"#,
+        )
+        .expect("write sequence");
+
+        let err = PromptSequenceRunner::load(&path).expect_err("invalid regex");
+        assert_eq!(
+            err.to_string(),
+            "invalid prompt-sequence expect_regex `(unclosed`"
+        );
+    }
+
+    #[test]
+    fn attachments_map_to_user_input_by_type() {
+        let dir = tempdir().expect("tempdir");
+        fs::write(dir.path().join("notes.md"), "# Notes\nship it").expect("write notes");
+        fs::write(dir.path().join("context.data"), "raw context").expect("write context");
+        let path = dir.path().join("sequence.toml");
+        fs::write(
+            &path,
+            r#"
+[[steps]]
+prompt = "review"
+attachments = [
+    "diagram.png",
+    "notes.md",
+    { path = "context.data", type = "text" },
+    "scan.heic",
+    "screenshot",
+]
+"#,
+        )
+        .expect("write sequence");
+
+        let entry = PromptSequenceRunner::load(&path)
+            .expect("load sequence")
+            .next_entry()
+            .expect("entry");
+        assert_eq!(
+            entry.items,
+            vec![
+                UserInput::LocalImage {
+                    path: dir.path().join("diagram.png"),
+                },
+                UserInput::Text {
+                    text: format!(
+                        "Attached file `{}`:\n\n# Notes\nship it",
+                        dir.path().join("notes.md").display()
+                    ),
+                },
+                UserInput::Text {
+                    text: format!(
+                        "Attached file `{}`:\n\nraw context",
+                        dir.path().join("context.data").display()
+                    ),
+                },
+                UserInput::LocalImage {
+                    path: dir.path().join("scan.heic"),
+                },
+                UserInput::LocalImage {
+                    path: dir.path().join("screenshot"),
+                },
+                UserInput::Text {
+                    text: "review".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_image_becomes_data_url() {
+        let mut runner = load_sequence(
+            r#"
+[[steps]]
+prompt = "describe"
+attachments = [{ data = "iVBORw0K\nGgo=", mime = "image/png" }]
+"#,
+        );
+
+        let entry = runner.next_entry().expect("entry");
+        assert_eq!(
+            entry.items,
+            vec![
+                UserInput::Image {
+                    image_url: "data:image/png;base64,iVBORw0KGgo=".to_string(),
+                },
+                UserInput::Text {
+                    text: "describe".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_inline_attachments() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("sequence.toml");
+        let load_err = |attachment: &str| {
+            fs::write(
+                &path,
+                format!("[[steps]]\nprompt = \"describe\"\nattachments = [{attachment}]\n"),
+            )
+            .expect("write sequence");
+            PromptSequenceRunner::load(&path)
+                .expect_err("invalid inline attachment")
+                .to_string()
+        };
+
+        assert_eq!(
+            load_err(r#"{ data = "not base64!", mime = "image/png" }"#),
+            "inline prompt-sequence attachment is not valid base64"
+        );
+        assert_eq!(
+            load_err(r#"{ data = "iVBORw0KGgo=", mime = "application/pdf" }"#),
+            "unsupported MIME type `application/pdf` for inline prompt-sequence attachment (use one of image/png, image/jpeg, image/gif, image/webp)"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_attachment_types() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("sequence.toml");
+        let load_err = |attachments: &str| {
+            fs::write(
+                &path,
+                format!("[[steps]]\nprompt = \"review\"\nattachments = [{attachments}]\n"),
+            )
+            .expect("write sequence");
+            PromptSequenceRunner::load(&path)
+                .expect_err("unsupported attachment")
+                .to_string()
+        };
+        let report = dir.path().join("report.pdf");
+
+        assert_eq!(
+            load_err(r#"{ path = "report.pdf", type = "pdf" }"#),
+            format!(
+                "unsupported type `pdf` for prompt-sequence attachment {} (use \"image\" or \"text\")",
+                report.display()
+            )
+        );
+    }
+
+    #[test]
+    fn validate_lists_step_descriptions() {
+        let dir = tempdir().expect("tempdir");
+        fs::write(dir.path().join("diagram.png"), b"png").expect("write image");
+        let path = dir.path().join("sequence.toml");
+        fs::write(
+            &path,
+            r#"
+[variables]
+target = "parser"
+
+[[steps]]
+name = "Plan"
+prompt = "plan the {{target}} fix"
+attachments = ["diagram.png"]
+capture = "plan"
+
+[[steps]]
+prompt = "apply {{captured.plan}}"
+"#,
+        )
+        .expect("write sequence");
+
+        assert_eq!(
+            PromptSequenceRunner::validate(&path).expect("valid sequence"),
+            vec!["Plan".to_string(), "Step 2".to_string()]
+        );
+    }
+
+    #[test]
+    fn validate_names_missing_image_attachment() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("sequence.toml");
+        fs::write(
+            &path,
+            "[[steps]]\nprompt = \"review\"\nattachments = [\"diagram.png\"]\n",
+        )
+        .expect("write sequence");
+
+        let err = PromptSequenceRunner::validate(&path).expect_err("missing attachment");
+
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "prompt-sequence {} step Step 1 attaches {}, which does not exist",
+                path.display(),
+                dir.path().join("diagram.png").display()
+            )
+        );
+    }
+
+    #[test]
+    fn progress_tracks_finished_steps() {
+        let mut runner = load_sequence(
+            r#"
+[variables]
+skip = ""
+
+[[steps]]
+prompt = "first"
+
+[[steps]]
+prompt = "{{skip}}"
+
+[[steps]]
+prompt = "last"
+"#,
+        );
+        let mut snapshots = vec![(runner.progress(), runner.is_finished())];
+        while let Some(_entry) = runner.next_entry() {
+            snapshots.push((runner.progress(), runner.is_finished()));
+            runner.finish_step();
+            snapshots.push((runner.progress(), runner.is_finished()));
+        }
+
+        assert_eq!(
+            snapshots,
+            vec![
+                ((0, 3), false),
+                ((0, 3), false),
+                ((1, 3), false),
+                ((2, 3), false),
+                ((3, 3), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn captured_output_is_substituted_into_later_steps() {
+        let mut runner = load_sequence(
+            r#"
+[[steps]]
+name = "Plan"
+prompt = "Outline the migration"
+capture = "plan"
+
+[[steps]]
+prompt = "Carry out this plan:\n{{ captured.plan }}"
+"#,
+        );
+
+        runner.next_entry().expect("plan entry");
+        runner
+            .capture_output(Some("1. add the column\n2. backfill"))
+            .expect("capture plan");
+        runner.finish_step();
+        let entry = runner.next_entry().expect("follow-up entry");
+
+        assert_eq!(
+            entry.items,
+            vec![UserInput::Text {
+                text: "Carry out this plan:\n1. add the column\n2. backfill".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn capture_fails_without_final_message() {
+        let mut runner = load_sequence(
+            r#"
+[[steps]]
+name = "Plan"
+prompt = "Outline the migration"
+capture = "plan"
+"#,
+        );
+
+        runner.next_entry().expect("entry");
+        let err = runner.capture_output(None).expect_err("nothing to capture");
+        assert_eq!(
+            err.to_string(),
+            "prompt-sequence step Plan ended without a final message to capture as `plan`"
+        );
+    }
+
+    #[test]
+    fn rejects_references_to_undefined_captures() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("sequence.toml");
+        fs::write(
+            &path,
+            r#"
+[[steps]]
+prompt = "Use {{captured.plan}}"
+
+[[steps]]
+prompt = "Outline the migration"
+capture = "plan"
+"#,
+        )
+        .expect("write sequence");
+
+        let err = PromptSequenceRunner::load(&path).expect_err("capture defined too late");
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "prompt-sequence {} step Step 1 references `{{{{captured.plan}}}}`, but no earlier step sets capture = \"plan\"",
+                path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn skips_steps_emptied_by_substitution() {
+        let mut runner = load_sequence(
+            r#"
+[variables]
+followup = ""
+topic = "rollouts"
+
+[[steps]]
+name = "Follow-up"
+prompt = "{{ followup }}"
+
+[[steps]]
+prompt = "Summarize {{topic}}"
+"#,
+        );
+
+        let entry = runner.next_entry().expect("entry");
+        assert_eq!(
+            (entry.index, entry.items),
+            (
+                1,
+                vec![UserInput::Text {
+                    text: "Summarize rollouts".to_string(),
+                }]
+            )
+        );
+        assert!(runner.next_entry().is_none());
+    }
+
+    #[tokio::test]
+    async fn loads_sequence_from_url_with_local_attachments() {
+        use wiremock::Mock;
+        use wiremock::MockServer;
+        use wiremock::ResponseTemplate;
+        use wiremock::matchers::method;
+        use wiremock::matchers::path;
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/sequences/release.toml"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"
+[[steps]]
+prompt = "review"
+attachments = ["notes.md"]
+
+[[steps]]
+name = "Ship"
+prompt = "deploy"
+"#,
+            ))
+            .mount(&server)
+            .await;
+        let base = tempdir().expect("tempdir");
+        fs::write(base.path().join("notes.md"), "ship it").expect("write notes");
+        let url = format!("{}/sequences/release.toml", server.uri());
+
+        let mut runner = PromptSequenceRunner::load_url(&url, base.path())
+            .await
+            .expect("load sequence");
+
+        assert_eq!(runner.source(), Path::new(&url));
+        let entries: Vec<(String, Vec<UserInput>)> = std::iter::from_fn(|| {
+            runner
+                .next_entry()
+                .map(|entry| (entry.description, entry.items))
+        })
+        .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (
+                    "Step 1".to_string(),
+                    vec![
+                        UserInput::Text {
+                            text: format!(
+                                "Attached file `{}`:\n\nship it",
+                                base.path().join("notes.md").display()
+                            ),
+                        },
+                        UserInput::Text {
+                            text: "review".to_string(),
+                        },
+                    ],
+                ),
+                (
+                    "Ship".to_string(),
+                    vec![UserInput::Text {
+                        text: "deploy".to_string(),
+                    }],
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn url_fetch_failure_names_the_status() {
+        use wiremock::MockServer;
+
+        let server = MockServer::start().await;
+        let url = format!("{}/missing.toml", server.uri());
+
+        let err = PromptSequenceRunner::load_url(&url, Path::new("."))
+            .await
+            .expect_err("missing sequence");
+
+        assert_eq!(
+            err.to_string(),
+            format!("failed to fetch prompt-sequence {url}: server returned 404 Not Found")
+        );
+    }
 }
@@ -354,6 +354,20 @@ pub struct ShellToolCallParams {
     pub justification: Option<String>,
 }
 
+impl ShellToolCallParams {
+    /// Build the params for a `local_shell` exec action. The action carries no
+    /// sandbox or justification hints, so those fall back to the defaults.
+    pub fn from_local_shell_exec(exec: LocalShellExecAction) -> Self {
+        Self {
+            command: exec.command,
+            workdir: exec.working_directory,
+            timeout_ms: exec.timeout_ms,
+            sandbox_permissions: Some(SandboxPermissions::UseDefault),
+            justification: None,
+        }
+    }
+}
+
 /// If the `name` of a `ResponseItem::FunctionCall` is `shell_command`, the
 /// `arguments` field should deserialize to this struct.
 #[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema, TS)]
@@ -771,6 +785,28 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn shell_tool_call_params_from_local_shell_exec() {
+        let exec = LocalShellExecAction {
+            command: vec!["ls".to_string(), "-l".to_string()],
+            timeout_ms: Some(2500),
+            working_directory: Some("/tmp".to_string()),
+            env: None,
+            user: None,
+        };
+
+        assert_eq!(
+            ShellToolCallParams {
+                command: vec!["ls".to_string(), "-l".to_string()],
+                workdir: Some("/tmp".to_string()),
+                timeout_ms: Some(2500),
+                sandbox_permissions: Some(SandboxPermissions::UseDefault),
+                justification: None,
+            },
+            ShellToolCallParams::from_local_shell_exec(exec)
+        );
+    }
+
     #[test]
     fn local_image_read_error_adds_placeholder() -> Result<()> {
         let dir = tempdir()?;
@@ -113,6 +113,20 @@ pub enum Op {
         call_id: String,
         /// Structured payload to send back to the model.
         output: FunctionCallOutputPayload,
+        /// Announce `output` as interim progress without resolving the call,
+        /// which keeps waiting until a delivery without this flag arrives.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        keep_pending: bool,
+    },
+
+    /// Buffer part of a pending tool call's output without resolving it. The
+    /// chunks are prepended, in order, to the content of the eventual
+    /// [`Op::DeliverPendingToolResult`].
+    AppendPendingToolOutput {
+        /// Identifier of the pending tool call.
+        call_id: String,
+        /// Text to append to the buffered output.
+        chunk: String,
     },
 
     /// Override parts of the persistent turn context for subsequent turns.
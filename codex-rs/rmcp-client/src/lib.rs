@@ -24,3 +24,4 @@ pub use rmcp_client::Elicitation;
 pub use rmcp_client::ElicitationResponse;
 pub use rmcp_client::RmcpClient;
 pub use rmcp_client::SendElicitation;
+pub use utils::McpServerError;
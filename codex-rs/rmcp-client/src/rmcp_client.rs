@@ -24,6 +24,7 @@ use mcp_types::ReadResourceRequestParams;
 use mcp_types::ReadResourceResult;
 use mcp_types::RequestId;
 use reqwest::header::HeaderMap;
+use rmcp::model::CallToolRequest;
 use rmcp::model::CallToolRequestParam;
 use rmcp::model::ClientNotification;
 use rmcp::model::ClientRequest;
@@ -33,6 +34,7 @@ use rmcp::model::CustomNotification;
 use rmcp::model::CustomRequest;
 use rmcp::model::Extensions;
 use rmcp::model::InitializeRequestParam;
+use rmcp::model::Meta;
 use rmcp::model::PaginatedRequestParam;
 use rmcp::model::ReadResourceRequestParam;
 use rmcp::model::ServerResult;
@@ -350,18 +352,29 @@ impl RmcpClient {
         Ok(converted)
     }
 
+    /// Call the tool `name`. A `meta` object is sent as the request's
+    /// `_meta` so the server can correlate the call with the caller's logs.
     pub async fn call_tool(
         &self,
         name: String,
         arguments: Option<serde_json::Value>,
+        meta: Option<serde_json::Map<String, serde_json::Value>>,
         timeout: Option<Duration>,
     ) -> Result<CallToolResult> {
         self.refresh_oauth_if_needed().await;
         let service = self.service().await?;
         let params = CallToolRequestParams { arguments, name };
         let rmcp_params: CallToolRequestParam = convert_to_rmcp(params)?;
-        let fut = service.call_tool(rmcp_params);
-        let rmcp_result = run_with_timeout(fut, timeout, "tools/call").await?;
+        let mut request = CallToolRequest::new(rmcp_params);
+        if let Some(meta) = meta {
+            request.extensions.insert(Meta(meta));
+        }
+        let fut = service.send_request(ClientRequest::CallToolRequest(request));
+        let ServerResult::CallToolResult(rmcp_result) =
+            run_with_timeout(fut, timeout, "tools/call").await?
+        else {
+            return Err(anyhow!("unexpected response to tools/call"));
+        };
         let converted = convert_call_tool_result(rmcp_result)?;
         self.persist_oauth_tokens().await;
         Ok(converted)
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::env;
+use std::fmt;
 use std::time::Duration;
 
 use anyhow::Context;
@@ -12,9 +13,41 @@ use reqwest::header::HeaderName;
 use reqwest::header::HeaderValue;
 use rmcp::model::CallToolResult as RmcpCallToolResult;
 use rmcp::service::ServiceError;
+use serde::Serialize;
 use serde_json::Value;
 use tokio::time;
 
+/// JSON-RPC error returned by an MCP server. Kept as a typed error inside the
+/// `anyhow::Error` chain so callers can downcast to it and surface the code,
+/// message, and data instead of a flattened string.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct McpServerError {
+    pub code: i32,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl fmt::Display for McpServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MCP error {}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for McpServerError {}
+
+fn service_error(label: &str, err: ServiceError) -> anyhow::Error {
+    match err {
+        ServiceError::McpError(error) => anyhow::Error::new(McpServerError {
+            code: error.code.0,
+            message: error.message.into_owned(),
+            data: error.data,
+        })
+        .context(format!("{label} failed")),
+        err => anyhow!("{label} failed: {err}"),
+    }
+}
+
 pub(crate) async fn run_with_timeout<F, T>(
     fut: F,
     timeout: Option<Duration>,
@@ -27,9 +60,9 @@ where
         let result = time::timeout(duration, fut)
             .await
             .with_context(|| anyhow!("timed out awaiting {label} after {duration:?}"))?;
-        result.map_err(|err| anyhow!("{label} failed: {err}"))
+        result.map_err(|err| service_error(label, err))
     } else {
-        fut.await.map_err(|err| anyhow!("{label} failed: {err}"))
+        fut.await.map_err(|err| service_error(label, err))
     }
 }
 
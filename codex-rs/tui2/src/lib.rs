@@ -15,10 +15,12 @@ use codex_core::INTERACTIVE_SESSION_SOURCES;
 use codex_core::RolloutRecorder;
 use codex_core::auth::enforce_login_restrictions;
 use codex_core::config::Config;
+use codex_core::config::ConfigBuilder;
 use codex_core::config::ConfigOverrides;
 use codex_core::config::find_codex_home;
 use codex_core::config::load_config_as_toml_with_cli_overrides;
 use codex_core::config::resolve_oss_provider;
+use codex_core::config_loader::LoaderOverrides;
 use codex_core::find_conversation_path_by_selector_str;
 use codex_core::get_platform_sandbox;
 use codex_core::protocol::AskForApproval;
@@ -138,7 +140,10 @@ pub async fn run_main(
     // gpt-oss:20b) and ensure it is present locally. Also, force the built‑in
     // `oss` model provider.
     let overrides_cli = cli.config_overrides.clone();
-    let cli_kv_overrides = match overrides_cli.parse_overrides() {
+    let (cli_kv_overrides, loader_overrides) = match overrides_cli
+        .parse_overrides()
+        .and_then(|kv| Ok((kv, overrides_cli.loader_overrides()?)))
+    {
         // Parse `-c` overrides from the CLI.
         Ok(v) => v,
         #[allow(clippy::print_stderr)]
@@ -169,6 +174,7 @@ pub async fn run_main(
         &codex_home,
         &config_cwd,
         cli_kv_overrides.clone(),
+        loader_overrides.clone(),
     )
     .await
     {
@@ -235,7 +241,20 @@ pub async fn run_main(
         additional_writable_roots: additional_dirs,
     };
 
-    let config = load_config_or_exit(cli_kv_overrides.clone(), overrides.clone()).await;
+    let config = load_config_or_exit(
+        cli_kv_overrides.clone(),
+        overrides.clone(),
+        loader_overrides.clone(),
+    )
+    .await;
+
+    if let Err(err) = overrides_cli.write_config_dump(&config) {
+        #[allow(clippy::print_stderr)]
+        {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
 
     if let Some(warning) = add_dir_warning_message(&cli.add_dir, config.sandbox_policy.get()) {
         #[allow(clippy::print_stderr)]
@@ -340,6 +359,7 @@ pub async fn run_main(
         config,
         overrides,
         cli_kv_overrides,
+        loader_overrides,
         active_profile,
         feedback,
     )
@@ -352,6 +372,7 @@ async fn run_ratatui_app(
     initial_config: Config,
     overrides: ConfigOverrides,
     cli_kv_overrides: Vec<(String, toml::Value)>,
+    loader_overrides: LoaderOverrides,
     active_profile: Option<String>,
     feedback: codex_feedback::CodexFeedback,
 ) -> color_eyre::Result<AppExitInfo> {
@@ -434,7 +455,7 @@ async fn run_ratatui_app(
             .map(|d| d == TrustDirectorySelection::Trust)
             .unwrap_or(false)
         {
-            load_config_or_exit(cli_kv_overrides, overrides).await
+            load_config_or_exit(cli_kv_overrides, overrides, loader_overrides).await
         } else {
             initial_config
         }
@@ -586,9 +607,16 @@ fn get_login_status(config: &Config) -> LoginStatus {
 async fn load_config_or_exit(
     cli_kv_overrides: Vec<(String, toml::Value)>,
     overrides: ConfigOverrides,
+    loader_overrides: LoaderOverrides,
 ) -> Config {
+    let config = ConfigBuilder::default()
+        .cli_overrides(cli_kv_overrides)
+        .harness_overrides(overrides)
+        .loader_overrides(loader_overrides)
+        .build()
+        .await;
     #[allow(clippy::print_stderr)]
-    match Config::load_with_cli_overrides_and_harness_overrides(cli_kv_overrides, overrides).await {
+    match config {
         Ok(config) => config,
         Err(err) => {
             eprintln!("Error loading configuration: {err}");
@@ -638,7 +666,6 @@ fn should_show_login_screen(login_status: LoginStatus, config: &Config) -> bool
 #[cfg(test)]
 mod tests {
     use super::*;
-    use codex_core::config::ConfigBuilder;
     use codex_core::config::ProjectConfig;
     use serial_test::serial;
     use tempfile::TempDir;